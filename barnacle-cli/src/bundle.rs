@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use barnacle_lib::{Repository, repository::BundleContents};
+use clap::Subcommand;
+use colored::Colorize;
+use sysexits::ExitCode;
+
+use crate::dry_run;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Export the database, configuration and library to a bundle
+    Export {
+        out: PathBuf,
+        /// Leave out the files of mods, for sharing a setup rather than backing it up
+        #[arg(long)]
+        metadata_only: bool,
+    },
+    /// Replace the database and configuration with the contents of a bundle
+    Import {
+        bundle: PathBuf,
+        /// Empty directory to restore the library into
+        library_dir: PathBuf,
+    },
+}
+
+pub fn export(repo: &Repository, out: &Path, metadata_only: bool, dry_run: bool) {
+    if dry_run {
+        dry_run::would(&format!("export the library to {}", out.display()));
+        return;
+    }
+
+    let contents = if metadata_only {
+        BundleContents::MetadataOnly
+    } else {
+        BundleContents::Full
+    };
+
+    if let Err(err) = repo.export_bundle(out, contents) {
+        eprintln!("Failed to export bundle: {err}");
+        ExitCode::Software.exit()
+    }
+
+    println!("{}", format!("Exported to {}", out.display()).green());
+}
+
+/// Importing replaces the database, so this has to run before it's opened
+pub fn import(bundle: &Path, library_dir: &Path, dry_run: bool) {
+    if dry_run {
+        dry_run::would(&format!(
+            "replace the database with {} and restore the library into {}",
+            bundle.display(),
+            library_dir.display()
+        ));
+        return;
+    }
+
+    if let Err(err) = Repository::import_bundle(bundle, library_dir) {
+        eprintln!("Failed to import bundle: {err}");
+        ExitCode::DataErr.exit()
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Imported {} into {}",
+            bundle.display(),
+            library_dir.display()
+        )
+        .green()
+    );
+}
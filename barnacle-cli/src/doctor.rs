@@ -0,0 +1,92 @@
+use barnacle_lib::Repository;
+use clap::Args;
+use colored::Colorize;
+use sysexits::ExitCode;
+
+use crate::dry_run;
+
+#[derive(Args, Debug, Clone)]
+pub struct Command {
+    /// Clean up the problems that were found
+    #[arg(long)]
+    fix: bool,
+    /// Compact the database file, after backing it up
+    #[arg(long)]
+    compact: bool,
+}
+
+pub fn handle(repo: &Repository, cmd: &Command, dry_run: bool) {
+    check_orphans(repo, cmd, dry_run);
+
+    if cmd.compact {
+        compact(repo, dry_run);
+    }
+}
+
+fn check_orphans(repo: &Repository, cmd: &Command, dry_run: bool) {
+    let orphans = match repo.orphaned_mods() {
+        Ok(orphans) => orphans,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::Software.exit()
+        }
+    };
+
+    if orphans.is_empty() {
+        println!("{}", "No problems found".green());
+        return;
+    }
+
+    println!("Mods that no longer belong to a game:");
+    for orphan in &orphans {
+        println!("* {}", orphan.name().unwrap());
+    }
+
+    if !cmd.fix {
+        println!(
+            "{}",
+            "Run `barnacle doctor --fix` to remove them and their files".yellow()
+        );
+        return;
+    }
+
+    if dry_run {
+        dry_run::would(&format!("remove {} orphaned mods", orphans.len()));
+        return;
+    }
+
+    match repo.remove_orphaned_mods() {
+        Ok(removed) => println!(
+            "{}",
+            format!("Removed {} orphaned mods", removed.len()).green()
+        ),
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::Software.exit()
+        }
+    }
+}
+
+fn compact(repo: &Repository, dry_run: bool) {
+    if dry_run {
+        dry_run::would("back up and compact the database");
+        return;
+    }
+
+    match repo.compact() {
+        Ok(compaction) => println!(
+            "{}",
+            format!(
+                "Compacted the database from {} to {} bytes, saving {}",
+                compaction.before,
+                compaction.after,
+                compaction.saved()
+            )
+            .green()
+        ),
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::Software.exit()
+        }
+    }
+}
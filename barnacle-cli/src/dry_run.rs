@@ -0,0 +1,16 @@
+//! Reporting what mutating commands would do when run with `--dry-run`.
+
+use colored::Colorize;
+
+/// Print that `action` would be performed
+pub fn would(action: &str) {
+    println!("{} {action}", "Would".yellow());
+}
+
+/// Print that `command` can't be previewed, so nothing was done
+pub fn unsupported(command: &str) {
+    println!(
+        "{}",
+        format!("`{command}` can't be previewed, nothing was changed").yellow()
+    );
+}
@@ -1,17 +1,52 @@
-use barnacle_lib::{Repository, repository::DeployKind};
+use std::path::PathBuf;
+
+use barnacle_lib::{
+    Repository,
+    repository::{DeployKind, Game, LinkStrategy},
+};
 use clap::Subcommand;
+use sysexits::ExitCode;
+
+use crate::dry_run;
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
     /// List games
     List,
     /// Add a new game
-    Add { name: String },
+    Add {
+        name: String,
+        /// How mods are deployed to the game, such as `overlay` or `creation-engine`
+        #[arg(long, default_value_t = DeployKind::Overlay)]
+        deploy_kind: DeployKind,
+    },
+    /// Search the Steam and GOG libraries and common install directories for known games that
+    /// aren't in the library yet
+    Scan {
+        /// Add every game that's found, targeting its data directory
+        #[arg(long)]
+        add: bool,
+    },
     /// Activate the given game
     Activate { name: String },
+    /// Deploy the given game with hard links rather than symlinks, for games that don't follow
+    /// symlinks properly
+    Hardlinks {
+        name: String,
+        /// Go back to deploying with symlinks
+        #[arg(long)]
+        off: bool,
+    },
+    /// Set the directory the given game reads its plugins.txt from, such as its folder under
+    /// AppData/Local in the Proton prefix. Deploying writes the plugin order there.
+    PluginsDir {
+        name: String,
+        /// Leave out to keep the plugin order in the profile's directory instead
+        dir: Option<PathBuf>,
+    },
 }
 
-pub fn handle(repo: &Repository, cmd: &Command) {
+pub fn handle(repo: &Repository, cmd: &Command, dry_run: bool) {
     match cmd {
         Command::List => {
             let games = repo.games().unwrap();
@@ -19,12 +54,87 @@ pub fn handle(repo: &Repository, cmd: &Command) {
                 println!("{}", game.name().unwrap());
             }
         }
-        Command::Add { name } => {
-            repo.add_game(name, DeployKind::Overlay).unwrap();
+        Command::Add { name, deploy_kind } => {
+            if dry_run {
+                dry_run::would(&format!("add {deploy_kind} game {name}"));
+                return;
+            }
+            repo.add_game(name, *deploy_kind).unwrap();
+        }
+        Command::Scan { add } => {
+            let detected = repo.scan_for_games().unwrap();
+            if detected.is_empty() {
+                println!("No new games found");
+                return;
+            }
+            for game in &detected {
+                println!(
+                    "{} ({}) at {}",
+                    game.name,
+                    game.deploy_kind,
+                    game.path.display()
+                );
+            }
+            if !add {
+                return;
+            }
+            for game in &detected {
+                if dry_run {
+                    dry_run::would(&format!("add {} game {}", game.deploy_kind, game.name));
+                    continue;
+                }
+                if let Err(err) = repo.add_detected_game(game) {
+                    eprintln!("Failed to add {}: {err}", game.name);
+                }
+            }
         }
         Command::Activate { name } => {
-            let game = repo.search_game(name).unwrap().expect("game not found");
+            let game = find_game(repo, name);
+            if dry_run {
+                dry_run::would(&format!("activate game {name}"));
+                return;
+            }
             game.activate().unwrap();
         }
+        Command::Hardlinks { name, off } => {
+            let game = find_game(repo, name);
+            let (strategy, links) = if *off {
+                (LinkStrategy::Symlink, "symlinks")
+            } else {
+                (LinkStrategy::Hardlink, "hard links")
+            };
+            if dry_run {
+                dry_run::would(&format!("deploy {name} with {links}"));
+                return;
+            }
+            if let Err(err) = game.set_link_strategy(strategy) {
+                eprintln!("Failed to change how {name} is deployed: {err}");
+                ExitCode::Usage.exit()
+            }
+        }
+        Command::PluginsDir { name, dir } => {
+            let game = find_game(repo, name);
+            if dry_run {
+                match dir {
+                    Some(dir) => dry_run::would(&format!(
+                        "write the plugin order of {name} to {}",
+                        dir.display()
+                    )),
+                    None => dry_run::would(&format!("unset the plugins directory of {name}")),
+                }
+                return;
+            }
+            game.set_plugins_dir(dir.as_deref()).unwrap();
+        }
+    }
+}
+
+fn find_game(repo: &Repository, name: &str) -> Game {
+    match repo.search_game(name).unwrap() {
+        Some(game) => game,
+        None => {
+            eprintln!("Game not found: {name}");
+            ExitCode::Usage.exit()
+        }
     }
 }
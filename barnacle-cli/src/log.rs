@@ -0,0 +1,32 @@
+use std::process;
+
+use barnacle_lib::logging::{current_log_file, log_dir};
+use clap::Args;
+use sysexits::ExitCode;
+
+#[derive(Args, Debug, Clone)]
+pub struct Command {
+    /// Open the log file with the default application instead of printing its path
+    #[arg(long)]
+    open: bool,
+}
+
+pub fn handle(cmd: &Command) {
+    let Some(path) = current_log_file() else {
+        match log_dir() {
+            Ok(dir) => eprintln!("No log file in {}", dir.display()),
+            Err(err) => eprintln!("No log directory: {err}"),
+        }
+        ExitCode::Unavailable.exit()
+    };
+
+    if !cmd.open {
+        println!("{}", path.display());
+        return;
+    }
+
+    if let Err(err) = process::Command::new("xdg-open").arg(&path).status() {
+        eprintln!("Failed to open {}: {err}", path.display());
+        ExitCode::Unavailable.exit()
+    }
+}
@@ -1,12 +1,20 @@
-use barnacle_lib::Repository;
-use clap::{Parser, Subcommand};
+use barnacle_lib::{Repository, logging};
+use clap::{ArgAction, Parser, Subcommand};
 use colored::Colorize;
-use tracing::Level;
-use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use progress::ProgressFormat;
+use sysexits::ExitCode;
+use tracing::level_filters::LevelFilter;
 
+mod bundle;
+mod doctor;
+mod dry_run;
 mod game;
+mod log;
+mod migrate;
 mod mod_;
 mod profile;
+mod progress;
+mod relocate;
 
 #[derive(Parser, Debug)]
 #[command(name = "barnacle")]
@@ -22,6 +30,38 @@ struct Cli {
     /// Override the active profile
     #[arg(short, long, global = true)]
     profile: Option<String>,
+
+    /// Increase logging verbosity (-v for info, -vv for debug, -vvv for trace)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print what mutating commands would do without changing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// How to report progress during long operations
+    #[arg(long, global = true, value_enum, default_value_t)]
+    progress: ProgressFormat,
+}
+
+impl Cli {
+    /// The maximum level to log at. `RUST_LOG` takes precedence over this if it's set.
+    fn log_level(&self) -> LevelFilter {
+        if self.quiet {
+            return LevelFilter::ERROR;
+        }
+
+        match self.verbose {
+            0 => LevelFilter::WARN,
+            1 => LevelFilter::INFO,
+            2 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -35,25 +75,79 @@ enum Command {
     /// Operate on mods
     #[command(subcommand)]
     Mod(mod_::Command),
+    /// Inspect or perform database migrations
+    Migrate(migrate::Command),
+    /// Rewrite stored paths after moving the library to a new location
+    Relocate(relocate::Command),
+    /// Export or import the whole repository
+    #[command(subcommand)]
+    Bundle(bundle::Command),
+    /// Check the library for problems left behind by interrupted operations
+    Doctor(doctor::Command),
+    /// Print the path of the current log file
+    Log(log::Command),
 }
 
 fn main() {
     human_panic::setup_panic!();
 
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::TRACE)
-        .with_env_filter(EnvFilter::from_default_env())
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
-    let repo = Repository::new();
     let cli = Cli::parse();
 
+    // Kept alive so the log file is flushed on exit
+    let _log_guard = logging::init(cli.log_level());
+
+    if let Some(Command::Bundle(bundle::Command::Import {
+        bundle,
+        library_dir,
+    })) = &cli.command
+    {
+        bundle::import(bundle, library_dir, cli.dry_run);
+        return;
+    }
+
+    if let Some(Command::Log(cmd)) = &cli.command {
+        log::handle(cmd);
+        return;
+    }
+
+    // Migrations are performed explicitly by the migrate command, and never on a dry run
+    let migrating = matches!(cli.command, Some(Command::Migrate(_)));
+    let repo = if cli.dry_run {
+        Repository::for_dry_run()
+    } else if migrating {
+        Repository::without_migrations()
+    } else {
+        Repository::new()
+    };
+    let repo = match repo {
+        Ok(repo) => repo,
+        Err(err) => {
+            eprintln!("Failed to open the library: {err}");
+            ExitCode::IoErr.exit()
+        }
+    };
+
+    if cli.dry_run && !migrating && repo.needs_migration() {
+        eprintln!(
+            "The library needs migrating before anything can be previewed, run `barnacle migrate --run` first"
+        );
+        ExitCode::DataErr.exit()
+    }
+
     match &cli.command {
         Some(cmd) => match cmd {
-            Command::Game(cmd) => game::handle(&repo, cmd),
-            Command::Profile(cmd) => profile::handle(&repo, cmd),
-            Command::Mod(cmd) => mod_::handle(&repo, cmd),
+            Command::Game(cmd) => game::handle(&repo, cmd, cli.dry_run),
+            Command::Profile(cmd) => profile::handle(&repo, cmd, cli.dry_run),
+            Command::Mod(cmd) => mod_::handle(&repo, cmd, cli.progress, cli.dry_run),
+            Command::Migrate(cmd) => migrate::handle(&repo, cmd, cli.dry_run),
+            Command::Relocate(cmd) => relocate::handle(&repo, cmd, cli.dry_run),
+            Command::Doctor(cmd) => doctor::handle(&repo, cmd, cli.dry_run),
+            Command::Bundle(bundle::Command::Export { out, metadata_only }) => {
+                bundle::export(&repo, out, *metadata_only, cli.dry_run)
+            }
+            Command::Bundle(bundle::Command::Import { .. }) | Command::Log(_) => {
+                unreachable!("handled above")
+            }
         },
         None => status(&repo),
     }
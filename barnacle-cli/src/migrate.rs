@@ -0,0 +1,41 @@
+use barnacle_lib::Repository;
+use clap::Args;
+use colored::Colorize;
+
+use crate::dry_run;
+
+#[derive(Args, Debug, Clone)]
+pub struct Command {
+    /// Back up the database and perform any pending migrations
+    #[arg(long, conflicts_with = "status")]
+    run: bool,
+    /// Print the current and target model versions (default)
+    #[arg(long)]
+    status: bool,
+}
+
+pub fn handle(repo: &Repository, cmd: &Command, dry_run: bool) {
+    let target = repo.target_model_version();
+    let current = match repo.model_version() {
+        Some(version) => version.to_string(),
+        None => "None".into(),
+    };
+
+    println!("Current model version: {current}");
+    println!("Target model version: {target}");
+
+    if cmd.run && dry_run {
+        dry_run::unsupported("migrate --run");
+    } else if cmd.run {
+        if repo.migrate().unwrap() {
+            println!("{}", "Migration complete".green());
+        } else {
+            println!("Already up to date");
+        }
+    } else if repo.model_version().is_some_and(|v| v < target) {
+        println!(
+            "{}",
+            "Migration pending, run `barnacle migrate --run` to apply it".yellow()
+        );
+    }
+}
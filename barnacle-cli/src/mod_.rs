@@ -1,15 +1,55 @@
-use std::path::Path;
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
 
 use barnacle_lib::Repository;
 use clap::Subcommand;
 use sysexits::ExitCode;
 
+use crate::{dry_run, progress::ProgressFormat};
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
     /// List profiles
     List,
     /// Add a new profile
     Add { name: String, path: Option<String> },
+    /// Remove a mod and its files
+    Remove {
+        name: String,
+        /// Remove the mod even if profiles use it, dropping it from them
+        #[arg(long)]
+        force: bool,
+    },
+    /// Record that a mod requires another mod
+    Require { name: String, requirement: String },
+    /// Keep a mod's files writable so they can be edited in place, even though installed mods
+    /// are made read-only
+    Editable {
+        name: String,
+        /// Make the mod's files read-only again
+        #[arg(long)]
+        off: bool,
+    },
+    /// List the enabled mods that ship a file, the deployed one last
+    Which { path: String },
+    /// List mods whose names only differ by version, which may be merged with `barnacle mod merge`
+    SuggestMerges,
+    /// Merge a mod into another version of it, so profiles using it use the kept one instead
+    Merge {
+        /// The mod to keep
+        keep: String,
+        /// The mod to merge into the kept one and remove
+        drop: String,
+    },
+    /// Reorder mods to follow a file of mod names, one per line, such as a LOOT export
+    Reorder {
+        /// The file to read the order from, or `-` for stdin
+        #[arg(long)]
+        from: String,
+    },
 }
 
 pub struct ModRow {
@@ -17,22 +57,222 @@ pub struct ModRow {
     enabled: bool,
 }
 
-pub fn handle(repo: &Repository, cmd: &Command) {
+pub fn handle(repo: &Repository, cmd: &Command, progress: ProgressFormat, dry_run: bool) {
     if let Some(active_game) = repo.active_game().unwrap() {
         if let Some(active_profile) = active_game.active_profile().unwrap() {
             match cmd {
                 Command::List => {
                     let mods = active_profile.mod_entries().unwrap();
+                    if mods.is_empty() {
+                        println!("No mods in this profile, add one with `barnacle mod add`");
+                    }
                     for mod_ in mods {
                         println!("* {}", mod_.name().unwrap());
                     }
                 }
                 Command::Add { name, path } => {
+                    if dry_run {
+                        match path {
+                            Some(path) => dry_run::would(&format!("install {name} from {path}")),
+                            None => dry_run::would(&format!("add empty mod {name}")),
+                        }
+                        return;
+                    }
                     let mod_ = active_game
-                        .add_mod(name, path.as_deref().map(Path::new))
+                        .add_mod_with_progress(
+                            name,
+                            path.as_deref().map(Path::new),
+                            &mut progress.reporter(),
+                        )
                         .unwrap();
                     active_profile.add_mod_entry(mod_).unwrap();
                 }
+                Command::Which { path } => {
+                    let providers = active_profile.providers_of(Path::new(path)).unwrap();
+                    let Some((winner, others)) = providers.split_last() else {
+                        eprintln!("No enabled mod provides {path}");
+                        ExitCode::DataErr.exit()
+                    };
+
+                    for mod_ in others {
+                        println!("* {}", mod_.name().unwrap());
+                    }
+                    println!("* {} (deployed)", winner.name().unwrap());
+                }
+                Command::Reorder { from } => {
+                    let contents = read_order(from).unwrap_or_else(|err| {
+                        eprintln!("Failed to read {from}: {err}");
+                        ExitCode::NoInput.exit()
+                    });
+                    let order: Vec<String> = contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(String::from)
+                        .collect();
+
+                    if dry_run {
+                        match active_profile.load_order_plan(&order) {
+                            Ok(plan) => {
+                                for name in plan.unmatched {
+                                    eprintln!("No mod named {name}");
+                                }
+                                let names: Vec<String> =
+                                    plan.entries.iter().map(|e| e.name().unwrap()).collect();
+                                dry_run::would(&format!("reorder mods to {}", names.join(", ")));
+                            }
+                            Err(err) => {
+                                eprintln!("Failed to reorder mods: {err}");
+                                ExitCode::DataErr.exit()
+                            }
+                        }
+                        return;
+                    }
+
+                    match active_profile.apply_load_order(&order) {
+                        Ok(unmatched) => {
+                            for name in unmatched {
+                                eprintln!("No mod named {name}");
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to reorder mods: {err}");
+                            ExitCode::DataErr.exit()
+                        }
+                    }
+                }
+                Command::Remove { name, force } => {
+                    let mods = active_game.mods().unwrap();
+                    let Some(mod_) = mods.into_iter().find(|m| m.name().unwrap() == *name) else {
+                        eprintln!("Mod not found: {name}");
+                        ExitCode::Usage.exit()
+                    };
+
+                    let used_by = mod_.used_by().unwrap();
+                    if !used_by.is_empty() {
+                        let names: Vec<String> =
+                            used_by.iter().map(|p| p.name().unwrap()).collect();
+                        eprintln!(
+                            "{name} is used by {} profiles: {}",
+                            used_by.len(),
+                            names.join(", ")
+                        );
+                        if !force {
+                            eprintln!("Pass --force to remove it from them as well");
+                            ExitCode::Usage.exit()
+                        }
+                    }
+                    if dry_run {
+                        dry_run::would(&format!("remove mod {name}"));
+                        return;
+                    }
+
+                    for profile in used_by {
+                        for entry in profile.mod_entries().unwrap() {
+                            if entry.mod_().unwrap() == mod_
+                                && let Err(err) = profile.remove_mod_entry(entry)
+                            {
+                                eprintln!(
+                                    "Failed to remove {name} from {}: {err}",
+                                    profile.name().unwrap()
+                                );
+                                ExitCode::Software.exit()
+                            }
+                        }
+                    }
+                    if let Err(err) = mod_.remove() {
+                        eprintln!("Failed to remove {name}: {err}");
+                        ExitCode::Software.exit()
+                    }
+                }
+                Command::SuggestMerges => {
+                    let groups: Vec<_> = repo
+                        .suggest_merges()
+                        .unwrap()
+                        .into_iter()
+                        .filter(|group| {
+                            group
+                                .first()
+                                .is_some_and(|m| m.parent().unwrap() == active_game)
+                        })
+                        .collect();
+                    if groups.is_empty() {
+                        println!("No mods look like versions of each other");
+                    }
+                    for group in groups {
+                        let names: Vec<String> = group.iter().map(|m| m.name().unwrap()).collect();
+                        println!("* {}", names.join(", "));
+                    }
+                }
+                Command::Merge { keep, drop } => {
+                    let mods = active_game.mods().unwrap();
+                    let find = |name: &str| {
+                        mods.iter()
+                            .find(|m| m.name().unwrap() == name)
+                            .cloned()
+                            .unwrap_or_else(|| {
+                                eprintln!("Mod not found: {name}");
+                                ExitCode::Usage.exit()
+                            })
+                    };
+
+                    let (keep_mod, drop_mod) = (find(keep), find(drop));
+                    if dry_run {
+                        let used_by = drop_mod.used_by().unwrap();
+                        dry_run::would(&format!(
+                            "merge {drop} into {keep} in {} profiles and remove {drop}",
+                            used_by.len()
+                        ));
+                        return;
+                    }
+
+                    if let Err(err) = repo.merge_mods(&keep_mod, drop_mod) {
+                        eprintln!("Failed to merge {drop} into {keep}: {err}");
+                        ExitCode::DataErr.exit()
+                    }
+                }
+                Command::Editable { name, off } => {
+                    let mods = active_game.mods().unwrap();
+                    let Some(mod_) = mods.iter().find(|m| m.name().unwrap() == *name) else {
+                        eprintln!("Mod not found: {name}");
+                        ExitCode::Usage.exit()
+                    };
+                    if dry_run {
+                        if *off {
+                            dry_run::would(&format!("make the files of {name} read-only"));
+                        } else {
+                            dry_run::would(&format!("make the files of {name} writable"));
+                        }
+                        return;
+                    }
+
+                    if let Err(err) = mod_.set_editable(!off) {
+                        eprintln!("Failed to change whether {name} is editable: {err}");
+                        ExitCode::Software.exit()
+                    }
+                }
+                Command::Require { name, requirement } => {
+                    let mods = active_game.mods().unwrap();
+                    let find = |name: &str| {
+                        mods.iter()
+                            .find(|m| m.name().unwrap() == name)
+                            .unwrap_or_else(|| {
+                                eprintln!("Mod not found: {name}");
+                                ExitCode::Usage.exit()
+                            })
+                    };
+
+                    let (mod_, requirement_mod) = (find(name), find(requirement));
+                    if dry_run {
+                        dry_run::would(&format!("record that {name} requires {requirement}"));
+                        return;
+                    }
+
+                    if let Err(err) = mod_.add_requirement(requirement_mod) {
+                        eprintln!("Failed to add requirement: {err}");
+                        ExitCode::DataErr.exit()
+                    }
+                }
             }
         } else {
             eprintln!("No active profile");
@@ -43,3 +283,14 @@ pub fn handle(repo: &Repository, cmd: &Command) {
         ExitCode::Usage.exit()
     }
 }
+
+/// Read the whole order file, or stdin if `from` is `-`
+fn read_order(from: &str) -> io::Result<String> {
+    if from == "-" {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        Ok(contents)
+    } else {
+        fs::read_to_string(from)
+    }
+}
@@ -1,7 +1,14 @@
-use barnacle_lib::Repository;
+use std::path::PathBuf;
+
+use barnacle_lib::{
+    Repository,
+    repository::{Game, Profile, entities},
+};
 use clap::Subcommand;
 use sysexits::ExitCode;
 
+use crate::dry_run;
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
     /// List profiles
@@ -10,9 +17,36 @@ pub enum Command {
     Add { name: String },
     /// Activate the given profile
     Activate { name: String },
+    /// Clear the active profile
+    Deactivate,
+    /// Deploy the active profile, only updating what changed since the last deployment
+    Deploy {
+        /// Tear down the existing deployment and deploy from scratch
+        #[arg(long)]
+        full: bool,
+    },
+    /// Undeploy the active profile
+    Undeploy,
+    /// Print the commands that mount the active profile's overlays, for games deployed with
+    /// OverlayFS, to run with the needed privileges, for example through `pkexec`
+    DeployCommand,
+    /// Make the active profile inherit the enabled mods of another, or stop inheriting if no
+    /// base is given
+    Inherit { base: Option<String> },
+    /// List the enabled mods of the active profile that contribute nothing, since every file
+    /// they ship is provided by another mod
+    Redundant {
+        /// Disable them, except for locked mods and mods other enabled mods require
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Write the active profile's plugin load order to a plugins.txt style file
+    ExportPlugins { path: PathBuf },
+    /// Replace the active profile's plugin load order with the one in a plugins.txt style file
+    ImportPlugins { path: PathBuf },
 }
 
-pub fn handle(repo: &Repository, cmd: &Command) {
+pub fn handle(repo: &Repository, cmd: &Command, dry_run: bool) {
     if let Some(active_game) = repo.active_game().unwrap() {
         match cmd {
             Command::List => {
@@ -22,18 +56,206 @@ pub fn handle(repo: &Repository, cmd: &Command) {
                 }
             }
             Command::Add { name } => {
+                if dry_run {
+                    dry_run::would(&format!("add profile {name}"));
+                    return;
+                }
                 active_game.add_profile(name).unwrap();
             }
             Command::Activate { name } => {
-                let profile = active_game
-                    .search_profile(name)
-                    .unwrap()
-                    .expect("profile not found");
+                let profile = find_profile(&active_game, name);
+                if dry_run {
+                    dry_run::would(&format!("activate profile {name}"));
+                    return;
+                }
                 profile.activate().unwrap();
             }
+            Command::Deactivate => {
+                if dry_run {
+                    dry_run::would("clear the active profile");
+                    return;
+                }
+                active_game.clear_active_profile().unwrap();
+            }
+            Command::Deploy { full } => {
+                let profile = active_profile(&active_game);
+                if dry_run {
+                    preview_deploy(&profile);
+                    return;
+                }
+                let result = if *full {
+                    profile.redeploy()
+                } else {
+                    profile.deploy()
+                };
+
+                if let Err(err) = result {
+                    eprintln!("Failed to deploy: {err}");
+                    if matches!(err, entities::Error::MountFailed(..)) {
+                        eprintln!(
+                            "Mounting needs privileges, see `barnacle profile deploy-command` \
+                             for the commands to run yourself"
+                        );
+                    }
+                    ExitCode::Software.exit()
+                }
+            }
+            Command::DeployCommand => {
+                let profile = active_profile(&active_game);
+                match profile.deploy_command() {
+                    Ok(commands) if commands.is_empty() => {
+                        println!("This game is deployed with links, there's nothing to mount");
+                    }
+                    Ok(commands) => {
+                        for command in commands {
+                            println!("{command}");
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to work out the mount commands: {err}");
+                        ExitCode::Software.exit()
+                    }
+                }
+            }
+            Command::Undeploy => {
+                let profile = active_profile(&active_game);
+                if dry_run {
+                    dry_run::would(&format!("undeploy profile {}", profile.name().unwrap()));
+                    return;
+                }
+                profile.undeploy().unwrap();
+            }
+            Command::Inherit { base } => {
+                let profile = active_profile(&active_game);
+                let base = base.as_ref().map(|name| find_profile(&active_game, name));
+                if dry_run {
+                    match &base {
+                        Some(base) => dry_run::would(&format!(
+                            "make profile {} inherit from {}",
+                            profile.name().unwrap(),
+                            base.name().unwrap()
+                        )),
+                        None => dry_run::would(&format!(
+                            "stop profile {} from inheriting",
+                            profile.name().unwrap()
+                        )),
+                    }
+                    return;
+                }
+                if let Err(err) = profile.set_base(base.as_ref()) {
+                    eprintln!("Failed to set the base profile: {err}");
+                    ExitCode::Usage.exit()
+                }
+            }
+            Command::Redundant { disable } => {
+                let profile = active_profile(&active_game);
+                let redundant = profile.redundant_mods().unwrap();
+                if redundant.is_empty() {
+                    println!("Every enabled mod provides at least one file");
+                    return;
+                }
+                if !disable {
+                    for mod_ in &redundant {
+                        println!("* {}", mod_.name().unwrap());
+                    }
+                    return;
+                }
+                if dry_run {
+                    dry_run::would(&format!("disable up to {} redundant mods", redundant.len()));
+                    return;
+                }
+
+                match profile.disable_redundant_mods() {
+                    Ok(disabled) => {
+                        for mod_ in &disabled {
+                            println!("Disabled {}", mod_.name().unwrap());
+                        }
+                        for mod_ in redundant.iter().filter(|m| !disabled.contains(m)) {
+                            println!("Kept {}, it's locked or required", mod_.name().unwrap());
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to disable redundant mods: {err}");
+                        ExitCode::Software.exit()
+                    }
+                }
+            }
+            Command::ExportPlugins { path } => {
+                if dry_run {
+                    dry_run::would(&format!(
+                        "export the plugin load order to {}",
+                        path.display()
+                    ));
+                    return;
+                }
+                if let Err(err) = active_game.export_plugin_load_order(path) {
+                    eprintln!("Failed to export the plugin load order: {err}");
+                    ExitCode::Software.exit()
+                }
+            }
+            Command::ImportPlugins { path } => {
+                if dry_run {
+                    dry_run::would(&format!(
+                        "import the plugin load order from {}",
+                        path.display()
+                    ));
+                    return;
+                }
+                match active_game.import_plugin_load_order(path) {
+                    Ok(unknown) => {
+                        for name in unknown {
+                            println!("Skipped unknown plugin {name}");
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to import the plugin load order: {err}");
+                        ExitCode::Software.exit()
+                    }
+                }
+            }
         }
     } else {
         println!("No active game");
         ExitCode::Usage.exit()
     }
 }
+
+/// List the files deploying `profile` would link, along with the mod each comes from
+fn preview_deploy(profile: &Profile) {
+    let plan = match profile.deploy_plan() {
+        Ok(plan) => plan,
+        Err(err) => {
+            eprintln!("Failed to plan deployment: {err}");
+            ExitCode::Software.exit()
+        }
+    };
+
+    for (path, entry) in &plan {
+        println!("* {} ({})", path.display(), entry.name().unwrap());
+    }
+    dry_run::would(&format!(
+        "deploy {} files of profile {}",
+        plan.len(),
+        profile.name().unwrap()
+    ));
+}
+
+fn active_profile(game: &Game) -> Profile {
+    match game.active_profile().unwrap() {
+        Some(profile) => profile,
+        None => {
+            eprintln!("No active profile");
+            ExitCode::Usage.exit()
+        }
+    }
+}
+
+fn find_profile(game: &Game, name: &str) -> Profile {
+    match game.search_profile(name).unwrap() {
+        Some(profile) => profile,
+        None => {
+            eprintln!("Profile not found: {name}");
+            ExitCode::Usage.exit()
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::{self, IsTerminal, Write};
+
+use barnacle_lib::progress::Progress;
+use clap::ValueEnum;
+use tracing::warn;
+
+const BAR_WIDTH: u64 = 30;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// A progress bar on stderr, shown only when stderr is a terminal
+    #[default]
+    Human,
+    /// One JSON object per update on stdout
+    Json,
+}
+
+impl ProgressFormat {
+    /// Returns a callback that renders progress updates in this format
+    pub fn reporter(self) -> impl FnMut(Progress) {
+        let interactive = io::stderr().is_terminal();
+
+        move |progress| match self {
+            ProgressFormat::Human if interactive => print_bar(&progress),
+            ProgressFormat::Human => {}
+            ProgressFormat::Json => match serde_json::to_string(&progress) {
+                Ok(json) => println!("{json}"),
+                Err(err) => warn!("Failed to serialize progress: {err}"),
+            },
+        }
+    }
+}
+
+fn print_bar(progress: &Progress) {
+    let filled = (progress.current.min(progress.total) * BAR_WIDTH)
+        .checked_div(progress.total)
+        .unwrap_or(BAR_WIDTH);
+    let filled = usize::try_from(filled).unwrap_or_default();
+    let empty = usize::try_from(BAR_WIDTH)
+        .unwrap_or_default()
+        .saturating_sub(filled);
+
+    let path = progress
+        .path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let phase = progress.phase.to_string();
+
+    let mut stderr = io::stderr().lock();
+    // Clear the line first, the previous path may have been longer
+    let _ = write!(
+        stderr,
+        "\r\x1b[2K{:<8} [{}{}] {}/{} {}",
+        phase,
+        "#".repeat(filled),
+        " ".repeat(empty),
+        progress.current,
+        progress.total,
+        path
+    );
+    if progress.current >= progress.total {
+        let _ = writeln!(stderr);
+    }
+    let _ = stderr.flush();
+}
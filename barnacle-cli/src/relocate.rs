@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use barnacle_lib::Repository;
+use clap::Args;
+use colored::Colorize;
+use sysexits::ExitCode;
+
+#[derive(Args, Debug, Clone)]
+pub struct Command {
+    /// The directory the library and its referenced files used to live under
+    old_base: PathBuf,
+    /// The directory they have been moved to
+    new_base: PathBuf,
+}
+
+/// With `dry_run`, only the paths that would be rewritten are listed
+pub fn handle(repo: &Repository, cmd: &Command, dry_run: bool) {
+    let result = if dry_run {
+        repo.relocation_plan(&cmd.old_base, &cmd.new_base)
+    } else {
+        repo.relocate(&cmd.old_base, &cmd.new_base)
+    };
+
+    let relocations = match result {
+        Ok(relocations) => relocations,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::Usage.exit()
+        }
+    };
+
+    if relocations.is_empty() {
+        println!("Nothing to relocate");
+        return;
+    }
+
+    for relocation in &relocations {
+        println!(
+            "* {}: {} -> {}",
+            relocation.subject,
+            relocation.from.display(),
+            relocation.to.display()
+        );
+    }
+
+    if dry_run {
+        println!("{}", "Dry run, nothing was changed".yellow());
+    } else {
+        println!(
+            "{}",
+            format!("Relocated {} paths", relocations.len()).green()
+        );
+    }
+}
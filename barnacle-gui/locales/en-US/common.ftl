@@ -11,6 +11,10 @@ mod = { $count ->
     [1] Mod
    *[other] Mods
 }
+plugin = { $count ->
+    [1] Plugin
+   *[other] Plugins
+}
 tool = { $count ->
     [one] Tool
    *[other] Tools
@@ -26,3 +30,14 @@ new = New
 # Fields
 name = Name
 path = Path
+
+# States
+no-plugins = No plugins
+no-profile = No active profile
+
+# Notices
+item-removed = The item was removed elsewhere, reloading
+mods-added = { $count ->
+    [1] 1 mod added
+   *[other] { $count } mods added
+}
@@ -1,3 +1,18 @@
 library-manager_title = Library Manager
 library-manager_new-game = New { game }
 library-manager_overview = Overview
+library-manager_no-games = No games yet
+library-manager_no-selected-game = Select a game to manage it
+library-manager_overview_launches = Launched { $count ->
+    [one] once
+   *[other] { $count } times
+}
+library-manager_overview_play-time = Played for { $hours }h { $minutes }m
+library-manager_overview_last-played = Last played { $days ->
+    [0] today
+    [1] yesterday
+   *[other] { $days } days ago
+}
+library-manager_overview_never-played = Never played
+library-manager_overview_no-profiles = This game has no profiles yet
+library-manager_profiles_switches-game = Activating this profile also makes its game the active one
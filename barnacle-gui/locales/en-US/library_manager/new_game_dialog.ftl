@@ -1 +1,2 @@
 library-manager_new-game-dialog_deploy-kind = Deploy Kind
+library-manager_new-game-dialog_default-profile = Create a default profile
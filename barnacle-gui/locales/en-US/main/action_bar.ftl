@@ -1 +1,2 @@
 main_action-bar_add-mod = Add Mod
+main_action-bar_preview-deployment = Preview Deployment
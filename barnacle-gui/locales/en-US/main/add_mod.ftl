@@ -0,0 +1 @@
+main_add-mod_review = Review files before installing
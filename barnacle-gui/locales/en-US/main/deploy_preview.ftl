@@ -0,0 +1,3 @@
+main_deploy-preview_title = Files the game sees once the profile is deployed
+main_deploy-preview_empty = No enabled mod provides any files
+main_deploy-preview_overwrites-vanilla = Overwrites a game file
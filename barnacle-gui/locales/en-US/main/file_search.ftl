@@ -0,0 +1,3 @@
+main_file-search_placeholder = Which mod provides... (e.g. meshes/armor.nif)
+main_file-search_no-providers = No enabled mod provides this file
+main_file-search_deployed = { $name } (deployed)
@@ -0,0 +1,3 @@
+main_install_title = Installing { $name }
+main_install_progress = { $phase }: { $current } of { $total }
+main_install_canceling = Canceling, cleaning up what was installed so far...
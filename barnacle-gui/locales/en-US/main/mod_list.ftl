@@ -0,0 +1,13 @@
+main_mod-list_no-notes = This mod has no notes
+main_mod-list_columns = Columns
+main_mod-list_empty = No mods in this profile, click Add Mod to get started
+main_mod-list_reinstall = Reinstall
+main_mod-list_files-missing = This mod's files were moved or deleted
+main_mod-list_editable = Keep this mod's files writable so they can be edited in place
+main_mod-list_conflicts = { $count ->
+    [one] Conflicts over 1 file
+   *[other] Conflicts over { $count } files
+}
+main_mod-list_conflict-wins = { $path } (wins)
+main_mod-list_conflict-loses = { $path } (overridden by { $winner })
+main_mod-list_conflicts-more = …and { $count } more
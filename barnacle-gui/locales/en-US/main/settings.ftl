@@ -0,0 +1,6 @@
+main_settings_title = Settings
+main_settings_enable-new-mods = Enable mods when they are added to a profile
+main_settings_enforce-readonly = Make the files of installed mods read-only
+main_settings_enforce-readonly-hint = Keeps the game and tools from changing mod files through deployed links. Turning it off only affects mods installed afterwards, and edits to a mod's files can't be told apart from corruption when verifying it.
+main_settings_open-log = Open Log File
+main_settings_no-log = Nothing has been logged yet
@@ -0,0 +1,9 @@
+main_staging_title = Review files
+main_staging_data-root = Install from
+main_staging_everything = Everything
+main_staging_files = { $count ->
+    [one] 1 file will be installed
+   *[other] { $count } files will be installed
+}
+main_staging_install = Install
+main_staging_discard = Discard
@@ -0,0 +1,6 @@
+main_tools_no-game = No active game
+main_tools_no-tools = This game has no tools
+main_tools_running = Running...
+main_tools_kill = Kill
+main_tools_exited = Exited with { $status }
+main_tools_deploying = Deploying...
@@ -1,2 +1,3 @@
 main_top-bar_launch-game = Launch { game }
 main_top-bar_add-mod = Add { mod }
+main_top-bar_no-profile = (none)
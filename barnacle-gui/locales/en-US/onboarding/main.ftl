@@ -0,0 +1,10 @@
+onboarding_welcome = Welcome to Barnacle
+onboarding_add-game = Your library is empty. Add the first game you want to mod, and pick how its mods are deployed.
+onboarding_detected = These installed games were found, pick one to add it:
+onboarding_add-profile = Add a profile. Profiles are separate sets of mods and load orders for the same game.
+onboarding_skip = Skip
+onboarding_deploy-kind_overlay = Links mods straight into the game directory
+onboarding_deploy-kind_gamebryo = Like Overlay, and also manages plugins.txt
+onboarding_deploy-kind_creation-engine = For Skyrim, Fallout 4 and other Creation Engine games
+onboarding_deploy-kind_openmw = Adds each mod to openmw.cfg, leaving the game directory untouched
+onboarding_deploy-kind_baldurs-gate-3 = For Baldur's Gate 3 mods and its load order
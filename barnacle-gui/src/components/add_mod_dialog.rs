@@ -4,7 +4,7 @@ use barnacle_lib::Repository;
 use fluent_i18n::t;
 use iced::{
     Element, Task,
-    widget::{button, column, container, row, space, text, text_input},
+    widget::{button, checkbox, column, container, row, space, text, text_input},
 };
 use rfd::AsyncFileDialog;
 
@@ -16,6 +16,7 @@ pub enum Message {
     PathChanged(String),
     PickPath(PickPathKind),
     PathPicked(Option<String>),
+    ReviewToggled(bool),
     CancelButtonPressed,
     AddButtonPressed,
 }
@@ -24,7 +25,12 @@ pub enum Message {
 pub enum Action {
     None,
     Run(Task<Message>),
-    AddMod { name: String, path: String },
+    /// Add the mod, first staging its files for review if `review` is set
+    AddMod {
+        name: String,
+        path: String,
+        review: bool,
+    },
     Cancel,
 }
 
@@ -38,6 +44,7 @@ pub struct AddModDialog {
     repo: Repository,
     name: String,
     path: String,
+    review: bool,
 }
 
 impl AddModDialog {
@@ -47,6 +54,7 @@ impl AddModDialog {
                 repo: repo.clone(),
                 name: "".into(),
                 path: "".into(),
+                review: false,
             },
             Task::none(),
         )
@@ -91,6 +99,10 @@ impl AddModDialog {
                 }
                 Action::None
             }
+            Message::ReviewToggled(review) => {
+                self.review = review;
+                Action::None
+            }
             Message::CancelButtonPressed => {
                 self.clear();
                 Action::Cancel
@@ -98,6 +110,7 @@ impl AddModDialog {
             Message::AddButtonPressed => Action::AddMod {
                 name: self.name.clone(),
                 path: self.path.clone(),
+                review: self.review,
             },
         }
     }
@@ -114,6 +127,9 @@ impl AddModDialog {
                 button(icon("archive")).on_press(Message::PickPath(PickPathKind::Archive)),
                 button(icon("directory")).on_press(Message::PickPath(PickPathKind::Directory))
             ],
+            checkbox(self.review)
+                .label(t!("main_add-mod_review"))
+                .on_toggle(Message::ReviewToggled),
             space::vertical(),
             row![
                 space::horizontal(),
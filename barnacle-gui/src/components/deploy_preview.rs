@@ -0,0 +1,167 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use barnacle_lib::repository::{MergedTree, Mod, Profile, entities};
+use fluent_i18n::t;
+use iced::{
+    Element, Length, Padding, Task,
+    widget::{button, column, container, row, scrollable, space, text},
+};
+
+use crate::{icons::icon, loadable::Loadable, task};
+
+/// How far each level of the tree is indented
+const INDENT: u16 = 20;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    StateChanged(State),
+    CloseButtonPressed,
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Close,
+}
+
+pub type State = Loadable<Vec<Line>>;
+
+/// A directory or file of the tree, flattened so the view doesn't have to hit the database
+#[derive(Debug, Clone)]
+pub struct Line {
+    depth: u16,
+    name: String,
+    /// Name of the mod providing the file, `None` for directories
+    provider: Option<String>,
+    /// Whether the file replaces one of the game's own
+    vanilla: bool,
+}
+
+/// Read-only view of the file tree the game sees once the active profile is deployed
+pub struct DeployPreview {
+    state: State,
+}
+
+impl DeployPreview {
+    pub fn new() -> Self {
+        Self {
+            state: State::Loading,
+        }
+    }
+
+    /// Load the merged tree of `profile`
+    pub fn refresh(&mut self, profile: Option<&Profile>) -> Task<Message> {
+        self.state = State::Loading;
+        let Some(profile) = profile.cloned() else {
+            return Task::done(Message::StateChanged(State::Loaded(Vec::new())));
+        };
+
+        task::blocking(move || {
+            let overwrites: BTreeSet<PathBuf> = profile.vanilla_overwrites()?.into_keys().collect();
+            let mut lines = Vec::new();
+            flatten(
+                &profile.merged_tree()?,
+                Path::new(""),
+                &overwrites,
+                &mut HashMap::new(),
+                &mut lines,
+            )?;
+            Ok(lines)
+        })
+        .map(|result| Message::StateChanged(result.into()))
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::StateChanged(state) => {
+                self.state = state;
+                Action::None
+            }
+            Message::CloseButtonPressed => Action::Close,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title_bar = row![
+            text(t!("main_deploy-preview_title")),
+            space::horizontal(),
+            button(icon("close")).on_press(Message::CloseButtonPressed)
+        ];
+
+        let body = self
+            .state
+            .view_list(t!("main_deploy-preview_empty"), |lines| {
+                scrollable(column(lines.iter().map(line)))
+                    .height(Length::Fill)
+                    .into()
+            });
+
+        container(column![title_bar, body].spacing(8))
+            .padding(20)
+            .width(800)
+            .height(600)
+            .style(container::rounded_box)
+            .into()
+    }
+}
+
+fn line(line: &Line) -> Element<'_, Message> {
+    let indent = Padding::ZERO.left(f32::from(line.depth.saturating_mul(INDENT)));
+    let content = match &line.provider {
+        None => row![icon("directory"), text(&line.name)],
+        Some(provider) => row![
+            text(&line.name),
+            line.vanilla
+                .then(|| text(t!("main_deploy-preview_overwrites-vanilla")).style(text::warning)),
+            space::horizontal(),
+            text(provider)
+        ],
+    };
+
+    container(content.spacing(4)).padding(indent).into()
+}
+
+/// Append the directories and files of `tree`, found at `path`, to `lines`, directories first.
+/// Files in `overwrites` are marked as replacing a game file. Mod names are cached in `names`
+/// since most mods provide many files.
+fn flatten(
+    tree: &MergedTree,
+    path: &Path,
+    overwrites: &BTreeSet<PathBuf>,
+    names: &mut HashMap<Mod, String>,
+    lines: &mut Vec<Line>,
+) -> entities::Result<()> {
+    let depth = u16::try_from(path.iter().count()).unwrap_or(u16::MAX);
+    for (name, dir) in &tree.dirs {
+        lines.push(Line {
+            depth,
+            name: name.clone(),
+            provider: None,
+            vanilla: false,
+        });
+        flatten(dir, &path.join(name), overwrites, names, lines)?;
+    }
+
+    for (name, mod_) in &tree.files {
+        let provider = match names.get(mod_) {
+            Some(provider) => provider.clone(),
+            None => {
+                let provider = mod_.name()?;
+                names.insert(mod_.clone(), provider.clone());
+                provider
+            }
+        };
+
+        lines.push(Line {
+            depth,
+            name: name.clone(),
+            provider: Some(provider),
+            vanilla: overwrites.contains(&path.join(name)),
+        });
+    }
+
+    Ok(())
+}
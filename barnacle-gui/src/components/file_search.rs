@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use barnacle_lib::repository::Profile;
+use fluent_i18n::t;
+use iced::{
+    Element, Task,
+    widget::{column, text, text_input},
+};
+
+use crate::{errors::Reaction, task};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    QueryChanged(String),
+    Submitted,
+    Found(Vec<String>),
+    Failed(Reaction),
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    /// Look the query up in the active profile, see [`FileSearch::search`]
+    Search,
+    Failed(Reaction),
+}
+
+/// Looks up which enabled mods ship a file, to track down where a misbehaving file came from
+pub struct FileSearch {
+    query: String,
+    /// Names of the mods providing the last searched file, the deployed one last
+    providers: Option<Vec<String>>,
+}
+
+impl FileSearch {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            providers: None,
+        }
+    }
+
+    /// Search `profile` for the mods providing the queried file
+    pub fn search(&self, profile: Option<&Profile>) -> Task<Message> {
+        let Some(profile) = profile.cloned() else {
+            return Task::done(Message::Found(Vec::new()));
+        };
+        let path = PathBuf::from(self.query.trim());
+
+        task::blocking(move || {
+            profile
+                .providers_of(&path)?
+                .iter()
+                .map(|m| m.name())
+                .collect()
+        })
+        .map(|result| match result {
+            Ok(providers) => Message::Found(providers),
+            Err(err) => Message::Failed(err.into()),
+        })
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::QueryChanged(query) => {
+                self.query = query;
+                self.providers = None;
+                Action::None
+            }
+            Message::Submitted if self.query.trim().is_empty() => Action::None,
+            Message::Submitted => Action::Search,
+            Message::Found(providers) => {
+                self.providers = Some(providers);
+                Action::None
+            }
+            Message::Failed(reaction) => Action::Failed(reaction),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let input = text_input(&t!("main_file-search_placeholder"), &self.query)
+            .on_input(Message::QueryChanged)
+            .on_submit(Message::Submitted);
+
+        let results: Element<'_, Message> = match self.providers.as_deref() {
+            None => column![].into(),
+            Some([]) => text(t!("main_file-search_no-providers")).into(),
+            Some([others @ .., winner]) => column(others.iter().map(|name| text(name).into()))
+                .push(text(
+                    t!("main_file-search_deployed", { "name" => winner.as_str() }),
+                ))
+                .into(),
+        };
+
+        column![input, results].spacing(4).into()
+    }
+}
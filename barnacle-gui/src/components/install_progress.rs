@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use barnacle_lib::{
+    Repository,
+    progress::{Cancel, Progress},
+    repository::entities,
+};
+use fluent_i18n::t;
+use iced::{
+    Element,
+    futures::{SinkExt, Stream, channel::mpsc},
+    stream,
+    widget::{button, column, container, progress_bar, row, space, text},
+};
+use tokio::{sync::mpsc::unbounded_channel, task::spawn_blocking};
+
+use crate::errors::Reaction;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    CancelButtonPressed,
+    Install(Event),
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Installed,
+    Canceled,
+    Failed(Reaction),
+}
+
+/// What happened to an install running in the background
+#[derive(Debug, Clone)]
+pub enum Event {
+    Progress(Progress),
+    Installed,
+    Canceled,
+    Failed(Reaction),
+}
+
+/// Shows how far along installing a mod is, with a way to cancel it
+pub struct InstallProgress {
+    name: String,
+    cancel: Cancel,
+    progress: Option<Progress>,
+}
+
+impl InstallProgress {
+    /// Start installing the archive at `path` as a new mod of the active game, added to the
+    /// active profile, which is created first if the game has none. The returned stream reports
+    /// how it goes.
+    pub fn start(
+        repo: Repository,
+        name: String,
+        path: PathBuf,
+    ) -> (Self, impl Stream<Item = Event>) {
+        let cancel = Cancel::new();
+        let dialog = Self {
+            name: name.clone(),
+            cancel: cancel.clone(),
+            progress: None,
+        };
+
+        (dialog, install(repo, name, path, cancel))
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::CancelButtonPressed => {
+                // The install notices at its next file, cleans up and reports back
+                self.cancel.cancel();
+                Action::None
+            }
+            Message::Install(event) => match event {
+                Event::Progress(progress) => {
+                    self.progress = Some(progress);
+                    Action::None
+                }
+                Event::Installed => Action::Installed,
+                Event::Canceled => Action::Canceled,
+                Event::Failed(reaction) => Action::Failed(reaction),
+            },
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let (phase, current, total) = match &self.progress {
+            Some(progress) => (progress.phase.to_string(), progress.current, progress.total),
+            None => (String::new(), 0, 0),
+        };
+        let percent = (current.min(total) * 100).checked_div(total).unwrap_or(0);
+        let percent = f32::from(u16::try_from(percent).unwrap_or(100));
+
+        let canceling = self.cancel.is_canceled();
+        let status = if canceling {
+            t!("main_install_canceling")
+        } else {
+            t!("main_install_progress", {
+                "phase" => phase.as_str(),
+                "current" => current,
+                "total" => total
+            })
+        };
+
+        container(
+            column![
+                text(t!("main_install_title", { "name" => self.name.as_str() })),
+                progress_bar(0.0..=100.0, percent),
+                row![
+                    text(status),
+                    space::horizontal(),
+                    button(text(t!("cancel")))
+                        .style(button::danger)
+                        .on_press_maybe((!canceling).then_some(Message::CancelButtonPressed)),
+                ]
+                .spacing(8),
+            ]
+            .spacing(8),
+        )
+        .padding(20)
+        .width(500)
+        .style(container::rounded_box)
+        .into()
+    }
+}
+
+fn install(
+    repo: Repository,
+    name: String,
+    path: PathBuf,
+    cancel: Cancel,
+) -> impl Stream<Item = Event> {
+    stream::channel(100, |mut output: mpsc::Sender<Event>| async move {
+        let (progress_tx, mut progress_rx) = unbounded_channel();
+        let installed = spawn_blocking(move || -> entities::Result<()> {
+            let Some(active_game) = repo.active_game()? else {
+                return Ok(());
+            };
+
+            let mod_ = active_game.add_mod_cancelable(
+                &name,
+                Some(&path),
+                &mut |progress| {
+                    let _ = progress_tx.send(progress);
+                },
+                &cancel,
+            )?;
+            // Without an active profile, the mod is only added to the library
+            if let Some((_, active_profile)) = repo.ensure_active_context()? {
+                active_profile.add_mod_entry(mod_)?;
+            }
+            Ok(())
+        });
+
+        // The channel closes once the install is done with the sender
+        while let Some(progress) = progress_rx.recv().await {
+            let _ = output.send(Event::Progress(progress)).await;
+        }
+
+        let event = match installed.await.expect("blocking task shouldn't panic") {
+            Ok(()) => Event::Installed,
+            Err(entities::Error::Canceled) => Event::Canceled,
+            Err(err) => Event::Failed(err.into()),
+        };
+        let _ = output.send(event).await;
+    })
+}
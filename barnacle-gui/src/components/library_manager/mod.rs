@@ -1,21 +1,22 @@
 use crate::{
     components::library_manager::{new_game_dialog::NewGame, profiles_tab::new_dialog::NewProfile},
+    errors::Reaction,
     icons::icon,
-    modal,
+    loadable::{empty, error, loading},
+    modal, task,
 };
 use barnacle_lib::{
     Repository,
-    repository::{Game, Profile},
+    repository::{Game, Profile, entities},
 };
 use fluent_i18n::t;
 use iced::{
     Element, Length, Task,
     widget::{Column, button, column, container, row, rule, scrollable, space, text},
 };
-use iced_aw::Spinner;
-use tokio::task::spawn_blocking;
 
 pub mod new_game_dialog;
+pub mod overview_tab;
 pub mod profiles_tab;
 
 #[derive(Debug, Clone)]
@@ -24,10 +25,10 @@ pub enum Message {
     TabSelected(TabId),
     CloseButtonPressed,
     NewGameButtonPressed,
-    ActivateButtonPressed(Game),
     GameRowSelected(Game),
     // Components
     NewGameDialog(new_game_dialog::Message),
+    OverviewTab(overview_tab::Message),
     ProfilesTab(profiles_tab::Message),
 }
 
@@ -40,7 +41,9 @@ pub enum Action {
     DeleteGame(Game),
     ActivateGame(Game),
     CreateProfile { game: Game, new_profile: NewProfile },
+    ActivateProfile(Profile),
     DeleteProfile(Profile),
+    Failed(Reaction),
     Close,
 }
 
@@ -71,6 +74,7 @@ pub struct LibraryManager {
     show_new_game_dialog: bool,
     // Components
     new_game_dialog: new_game_dialog::Dialog,
+    overview_tab: overview_tab::Tab,
     profiles_tab: profiles_tab::Tab,
 }
 
@@ -87,6 +91,7 @@ impl LibraryManager {
                 selected_game: None,
                 show_new_game_dialog: false,
                 new_game_dialog,
+                overview_tab: overview_tab::Tab::new(),
                 profiles_tab,
             },
             Task::batch([
@@ -103,18 +108,25 @@ impl LibraryManager {
     pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::StateChanged(state) => {
+                if let State::Loaded { games, .. } = &state {
+                    // Keep the selection unless the selected game went away
+                    if let Some(selected) = &self.selected_game
+                        && !games.iter().any(|g| &g.entity == selected)
+                    {
+                        self.selected_game = None;
+                    }
+                }
                 self.state = state;
                 match &self.state {
                     State::Loaded { active_game, .. } => {
                         // If there isn't already a selected game, we can set it to the active game
-                        let selected_game = self.selected_game.get_or_insert(active_game.clone());
+                        let selected_game = self
+                            .selected_game
+                            .get_or_insert(active_game.clone())
+                            .clone();
 
                         // We only want to load the tab contents if we have a selected_game
-                        Action::Run(
-                            self.profiles_tab
-                                .refresh(selected_game)
-                                .map(Message::ProfilesTab),
-                        )
+                        Action::Run(self.refresh_tabs(&selected_game))
                     }
                     _ => Action::None,
                 }
@@ -128,10 +140,9 @@ impl LibraryManager {
                 self.show_new_game_dialog = true;
                 Action::None
             }
-            Message::ActivateButtonPressed(game) => Action::ActivateGame(game),
             Message::GameRowSelected(game) => {
                 self.selected_game = Some(game.clone());
-                Action::Run(self.profiles_tab.refresh(&game).map(Message::ProfilesTab))
+                Action::Run(self.refresh_tabs(&game))
             }
             Message::NewGameDialog(message) => match self.new_game_dialog.update(message) {
                 new_game_dialog::Action::None => Action::None,
@@ -145,17 +156,21 @@ impl LibraryManager {
                     Action::None
                 }
             },
+            Message::OverviewTab(message) => match self.overview_tab.update(message) {
+                overview_tab::Action::None => Action::None,
+                overview_tab::Action::Activate => match &self.selected_game {
+                    Some(selected_game) => Action::ActivateGame(selected_game.clone()),
+                    None => Action::None,
+                },
+                overview_tab::Action::Failed(reaction) => Action::Failed(reaction),
+            },
             Message::ProfilesTab(message) => match self.profiles_tab.update(message) {
                 // TODO: Do top-level if let Some(selected_game)
                 profiles_tab::Action::None => Action::None,
                 profiles_tab::Action::Run(task) => Action::Run(task.map(Message::ProfilesTab)),
                 profiles_tab::Action::Refresh => {
                     if let Some(selected_game) = &self.selected_game {
-                        Action::Run(
-                            self.profiles_tab
-                                .refresh(selected_game)
-                                .map(Message::ProfilesTab),
-                        )
+                        Action::Run(self.refresh_tabs(selected_game))
                     } else {
                         Action::None
                     }
@@ -170,7 +185,9 @@ impl LibraryManager {
                         Action::None
                     }
                 }
+                profiles_tab::Action::Activate(profile) => Action::ActivateProfile(profile),
                 profiles_tab::Action::Delete(profile) => Action::DeleteProfile(profile),
+                profiles_tab::Action::Failed(reaction) => Action::Failed(reaction),
             },
         }
     }
@@ -189,9 +206,11 @@ impl LibraryManager {
         .on_press(Message::NewGameButtonPressed);
 
         let body: Element<'_, Message> = match &self.state {
-            State::Loading => Spinner::new().into(),
-            State::Error(e) => text(e).into(),
-            State::NoGames => column![text("No games"), new_game_button].into(),
+            State::Loading => loading(),
+            State::Error(e) => error(e),
+            State::NoGames => {
+                column![empty(t!("library-manager_no-games")), new_game_button].into()
+            }
             State::Loaded { active_game, games } => {
                 let game_rows = games
                     .iter()
@@ -211,16 +230,13 @@ impl LibraryManager {
                         self.tab_button(TabId::Profiles),
                     ];
                     let tab_view: Element<'_, Message> = match self.active_tab {
-                        TabId::Overview => column![button(text(t!("activate"))).on_press(
-                            Message::ActivateButtonPressed(self.selected_game.clone().unwrap())
-                        )]
-                        .into(),
+                        TabId::Overview => self.overview_tab.view().map(Message::OverviewTab),
                         TabId::Profiles => self.profiles_tab.view().map(Message::ProfilesTab),
                     };
 
                     column![tab_bar, tab_view]
                 } else {
-                    column![text("No selected game")]
+                    column![empty(t!("library-manager_no-selected-game"))]
                 };
 
                 row![
@@ -249,6 +265,14 @@ impl LibraryManager {
         .into()
     }
 
+    /// Load the contents of every tab for `game`
+    fn refresh_tabs(&self, game: &Game) -> Task<Message> {
+        Task::batch([
+            self.overview_tab.refresh(game).map(Message::OverviewTab),
+            self.profiles_tab.refresh(game).map(Message::ProfilesTab),
+        ])
+    }
+
     fn tab_button(&self, tab: TabId) -> Element<'_, Message> {
         let label = match tab {
             TabId::Overview => t!("library-manager_overview"),
@@ -268,35 +292,30 @@ impl LibraryManager {
 }
 
 fn load_state(repo: Repository) -> Task<Message> {
-    let repo = repo.clone();
-    Task::perform(
-        async {
-            spawn_blocking(move || {
-                let active_game = repo.active_game().unwrap();
-                let games: Vec<GameRow> = repo
-                    .games()
-                    .unwrap()
-                    .iter()
-                    .map(|g| GameRow {
-                        entity: g.clone(),
-                        name: g.name().unwrap(),
-                    })
-                    .collect();
-
-                if !games.is_empty() {
-                    State::Loaded {
-                        active_game: active_game.unwrap(),
-                        games,
-                    }
-                } else {
-                    State::NoGames
-                }
+    task::run_repo(&repo, |repo| {
+        let active_game = repo.active_game()?;
+        let games = repo
+            .games()?
+            .into_iter()
+            .map(|g| {
+                Ok(GameRow {
+                    name: g.name()?,
+                    entity: g,
+                })
             })
-            .await
-            .unwrap()
-        },
-        Message::StateChanged,
-    )
+            .collect::<entities::Result<Vec<_>>>()?;
+
+        Ok(match active_game {
+            Some(active_game) if !games.is_empty() => State::Loaded { active_game, games },
+            _ => State::NoGames,
+        })
+    })
+    .map(|result| {
+        Message::StateChanged(match result {
+            Ok(state) => state,
+            Err(err) => State::Error(err.to_string()),
+        })
+    })
 }
 
 // Generate a row that represents a Game
@@ -324,7 +343,7 @@ fn game_row<'a>(
         .into()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GameRow {
     entity: Game,
     name: String,
@@ -2,7 +2,7 @@ use barnacle_lib::repository::DeployKind;
 use fluent_i18n::t;
 use iced::{
     Element, Task,
-    widget::{button, column, combo_box, container, row, space, text, text_input},
+    widget::{button, checkbox, column, combo_box, container, row, space, text, text_input},
 };
 use strum::IntoEnumIterator;
 
@@ -12,6 +12,7 @@ pub const ID: &str = "new_game_dialog";
 pub enum Message {
     NameInput(String),
     DeployKindSelected(DeployKind),
+    DefaultProfileToggled(bool),
     CancelPressed,
     CreatePressed,
 }
@@ -20,6 +21,8 @@ pub enum Message {
 pub struct NewGame {
     pub name: String,
     pub deploy_kind: DeployKind,
+    /// Whether to give the game a default profile to start with
+    pub default_profile: bool,
 }
 
 #[derive(Debug)]
@@ -35,6 +38,7 @@ pub struct Dialog {
     name: String,
     deploy_kind: Option<DeployKind>,
     deploy_kind_state: combo_box::State<DeployKind>,
+    default_profile: bool,
 }
 
 impl Dialog {
@@ -44,6 +48,7 @@ impl Dialog {
                 name: "".into(),
                 deploy_kind: None,
                 deploy_kind_state: combo_box::State::new(DeployKind::iter().collect()),
+                default_profile: true,
             },
             Task::none(),
         )
@@ -53,6 +58,7 @@ impl Dialog {
     pub fn clear(&mut self) {
         self.name.clear();
         self.deploy_kind = None;
+        self.default_profile = true;
     }
 
     pub fn update(&mut self, message: Message) -> Action {
@@ -65,6 +71,10 @@ impl Dialog {
                 self.deploy_kind = Some(kind);
                 Action::None
             }
+            Message::DefaultProfileToggled(default_profile) => {
+                self.default_profile = default_profile;
+                Action::None
+            }
             Message::CancelPressed => {
                 self.clear();
                 Action::Cancel
@@ -72,10 +82,15 @@ impl Dialog {
             Message::CreatePressed => {
                 let name = self.name.clone();
                 let deploy_kind = self.deploy_kind.unwrap();
+                let default_profile = self.default_profile;
 
                 self.clear();
 
-                Action::CreateGame(NewGame { name, deploy_kind })
+                Action::CreateGame(NewGame {
+                    name,
+                    deploy_kind,
+                    default_profile,
+                })
             }
         }
     }
@@ -95,6 +110,9 @@ impl Dialog {
                     Message::DeployKindSelected
                 ),
             ],
+            checkbox(self.default_profile)
+                .label(t!("library-manager_new-game-dialog_default-profile"))
+                .on_toggle(Message::DefaultProfileToggled),
             space::vertical(),
             row![
                 space::horizontal(),
@@ -0,0 +1,131 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use barnacle_lib::repository::{Game, Profile};
+use fluent_i18n::t;
+use iced::{
+    Element, Task,
+    widget::{Column, button, column, row, space, text},
+};
+
+use crate::{errors::Reaction, loadable::Loadable, task};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    StateChanged(State),
+    ActivateButtonPressed,
+    Failed(Reaction),
+}
+
+pub enum Action {
+    None,
+    Activate,
+    Failed(Reaction),
+}
+
+pub type State = Loadable<Vec<ProfileStats>>;
+
+/// How often and how long a profile has been played
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileStats {
+    pub entity: Profile,
+    pub name: String,
+    pub launch_count: u64,
+    /// In seconds since the unix epoch
+    pub last_played: Option<u64>,
+    pub play_time: Duration,
+}
+
+/// Summary of the selected game, with play statistics for each of its profiles
+pub struct Tab {
+    state: State,
+}
+
+impl Tab {
+    pub fn new() -> Self {
+        Self {
+            state: State::Loading,
+        }
+    }
+
+    pub fn refresh(&self, game: &Game) -> Task<Message> {
+        let game = game.clone();
+        task::blocking(move || {
+            game.profiles()?
+                .into_iter()
+                .map(|entity| {
+                    Ok(ProfileStats {
+                        name: entity.name()?,
+                        launch_count: entity.launch_count()?,
+                        last_played: entity.last_played()?,
+                        play_time: entity.play_time()?,
+                        entity,
+                    })
+                })
+                .collect()
+        })
+        .map(|result| match result {
+            Ok(stats) => Message::StateChanged(State::Loaded(stats)),
+            Err(err) => Message::Failed(Reaction::from(err)),
+        })
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::StateChanged(state) => {
+                self.state = state;
+                Action::None
+            }
+            Message::ActivateButtonPressed => Action::Activate,
+            Message::Failed(reaction) => Action::Failed(reaction),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let stats = self
+            .state
+            .view_list(t!("library-manager_overview_no-profiles"), |stats| {
+                Column::with_children(stats.iter().map(stats_row))
+                    .spacing(8)
+                    .into()
+            });
+
+        column![
+            button(text(t!("activate"))).on_press(Message::ActivateButtonPressed),
+            stats
+        ]
+        .spacing(8)
+        .into()
+    }
+}
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+fn stats_row(stats: &ProfileStats) -> Element<'_, Message> {
+    let last_played = match stats.last_played {
+        Some(last_played) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let days = now.saturating_sub(last_played).div_euclid(SECONDS_PER_DAY);
+            t!("library-manager_overview_last-played", { "days" => days })
+        }
+        None => t!("library-manager_overview_never-played"),
+    };
+    let minutes = stats.play_time.as_secs().div_euclid(60);
+
+    column![
+        text(&stats.name),
+        row![
+            text(t!("library-manager_overview_launches", { "count" => stats.launch_count })),
+            space::horizontal(),
+            text(t!("library-manager_overview_play-time", {
+                "hours" => minutes.div_euclid(60),
+                "minutes" => minutes.rem_euclid(60)
+            })),
+            space::horizontal(),
+            text(last_played),
+        ],
+    ]
+    .into()
+}
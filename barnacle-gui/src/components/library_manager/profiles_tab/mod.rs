@@ -1,5 +1,6 @@
 use crate::{
-    components::library_manager::profiles_tab::new_dialog::NewProfile, icons::icon, modal,
+    components::library_manager::profiles_tab::new_dialog::NewProfile, errors::Reaction,
+    icons::icon, loadable::Loadable, modal, task,
 };
 use barnacle_lib::{
     Repository,
@@ -8,10 +9,8 @@ use barnacle_lib::{
 use fluent_i18n::t;
 use iced::{
     Element, Length, Task,
-    widget::{Column, button, column, container, row, scrollable, space, text},
+    widget::{Column, button, column, container, row, scrollable, space, text, tooltip},
 };
-use iced_aw::Spinner;
-use tokio::task::spawn_blocking;
 
 use crate::components::library_manager::profiles_tab::{
     edit_dialog::EditDialog, new_dialog::NewDialog,
@@ -24,10 +23,12 @@ pub mod new_dialog;
 pub enum Message {
     StateChanged(State),
     NewButtonPressed,
+    ActivateButtonPressed(Profile),
     EditButtonPressed(Profile),
     DeleteButtonPressed(Profile),
     ProfileCreated,
     ProfileEdited,
+    Failed(Reaction),
     // Child messages
     NewDialog(new_dialog::Message),
     EditDialog(edit_dialog::Message),
@@ -38,14 +39,24 @@ pub enum Action {
     Run(Task<Message>),
     Refresh,
     Create(NewProfile),
+    Activate(Profile),
     Delete(Profile),
+    Failed(Reaction),
 }
 
-#[derive(Debug, Clone)]
-pub enum State {
-    Loading,
-    Error(String),
-    Loaded(Vec<Profile>),
+pub type State = Loadable<Vec<ProfileRow>>;
+
+/// Snapshot of a [`Profile`] as it's displayed, so the view never touches a profile that may
+/// have been removed since
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileRow {
+    pub entity: Profile,
+    pub name: String,
+    /// Whether this is the profile in use, the active one of the active game
+    pub active: bool,
+    /// Whether the profile's game is the active one, otherwise activating the profile switches
+    /// games too
+    pub in_active_game: bool,
 }
 
 pub struct Tab {
@@ -78,16 +89,24 @@ impl Tab {
 
     pub fn refresh(&self, game: &Game) -> Task<Message> {
         let game = game.clone();
-        Task::perform(
-            {
-                async {
-                    spawn_blocking(move || State::Loaded(game.profiles().unwrap()))
-                        .await
-                        .unwrap()
-                }
-            },
-            Message::StateChanged,
-        )
+        task::blocking(move || {
+            let in_active_game = game.is_active()?;
+            game.profiles()?
+                .into_iter()
+                .map(|entity| {
+                    Ok(ProfileRow {
+                        name: entity.name()?,
+                        active: in_active_game && entity.is_active()?,
+                        in_active_game,
+                        entity,
+                    })
+                })
+                .collect()
+        })
+        .map(|result| match result {
+            Ok(rows) => Message::StateChanged(State::Loaded(rows)),
+            Err(err) => Message::Failed(Reaction::from(err)),
+        })
     }
 
     pub fn update(&mut self, message: Message) -> Action {
@@ -98,16 +117,21 @@ impl Tab {
             }
             Message::ProfileCreated => Action::Refresh,
             Message::ProfileEdited => Action::Refresh,
+            Message::Failed(reaction) => Action::Failed(reaction),
             Message::NewButtonPressed => {
                 self.show_new_dialog = true;
                 Action::None
             }
+            Message::ActivateButtonPressed(profile) => Action::Activate(profile),
             Message::EditButtonPressed(profile) => {
                 self.edit_dialog.load(profile);
                 Action::None
             }
             Message::DeleteButtonPressed(profile) => {
-                self.state = State::Loading;
+                // Drop the row straight away, the view never has to see the removed profile
+                if let State::Loaded(rows) = &mut self.state {
+                    rows.retain(|r| r.entity != profile);
+                }
                 Action::Delete(profile)
             }
             Message::NewDialog(message) => match self.new_dialog.update(message) {
@@ -128,32 +152,30 @@ impl Tab {
                     edit_dialog::Action::None => Action::None,
                     edit_dialog::Action::Run(task) => Action::Run(task.map(Message::EditDialog)),
                     edit_dialog::Action::Cancel => Action::None,
-                    edit_dialog::Action::Edit { profile, name } => Action::Run(Task::perform(
-                        async {
-                            spawn_blocking(move || {
-                                profile.set_name(&name).unwrap();
-                            })
-                            .await
-                        },
-                        |_| Message::ProfileEdited,
-                    )),
+                    edit_dialog::Action::Edit { profile, name } => Action::Run(
+                        task::run_repo(&self.repo, move |repo| {
+                            repo.rename_profile(&profile, &name)
+                        })
+                        .map(|result| match result {
+                            Ok(()) => Message::ProfileEdited,
+                            Err(err) => Message::Failed(Reaction::from(err)),
+                        }),
+                    ),
                 },
                 _ => Action::None,
             },
         }
     }
     pub fn view(&self) -> Element<'_, Message> {
-        let content = match &self.state {
-            State::Loading => Spinner::new().into(),
-            State::Error(e) => text(e).into(),
-            State::Loaded(profiles) => column![
+        let content = self.state.view(|profiles| {
+            column![
                 button(text(t!("new"))).on_press(Message::NewButtonPressed),
                 scrollable(Column::with_children(
-                    profiles.iter().map(|p| self.profile_row(p))
+                    profiles.iter().map(|row| self.profile_row(row))
                 ))
             ]
-            .into(),
-        };
+            .into()
+        });
 
         if self.show_new_dialog {
             modal(
@@ -166,13 +188,33 @@ impl Tab {
         }
     }
 
-    fn profile_row<'a>(&'a self, profile: &'a Profile) -> Element<'a, Message> {
+    fn profile_row<'a>(&'a self, row: &'a ProfileRow) -> Element<'a, Message> {
+        let activate: Element<'a, Message> = if row.active {
+            icon("check").into()
+        } else {
+            let activate = button(text(t!("activate")))
+                .on_press(Message::ActivateButtonPressed(row.entity.clone()));
+            if row.in_active_game {
+                activate.into()
+            } else {
+                tooltip(
+                    activate,
+                    container(text(t!("library-manager_profiles_switches-game")))
+                        .padding(8)
+                        .style(container::rounded_box),
+                    tooltip::Position::FollowCursor,
+                )
+                .into()
+            }
+        };
+
         container(
             row![
-                text(profile.name().unwrap()),
+                text(&row.name),
                 space::horizontal(),
+                activate,
                 button(icon("edit")),
-                button(icon("delete")).on_press(Message::DeleteButtonPressed(profile.clone()))
+                button(icon("delete")).on_press(Message::DeleteButtonPressed(row.entity.clone()))
             ]
             .padding(12),
         )
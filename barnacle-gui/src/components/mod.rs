@@ -1,36 +1,71 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use barnacle_lib::{Repository, repository::Profile};
-use derive_more::{Deref, Display};
+use barnacle_lib::{
+    Repository,
+    repository::{Game, Profile, StagedMod, entities},
+};
+use derive_more::Display;
 use fluent_i18n::t;
 use iced::{
     Element,
-    Length::Fill,
-    Task, Theme,
-    widget::{button, column, combo_box, row, space, text},
+    Length::{Fill, FillPortion},
+    Subscription, Task, Theme,
+    widget::{button, column, combo_box, container, row, space, text},
 };
 use parking_lot::RwLock;
-use tokio::task::spawn_blocking;
 
 use crate::{
     components::{
-        add_mod_dialog::AddModDialog, library_manager::LibraryManager, mod_list::ModList,
+        add_mod_dialog::AddModDialog,
+        deploy_preview::DeployPreview,
+        file_search::FileSearch,
+        install_progress::InstallProgress,
+        library_manager::{LibraryManager, new_game_dialog::NewGame},
+        mod_list::ModList,
+        onboarding::Onboarding,
+        plugin_list::PluginList,
+        settings::Settings,
+        staging_dialog::StagingDialog,
+        tool_runner::ToolRunner,
     },
     config::GuiConfig,
+    errors::Reaction,
     icons::icon,
-    modal,
+    modal, task,
 };
 
 pub mod add_mod_dialog;
+pub mod deploy_preview;
+pub mod file_search;
+pub mod install_progress;
 pub mod library_manager;
 pub mod mod_list;
+pub mod onboarding;
+pub mod plugin_list;
+pub mod settings;
+pub mod staging_dialog;
+pub mod tool_runner;
+
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
 
 #[derive(Debug, Clone)]
 pub enum Message {
     StateChanged(State),
     AddModButtonPressed,
+    DeployPreviewButtonPressed,
     LibraryManagerButtonPressed,
+    SettingsButtonPressed,
+    ToolsButtonPressed,
     ModAdded,
+    /// A new mod's archive was staged for review before it's installed
+    ModStaged(StagedMod),
+    StagingClosed,
+    LibraryIndexed,
     GameAdded,
     GameEdited,
     GameDeleted,
@@ -38,11 +73,24 @@ pub enum Message {
     ProfileAdded,
     ProfileDeleted,
     ProfileSelected(ProfileOption),
-    ProfileActivated(Profile),
+    ProfileActivated(Option<Profile>),
+    Failed(Reaction),
+    /// The toast with the given ID has been shown long enough
+    ToastExpired(u64),
+    OnboardingGameAdded(Game),
+    OnboardingFinished,
     // Components
     AddModDialog(add_mod_dialog::Message),
+    DeployPreview(deploy_preview::Message),
     ModList(mod_list::Message),
+    PluginList(plugin_list::Message),
+    Settings(settings::Message),
+    StagingDialog(staging_dialog::Message),
+    FileSearch(file_search::Message),
+    InstallProgress(install_progress::Message),
     LibraryManager(library_manager::Message),
+    Onboarding(onboarding::Message),
+    ToolRunner(tool_runner::Message),
 }
 
 #[derive(Debug, Clone)]
@@ -51,7 +99,7 @@ pub enum State {
     Error(String),
     NoGames,
     Loaded {
-        active_profile: Option<ProfileOption>,
+        active_profile: ProfileOption,
         profiles: Vec<ProfileOption>,
     },
 }
@@ -63,18 +111,35 @@ pub struct App {
     theme: Theme,
     profile_selector: ProfileSelector,
     // State
+    /// Short notice shown over the main view
+    toast: Option<String>,
+    /// ID of the current toast, bumped for each one so an older toast's timer can't clear it
+    toast_id: u64,
     show_library_manager: bool,
     show_add_mod_dialog: bool,
+    show_deploy_preview: bool,
+    show_tool_runner: bool,
+    show_settings: bool,
     // Components
     add_mod_dialog: AddModDialog,
+    deploy_preview: DeployPreview,
     mod_list: ModList,
+    plugin_list: PluginList,
+    file_search: FileSearch,
     library_manager: LibraryManager,
+    settings: Settings,
+    tool_runner: ToolRunner,
+    /// Shown while a staged mod is being reviewed
+    staging_dialog: Option<StagingDialog>,
+    /// Shown while a mod is being installed
+    install_progress: Option<InstallProgress>,
+    /// Shown instead of everything else while the library is empty
+    onboarding: Option<Onboarding>,
 }
 
 impl App {
     pub const TITLE: &str = "Barnacle";
-    pub fn new() -> (Self, Task<Message>) {
-        let repo = Repository::new();
+    pub fn new(repo: Repository) -> (Self, Task<Message>) {
         let cfg = Arc::new(RwLock::new(GuiConfig::load()));
         let theme = cfg.read().theme();
 
@@ -88,19 +153,33 @@ impl App {
                 state: State::Loading,
                 title: Self::TITLE.to_string(),
                 theme,
+                toast: None,
+                toast_id: 0,
                 show_library_manager: false,
                 show_add_mod_dialog: false,
+                show_deploy_preview: false,
+                show_tool_runner: false,
+                show_settings: false,
                 profile_selector: ProfileSelector {
                     state: combo_box::State::new(Vec::new()),
                     selected: None,
                 },
                 add_mod_dialog,
+                deploy_preview: DeployPreview::new(),
                 mod_list,
+                plugin_list: PluginList::new(),
+                file_search: FileSearch::new(),
                 library_manager,
+                settings: Settings::new(repo.clone()),
+                tool_runner: ToolRunner::new(),
+                staging_dialog: None,
+                install_progress: None,
+                onboarding: None,
             },
             Task::batch([
                 library_manager_task.map(Message::LibraryManager),
                 load_state(repo.clone()),
+                reindex(repo.clone()),
             ]),
         )
     }
@@ -109,11 +188,50 @@ impl App {
         load_state(self.repo.clone())
     }
 
+    /// Reload everything shown for the given profile, or clear it if there isn't one
+    fn refresh_profile(&self, profile: Option<&Profile>) -> Task<Message> {
+        Task::batch([
+            self.mod_list.refresh(profile).map(Message::ModList),
+            self.plugin_list.refresh(profile).map(Message::PluginList),
+        ])
+    }
+
+    /// Show `message` in a toast for a little while
+    fn notify(&mut self, message: String) -> Task<Message> {
+        self.toast = Some(message);
+        self.toast_id += 1;
+        let id = self.toast_id;
+        Task::perform(tokio::time::sleep(TOAST_DURATION), move |_| {
+            Message::ToastExpired(id)
+        })
+    }
+
+    /// React to a failed operation. See [`Reaction`].
+    fn react(&mut self, reaction: Reaction) -> Task<Message> {
+        let expire = self.notify(reaction.message());
+
+        match reaction {
+            Reaction::Removed => Task::batch([
+                expire,
+                self.refresh(),
+                self.library_manager.refresh().map(Message::LibraryManager),
+                self.refresh_profile(self.profile_selector.active()),
+            ]),
+            Reaction::Report(_) => expire,
+        }
+    }
+
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::StateChanged(state) => {
                 self.state = state;
 
+                if matches!(self.state, State::NoGames) && self.onboarding.is_none() {
+                    let (onboarding, task) = Onboarding::new(&self.repo);
+                    self.onboarding = Some(onboarding);
+                    return task.map(Message::Onboarding);
+                }
+
                 if let State::Loaded {
                     active_profile,
                     profiles,
@@ -121,12 +239,10 @@ impl App {
                 {
                     self.profile_selector = ProfileSelector {
                         state: combo_box::State::new(profiles.clone()),
-                        selected: active_profile.clone(),
+                        selected: Some(active_profile.clone()),
                     };
 
-                    if let Some(active_profile) = active_profile {
-                        return self.mod_list.refresh(active_profile).map(Message::ModList);
-                    }
+                    return self.refresh_profile(active_profile.entity.as_ref());
                 }
 
                 Task::none()
@@ -134,126 +250,322 @@ impl App {
             Message::AddModDialog(message) => match self.add_mod_dialog.update(message) {
                 add_mod_dialog::Action::None => Task::none(),
                 add_mod_dialog::Action::Run(task) => task.map(Message::AddModDialog),
-                add_mod_dialog::Action::AddMod { name, path } => {
+                add_mod_dialog::Action::AddMod {
+                    name,
+                    path,
+                    review: true,
+                } => {
                     self.show_add_mod_dialog = false;
-                    let repo = self.repo.clone();
-                    Task::perform(
-                        async {
-                            spawn_blocking(move || {
-                                if let Some(active_game) = repo.active_game().unwrap() {
-                                    let mod_ = active_game
-                                        .add_mod(&name, Some(&PathBuf::from(path)))
-                                        .unwrap();
-
-                                    if let Some(active_profile) =
-                                        active_game.active_profile().unwrap()
-                                    {
-                                        active_profile.add_mod_entry(mod_).unwrap();
-                                    }
-                                }
-                            })
-                            .await
-                        },
-                        |_| Message::ModAdded,
-                    )
+                    task::run_repo(&self.repo, move |repo| {
+                        let Some(active_game) = repo.active_game()? else {
+                            return Ok(None);
+                        };
+
+                        // The mod stays empty until the staged files are installed
+                        let mod_ = active_game.add_mod(&name, None)?;
+                        match mod_.stage(Path::new(&path)) {
+                            Ok(staged) => Ok(Some(staged)),
+                            Err(err) => {
+                                mod_.remove()?;
+                                Err(err)
+                            }
+                        }
+                    })
+                    .map(|result| match result {
+                        Ok(Some(staged)) => Message::ModStaged(staged),
+                        Ok(None) => Message::StagingClosed,
+                        Err(err) => Message::Failed(err.into()),
+                    })
+                }
+                add_mod_dialog::Action::AddMod { name, path, .. } => {
+                    self.show_add_mod_dialog = false;
+                    let (install_progress, events) =
+                        InstallProgress::start(self.repo.clone(), name, PathBuf::from(path));
+                    self.install_progress = Some(install_progress);
+                    Task::run(events, |event| {
+                        Message::InstallProgress(install_progress::Message::Install(event))
+                    })
                 }
                 add_mod_dialog::Action::Cancel => {
                     self.show_add_mod_dialog = false;
                     Task::none()
                 }
             },
-            Message::ModList(message) => match self.mod_list.update(message) {
-                mod_list::Action::None => Task::none(),
-                mod_list::Action::Run(task) => task.map(Message::ModList),
+            Message::ModStaged(staged) => match StagingDialog::new(staged) {
+                Ok(dialog) => {
+                    self.staging_dialog = Some(dialog);
+                    Task::none()
+                }
+                Err(reaction) => self.react(reaction),
+            },
+            Message::StagingClosed => {
+                self.staging_dialog = None;
+                Task::none()
+            }
+            Message::StagingDialog(message) => {
+                let Some(dialog) = &mut self.staging_dialog else {
+                    return Task::none();
+                };
+
+                match dialog.update(message) {
+                    staging_dialog::Action::None => Task::none(),
+                    staging_dialog::Action::Failed(reaction) => self.react(reaction),
+                    staging_dialog::Action::Commit(staged) => {
+                        self.staging_dialog = None;
+                        task::run_repo(&self.repo, move |repo| {
+                            let mod_ = staged.mod_().clone();
+                            staged.commit()?;
+
+                            if let Some((_, active_profile)) = repo.ensure_active_context()? {
+                                active_profile.add_mod_entry(mod_)?;
+                            }
+                            Ok(())
+                        })
+                        .map(|result| match result {
+                            Ok(()) => Message::ModAdded,
+                            Err(err) => Message::Failed(err.into()),
+                        })
+                    }
+                    staging_dialog::Action::Discard(staged) => {
+                        self.staging_dialog = None;
+                        task::blocking(move || {
+                            let mod_ = staged.mod_().clone();
+                            staged.discard()?;
+                            mod_.remove()
+                        })
+                        .map(|result| match result {
+                            Ok(()) => Message::StagingClosed,
+                            Err(err) => Message::Failed(err.into()),
+                        })
+                    }
+                }
+            }
+            Message::InstallProgress(message) => {
+                let Some(install_progress) = &mut self.install_progress else {
+                    return Task::none();
+                };
+
+                match install_progress.update(message) {
+                    install_progress::Action::None => Task::none(),
+                    install_progress::Action::Installed => {
+                        self.install_progress = None;
+                        self.refresh_profile(self.profile_selector.active())
+                    }
+                    install_progress::Action::Canceled => {
+                        self.install_progress = None;
+                        Task::none()
+                    }
+                    install_progress::Action::Failed(reaction) => {
+                        self.install_progress = None;
+                        self.react(reaction)
+                    }
+                }
+            }
+            Message::DeployPreview(message) => match self.deploy_preview.update(message) {
+                deploy_preview::Action::None => Task::none(),
+                deploy_preview::Action::Close => {
+                    self.show_deploy_preview = false;
+                    Task::none()
+                }
+            },
+            Message::Settings(message) => match self.settings.update(message) {
+                settings::Action::None => Task::none(),
+                settings::Action::Close => {
+                    self.show_settings = false;
+                    Task::none()
+                }
+            },
+            Message::ToolRunner(message) => match self.tool_runner.update(message) {
+                tool_runner::Action::None => Task::none(),
+                tool_runner::Action::Run(task) => task.map(Message::ToolRunner),
+                tool_runner::Action::Close => {
+                    self.show_tool_runner = false;
+                    Task::none()
+                }
+            },
+            Message::ModList(message) => {
+                let refreshed = matches!(message, mod_list::Message::StateChanged(..));
+                let task = match self.mod_list.update(message) {
+                    mod_list::Action::None => Task::none(),
+                    mod_list::Action::Run(task) => task.map(Message::ModList),
+                    mod_list::Action::Failed(reaction) => return self.react(reaction),
+                };
+
+                match self.mod_list.last_diff() {
+                    Some(diff) if refreshed && diff.added > 0 => {
+                        let notice = self.notify(t!("mods-added", { "count" => diff.added }));
+                        Task::batch([task, notice])
+                    }
+                    _ => task,
+                }
+            }
+            Message::PluginList(message) => match self.plugin_list.update(message) {
+                plugin_list::Action::None => Task::none(),
+                plugin_list::Action::Run(task) => task.map(Message::PluginList),
+                plugin_list::Action::Failed(reaction) => self.react(reaction),
+            },
+            Message::FileSearch(message) => match self.file_search.update(message) {
+                file_search::Action::None => Task::none(),
+                file_search::Action::Search => self
+                    .file_search
+                    .search(self.profile_selector.active())
+                    .map(Message::FileSearch),
+                file_search::Action::Failed(reaction) => self.react(reaction),
             },
             Message::LibraryManager(message) => match self.library_manager.update(message) {
                 library_manager::Action::None => Task::none(),
                 library_manager::Action::Run(task) => task.map(Message::LibraryManager),
-                library_manager::Action::CreateGame(new_game) => Task::perform(
-                    {
-                        let repo = self.repo.clone();
-                        async move {
-                            spawn_blocking(move || {
-                                repo.add_game(&new_game.name, new_game.deploy_kind)
-                            })
-                            .await
-                        }
-                    },
-                    |_| Message::GameAdded,
-                ),
-                library_manager::Action::DeleteGame(game) => Task::perform(
-                    async move { spawn_blocking(move || game.remove().unwrap()).await },
-                    |_| Message::GameDeleted,
-                ),
-                library_manager::Action::ActivateGame(game) => Task::perform(
-                    async move { spawn_blocking(move || game.activate().unwrap()).await },
-                    |_| Message::GameActivated,
-                ),
-                library_manager::Action::CreateProfile { game, new_profile } => Task::perform(
-                    {
-                        let game = game.clone();
-                        async {
-                            spawn_blocking(move || game.add_profile(&new_profile.name).unwrap())
-                                .await
-                                .unwrap()
+                library_manager::Action::CreateGame(new_game) => {
+                    task::run_repo(&self.repo, move |repo| add_game(&repo, &new_game)).map(
+                        |result| match result {
+                            Ok(_) => Message::GameAdded,
+                            Err(err) => Message::Failed(err.into()),
+                        },
+                    )
+                }
+                library_manager::Action::DeleteGame(game) => task::blocking(move || game.remove())
+                    .map(|result| match result {
+                        Ok(()) => Message::GameDeleted,
+                        Err(err) => Message::Failed(err.into()),
+                    }),
+                library_manager::Action::ActivateGame(game) => {
+                    task::blocking(move || game.activate()).map(|result| match result {
+                        Ok(()) => Message::GameActivated,
+                        Err(err) => Message::Failed(err.into()),
+                    })
+                }
+                // Activating a profile of another game switches to that game as well
+                library_manager::Action::ActivateProfile(profile) => task::blocking(move || {
+                    profile.activate().map(|()| profile)
+                })
+                .map(|result| match result {
+                    Ok(profile) => Message::ProfileActivated(Some(profile)),
+                    Err(err) => Message::Failed(err.into()),
+                }),
+                library_manager::Action::CreateProfile { game, new_profile } => {
+                    task::blocking(move || game.add_profile(&new_profile.name)).map(|result| {
+                        match result {
+                            Ok(_) => Message::ProfileAdded,
+                            Err(err) => Message::Failed(err.into()),
                         }
-                    },
-                    |_| Message::ProfileAdded,
-                ),
-                // library_manager::Action::EditGame(edit) => Task::perform(
-                //     async move {
-                //         spawn_blocking(move || {
-                //             edit.game.set_name(&edit.name).unwrap();
-                //             edit.game.set_deploy_kind(edit.deploy_kind).unwrap();
-                //         })
-                //         .await
-                //         .unwrap()
-                //     },
-                //     |_| Message::GameEdited,
-                // ),
-                library_manager::Action::DeleteProfile(profile) => Task::perform(
-                    async {
-                        spawn_blocking(move || {
-                            profile.remove().unwrap();
-                        })
-                        .await
-                        .unwrap()
-                    },
-                    |_| Message::ProfileDeleted,
-                ),
+                    })
+                }
+                // library_manager::Action::EditGame(edit) => task::blocking(move || {
+                //     edit.game.set_name(&edit.name)?;
+                //     edit.game.set_deploy_kind(edit.deploy_kind)
+                // })
+                // .map(|result| match result {
+                //     Ok(()) => Message::GameEdited,
+                //     Err(err) => Message::Failed(err.into()),
+                // }),
+                library_manager::Action::DeleteProfile(profile) => {
+                    task::blocking(move || profile.remove()).map(|result| match result {
+                        Ok(()) => Message::ProfileDeleted,
+                        Err(err) => Message::Failed(err.into()),
+                    })
+                }
+                library_manager::Action::Failed(reaction) => self.react(reaction),
                 library_manager::Action::Close => {
                     self.show_library_manager = false;
                     Task::none()
                 }
             },
+            Message::Onboarding(message) => {
+                let Some(onboarding) = &mut self.onboarding else {
+                    return Task::none();
+                };
+
+                match onboarding.update(message) {
+                    onboarding::Action::None => Task::none(),
+                    onboarding::Action::Run(task) => task.map(Message::Onboarding),
+                    onboarding::Action::CreateGame(new_game) => {
+                        let default_profile = new_game.default_profile;
+                        task::run_repo(&self.repo, move |repo| add_game(&repo, &new_game)).map(
+                            move |result| match result {
+                                // The game already has a profile, so onboarding is over
+                                Ok(_) if default_profile => Message::OnboardingFinished,
+                                Ok(game) => Message::OnboardingGameAdded(game),
+                                Err(err) => Message::Failed(err.into()),
+                            },
+                        )
+                    }
+                    onboarding::Action::AddDetectedGame(detected) => {
+                        task::run_repo(&self.repo, move |repo| repo.add_detected_game(&detected))
+                            .map(|result| match result {
+                                Ok(game) => Message::OnboardingGameAdded(game),
+                                Err(err) => Message::Failed(err.into()),
+                            })
+                    }
+                    onboarding::Action::CreateProfile { game, new_profile } => {
+                        self.onboarding = None;
+                        task::blocking(move || game.add_profile(&new_profile.name)).map(|result| {
+                            match result {
+                                Ok(_) => Message::ProfileAdded,
+                                Err(err) => Message::Failed(err.into()),
+                            }
+                        })
+                    }
+                    onboarding::Action::Finish => {
+                        self.onboarding = None;
+                        self.refresh()
+                    }
+                }
+            }
+            Message::OnboardingFinished => {
+                self.onboarding = None;
+                Task::batch([
+                    self.library_manager.refresh().map(Message::LibraryManager),
+                    self.refresh(),
+                ])
+            }
+            Message::OnboardingGameAdded(game) => {
+                if let Some(onboarding) = &mut self.onboarding {
+                    onboarding.game_added(game);
+                }
+                Task::batch([
+                    self.library_manager.refresh().map(Message::LibraryManager),
+                    self.refresh(),
+                ])
+            }
             Message::AddModButtonPressed => {
                 self.show_add_mod_dialog = true;
                 Task::none()
             }
+            Message::DeployPreviewButtonPressed => {
+                self.show_deploy_preview = true;
+                self.deploy_preview
+                    .refresh(self.profile_selector.active())
+                    .map(Message::DeployPreview)
+            }
+            Message::SettingsButtonPressed => {
+                self.show_settings = true;
+                Task::none()
+            }
+            Message::ToolsButtonPressed => {
+                self.show_tool_runner = true;
+                self.tool_runner
+                    .refresh(&self.repo)
+                    .map(Message::ToolRunner)
+            }
             Message::LibraryManagerButtonPressed => {
                 self.show_library_manager = true;
                 Task::none()
             }
-            Message::ModAdded => {
-                if let Some(active_profile) = &self.profile_selector.selected {
-                    self.mod_list.refresh(active_profile).map(Message::ModList)
-                } else {
-                    Task::none()
-                }
-            }
+            Message::LibraryIndexed => Task::none(),
+            Message::ModAdded => self.refresh_profile(self.profile_selector.active()),
             Message::ProfileSelected(profile) => {
                 self.profile_selector.selected = Some(profile.clone());
-                Task::perform(
-                    async {
-                        spawn_blocking(move || {
-                            profile.activate().unwrap();
-                            profile.entity
-                        })
-                        .await
-                        .unwrap()
-                    },
-                    Message::ProfileActivated,
-                )
+                task::run_repo(&self.repo, move |repo| {
+                    match &profile.entity {
+                        Some(entity) => entity.activate()?,
+                        None => repo.clear_active_profile()?,
+                    }
+                    Ok(profile.entity)
+                })
+                .map(|result| match result {
+                    Ok(profile) => Message::ProfileActivated(profile),
+                    Err(err) => Message::Failed(err.into()),
+                })
             }
             // TODO: Update the mod list too. If the profile it's referring to is deleted, it needs
             // to know.
@@ -263,7 +575,8 @@ impl App {
             ]),
             Message::ProfileActivated(profile) => Task::batch([
                 self.refresh(),
-                self.mod_list.refresh(&profile).map(Message::ModList),
+                self.refresh_profile(profile.as_ref()),
+                self.library_manager.refresh().map(Message::LibraryManager),
             ]),
             Message::GameAdded | Message::GameEdited | Message::GameDeleted => {
                 self.library_manager.refresh().map(Message::LibraryManager)
@@ -272,16 +585,33 @@ impl App {
                 self.library_manager.refresh().map(Message::LibraryManager),
                 self.refresh(),
             ]),
+            Message::Failed(reaction) => self.react(reaction),
+            Message::ToastExpired(id) => {
+                if id == self.toast_id {
+                    self.toast = None;
+                }
+                Task::none()
+            }
         }
     }
 
     // Render the application and pass along messages from components to update()
     pub fn view(&self) -> Element<'_, Message> {
+        let toast = self.toast.as_ref().map(|toast| {
+            container(text(toast))
+                .padding(8)
+                .style(container::rounded_box)
+        });
+
+        if let Some(onboarding) = &self.onboarding {
+            return column![toast, onboarding.view().map(Message::Onboarding)].into();
+        }
+
         let content = column![
             // Top bar
             row![
                 button(text(t!("main_top-bar_launch-game", { "count" => 1 }))),
-                button(icon("wrench")),
+                button(icon("wrench")).on_press(Message::ToolsButtonPressed),
                 text(t!("profile", { "count" => 1 })),
                 combo_box(
                     &self.profile_selector.state,
@@ -291,20 +621,34 @@ impl App {
                 ),
                 space::horizontal(),
                 button(icon("library")).on_press(Message::LibraryManagerButtonPressed),
-                button(icon("settings")),
+                button(icon("settings")).on_press(Message::SettingsButtonPressed),
                 button(icon("notifications"))
             ],
             // Action bar
             row![
                 button(text(t!("main_action-bar_add-mod", { "count" => 1 }))).on_press_maybe(
                     self.profile_selector
-                        .selected
+                        .active()
                         .is_some()
                         .then_some(Message::AddModButtonPressed)
-                )
+                ),
+                button(text(t!("main_action-bar_preview-deployment"))).on_press_maybe(
+                    self.profile_selector
+                        .active()
+                        .is_some()
+                        .then_some(Message::DeployPreviewButtonPressed)
+                ),
             ],
-            // Mod list
-            self.mod_list.view().map(Message::ModList),
+            toast,
+            row![
+                container(self.mod_list.view().map(Message::ModList)).width(FillPortion(3)),
+                column![
+                    container(self.plugin_list.view().map(Message::PluginList)).height(Fill),
+                    self.file_search.view().map(Message::FileSearch),
+                ]
+                .width(FillPortion(1)),
+            ]
+            .height(Fill),
         ]
         .height(Fill);
 
@@ -314,17 +658,60 @@ impl App {
                 self.library_manager.view().map(Message::LibraryManager),
                 None,
             )
+        } else if let Some(install_progress) = &self.install_progress {
+            modal(
+                content,
+                install_progress.view().map(Message::InstallProgress),
+                None,
+            )
+        } else if let Some(staging_dialog) = &self.staging_dialog {
+            modal(
+                content,
+                staging_dialog.view().map(Message::StagingDialog),
+                None,
+            )
         } else if self.show_add_mod_dialog {
             modal(
                 content,
                 self.add_mod_dialog.view().map(Message::AddModDialog),
                 None,
             )
+        } else if self.show_tool_runner {
+            modal(
+                content,
+                self.tool_runner.view().map(Message::ToolRunner),
+                None,
+            )
+        } else if self.show_deploy_preview {
+            modal(
+                content,
+                self.deploy_preview.view().map(Message::DeployPreview),
+                None,
+            )
+        } else if self.show_settings {
+            modal(content, self.settings.view().map(Message::Settings), None)
         } else {
             content.into()
         }
     }
 
+    pub fn subscription(&self) -> Subscription<Message> {
+        // The list can only be navigated while it's visible
+        if self.onboarding.is_some()
+            || self.show_library_manager
+            || self.show_add_mod_dialog
+            || self.staging_dialog.is_some()
+            || self.install_progress.is_some()
+            || self.show_deploy_preview
+            || self.show_tool_runner
+            || self.show_settings
+        {
+            return Subscription::none();
+        }
+
+        self.mod_list.subscription().map(Message::ModList)
+    }
+
     pub fn title(&self) -> String {
         self.title.clone()
     }
@@ -334,37 +721,46 @@ impl App {
     }
 }
 
+/// Add the game described by `new_game`, with a default profile if it asked for one
+fn add_game(repo: &Repository, new_game: &NewGame) -> entities::Result<Game> {
+    if new_game.default_profile {
+        repo.add_game_with_default_profile(&new_game.name, new_game.deploy_kind)
+    } else {
+        repo.add_game(&new_game.name, new_game.deploy_kind)
+    }
+}
+
 fn load_state(repo: Repository) -> Task<Message> {
-    let repo = repo.clone();
-    Task::perform(
-        async {
-            spawn_blocking(move || {
-                if let Some(active_game) = repo.active_game().unwrap() {
-                    let active_profile = active_game.active_profile().unwrap();
-                    State::Loaded {
-                        active_profile: active_profile.map(|p| ProfileOption {
-                            entity: p.clone(),
-                            name: p.name().unwrap(),
-                        }),
-                        profiles: active_game
-                            .profiles()
-                            .unwrap()
-                            .into_iter()
-                            .map(|p| ProfileOption {
-                                entity: p.clone(),
-                                name: p.name().unwrap(),
-                            })
-                            .collect(),
-                    }
-                } else {
-                    State::NoGames
-                }
-            })
-            .await
-            .unwrap()
-        },
-        Message::StateChanged,
-    )
+    task::run_repo(&repo, |repo| {
+        let Some(active_game) = repo.active_game()? else {
+            return Ok(State::NoGames);
+        };
+
+        let active_profile = match active_game.active_profile()? {
+            Some(profile) => ProfileOption::new(profile)?,
+            None => ProfileOption::none(),
+        };
+        let profiles = std::iter::once(Ok(ProfileOption::none()))
+            .chain(active_game.profiles()?.into_iter().map(ProfileOption::new))
+            .collect::<entities::Result<_>>()?;
+
+        Ok(State::Loaded {
+            active_profile,
+            profiles,
+        })
+    })
+    .map(|result| match result {
+        Ok(state) => Message::StateChanged(state),
+        Err(err) => Message::Failed(err.into()),
+    })
+}
+
+/// Refresh the file index of every mod in the background
+fn reindex(repo: Repository) -> Task<Message> {
+    task::run_repo(&repo, |repo| repo.reindex()).map(|result| match result {
+        Ok(()) => Message::LibraryIndexed,
+        Err(err) => Message::Failed(err.into()),
+    })
 }
 
 #[derive(Debug)]
@@ -373,10 +769,33 @@ struct ProfileSelector {
     selected: Option<ProfileOption>,
 }
 
-#[derive(Clone, Debug, Display, Deref)]
+impl ProfileSelector {
+    /// Returns the selected profile, if one is selected other than "none"
+    fn active(&self) -> Option<&Profile> {
+        self.selected.as_ref().and_then(|p| p.entity.as_ref())
+    }
+}
+
+/// An entry in the profile selector. The entry without a profile clears the active profile.
+#[derive(Clone, Debug, Display)]
 #[display("{}", name)]
 pub struct ProfileOption {
-    #[deref]
-    entity: Profile,
+    entity: Option<Profile>,
     name: String,
 }
+
+impl ProfileOption {
+    fn new(profile: Profile) -> entities::Result<Self> {
+        Ok(Self {
+            name: profile.name()?,
+            entity: Some(profile),
+        })
+    }
+
+    fn none() -> Self {
+        Self {
+            entity: None,
+            name: t!("main_top-bar_no-profile"),
+        }
+    }
+}
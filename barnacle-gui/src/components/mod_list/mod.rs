@@ -1,42 +1,157 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
 use crate::{
-    components::mod_list::state::{ContextMenuState, SortColumn, SortState},
+    components::mod_list::state::{
+        ColumnLayout, ColumnSetting, ContextMenuState, ListColumn, SortColumn, SortState,
+    },
     config::Cfg,
+    errors::Reaction,
+    icons::icon,
+    list_diff::ListDiff,
+    loadable::{empty, error, loading},
+    task,
 };
 use barnacle_lib::{
     Repository,
-    repository::{Profile, entities::ModEntry},
+    repository::{
+        Profile,
+        entities::{self, ModEntry},
+    },
 };
+use fluent_i18n::t;
 use iced::{
-    Element, Length, Point, Task,
-    widget::{button, checkbox, column, row, scrollable, table, text},
+    Element, Event, Length, Point, Subscription, Task, event,
+    keyboard::{self, key::Named},
+    widget::{
+        Id, button, checkbox, column, container, operation, row, scrollable,
+        scrollable::AbsoluteOffset, space, table, text, tooltip,
+    },
 };
-use iced_aw::Spinner;
+use rfd::AsyncFileDialog;
 use sweeten::widget::mouse_area;
-use tokio::task::spawn_blocking;
 
 pub mod state;
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    StateChanged(State),
+    /// The list was loaded for the given profile
+    StateChanged(Option<Profile>, State),
+    Scrolled(AbsoluteOffset),
     SortChanged(SortColumn),
     ClickedOutContextMenu,
     ToggleModEntry(ModEntry, bool),
+    ToggleLocked(ModEntry, bool),
+    /// Make the mod's files writable so they can be edited in place, if set
+    ToggleEditable(ModEntry, bool),
     ModEntryRightClicked(ModEntry, Point),
     ModEntryDeleted(ModEntry),
+    KeyPressed(Key),
+    HeaderRightClicked,
+    ColumnChooserClosed,
+    ColumnToggled(ListColumn, bool),
+    /// Move a column one place, later if set
+    ColumnMoved(ListColumn, bool),
+    ReinstallPressed(ModEntry),
+    /// The mod's source is gone too, so a new one has to be picked
+    ReinstallSourceMissing(ModEntry),
+    ReinstallSourcePicked(ModEntry, Option<PathBuf>),
+    Reinstalled(ModEntry),
+    ReinstallFailed(ModEntry, Reaction),
+}
+
+/// Keys used to navigate the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    /// Toggle whether the focused entry is enabled
+    Toggle,
+    /// Show or hide the notes of the focused entry
+    Notes,
 }
 
 #[derive(Debug)]
 pub enum Action {
     None,
     Run(Task<Message>),
+    /// An operation on an entry failed, and the parent has to react to it
+    Failed(Reaction),
 }
 
 #[derive(Debug, Clone)]
 pub enum State {
     Loading,
     Error(String),
-    Loaded(Vec<ModEntry>),
+    /// There's no active profile to show the mods of
+    NoProfile,
+    Loaded(Vec<ModRow>),
+}
+
+/// Snapshot of a [`ModEntry`] as it's displayed, so the view doesn't have to hit the database and
+/// refreshes can tell which rows changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModRow {
+    pub entry: ModEntry,
+    pub name: String,
+    pub category: String,
+    pub version: Option<String>,
+    /// Bytes the mod takes up in the library
+    pub size: u64,
+    pub enabled: bool,
+    pub locked: bool,
+    pub notes: String,
+    /// Whether the mod's files were moved or deleted outside of Barnacle
+    pub missing: bool,
+    /// Whether the mod's files are left writable
+    pub editable: bool,
+    /// Files other enabled mods ship as well
+    pub conflicts: Vec<FileConflict>,
+}
+
+/// A file more than one enabled mod ships, from the point of view of one of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileConflict {
+    pub path: PathBuf,
+    /// Name of the mod providing the file instead, or `None` if this mod wins
+    pub overridden_by: Option<String>,
+}
+
+impl ModRow {
+    /// Load the row of `entry`, picking its conflicts out of [`Profile::conflicts`]
+    fn load(
+        entry: ModEntry,
+        conflicts: &BTreeMap<PathBuf, Vec<ModEntry>>,
+    ) -> entities::Result<Self> {
+        let conflicts = conflicts
+            .iter()
+            .filter(|(_, providers)| providers.contains(&entry))
+            .map(|(path, providers)| {
+                let overridden_by = match providers.last() {
+                    Some(winner) if winner != &entry => Some(winner.name()?),
+                    _ => None,
+                };
+                Ok(FileConflict {
+                    path: path.clone(),
+                    overridden_by,
+                })
+            })
+            .collect::<entities::Result<_>>()?;
+
+        let mod_ = entry.mod_()?;
+        Ok(Self {
+            name: entry.name()?,
+            category: entry.category()?,
+            version: mod_.version()?,
+            size: mod_.size()?,
+            enabled: entry.enabled()?,
+            locked: entry.locked()?,
+            notes: entry.notes()?,
+            missing: mod_.files_missing()?,
+            editable: mod_.editable()?,
+            conflicts,
+            entry,
+        })
+    }
 }
 
 pub struct ModList {
@@ -45,35 +160,113 @@ pub struct ModList {
     state: State,
     sort: SortState,
     context_menu: Option<ContextMenuState>,
+    scroll_id: Id,
+    scroll_offset: AbsoluteOffset,
+    /// The profile the list was last loaded for
+    profile: Option<Profile>,
+    last_diff: Option<ListDiff>,
+    /// Index of the keyboard focused row, in displayed order
+    focused_index: Option<usize>,
+    /// Whether the notes of the focused row are shown
+    show_notes: bool,
+    columns: ColumnLayout,
+    show_column_chooser: bool,
 }
 
 impl ModList {
     pub fn new(repo: Repository, cfg: Cfg) -> Self {
+        let columns = cfg.read().mod_list.columns.clone().normalized();
         Self {
             repo: repo.clone(),
             cfg,
             state: State::Loading,
             sort: SortState::default(),
             context_menu: None,
+            scroll_id: Id::unique(),
+            scroll_offset: AbsoluteOffset::default(),
+            profile: None,
+            last_diff: None,
+            focused_index: None,
+            show_notes: false,
+            columns,
+            show_column_chooser: false,
         }
     }
 
-    pub fn refresh(&self, profile: &Profile) -> Task<Message> {
-        let profile = profile.clone();
-        Task::perform(
-            async {
-                spawn_blocking(move || State::Loaded(profile.mod_entries().unwrap()))
-                    .await
-                    .unwrap()
-            },
-            Message::StateChanged,
-        )
+    /// Returns what changed in the list during the last refresh, `None` if it wasn't a refresh of
+    /// the same profile
+    pub fn last_diff(&self) -> Option<ListDiff> {
+        self.last_diff
+    }
+
+    pub fn refresh(&self, profile: Option<&Profile>) -> Task<Message> {
+        let Some(profile) = profile.cloned() else {
+            return Task::done(Message::StateChanged(None, State::NoProfile));
+        };
+        let loaded = profile.clone();
+        task::blocking(move || {
+            let conflicts = profile.conflicts()?;
+            profile
+                .mod_entries()?
+                .into_iter()
+                .map(|entry| ModRow::load(entry, &conflicts))
+                .collect()
+        })
+        .map(move |result| {
+            Message::StateChanged(
+                Some(loaded.clone()),
+                match result {
+                    Ok(rows) => State::Loaded(rows),
+                    Err(err) => State::Error(err.to_string()),
+                },
+            )
+        })
     }
 
     pub fn update(&mut self, message: Message) -> Action {
         match message {
-            Message::StateChanged(state) => {
+            Message::StateChanged(profile, state) => {
+                // Loading another profile isn't a change to the list
+                let same_profile = profile == self.profile;
+                self.profile = profile;
+                self.last_diff = None;
+
+                if let State::Loaded(rows) = &state {
+                    if let State::Loaded(previous) = &self.state
+                        && same_profile
+                    {
+                        self.last_diff =
+                            Some(ListDiff::new(previous, rows, |a, b| a.entry == b.entry));
+                    }
+
+                    // Keep the focus on the same entry if it's still there, otherwise on the
+                    // same position
+                    if let Some(index) = self.focused_index {
+                        let entry = self.focused_row().map(|r| r.entry.clone());
+                        let last = rows.len().checked_sub(1);
+                        self.focused_index = rows
+                            .iter()
+                            .position(|r| Some(&r.entry) == entry.as_ref())
+                            .or_else(|| last.map(|last| index.min(last)));
+                    }
+
+                    // Keep the context menu open unless its entry went away
+                    if let Some(menu) = &self.context_menu
+                        && !rows.iter().any(|r| r.entry == menu.entry)
+                    {
+                        self.context_menu = None;
+                    }
+                }
                 self.state = state;
+
+                // The table is rebuilt from scratch, so put the scroll position back
+                Action::Run(operation::scroll_to(
+                    self.scroll_id.clone(),
+                    self.scroll_offset,
+                ))
+            }
+            Message::Scrolled(offset) => {
+                self.scroll_offset = offset;
                 Action::None
             }
             Message::SortChanged(column) => {
@@ -87,8 +280,37 @@ impl ModList {
             }
             Message::ToggleModEntry(entry, state) => {
                 // TODO: This should be async
-                let entry = entry.clone();
-                entry.set_enabled(state).unwrap();
+                if let Err(err) = entry.set_enabled(state) {
+                    return self.failed(&entry, err.into());
+                }
+                if let Some(row) = self.row_mut(&entry) {
+                    row.enabled = state;
+                }
+
+                // Enabling or disabling a mod changes who wins its conflicts
+                match entry.parent() {
+                    Ok(profile) => Action::Run(self.refresh(Some(&profile))),
+                    Err(err) => self.failed(&entry, err.into()),
+                }
+            }
+            Message::ToggleLocked(entry, locked) => {
+                // TODO: This should be async
+                if let Err(err) = entry.set_locked(locked) {
+                    return self.failed(&entry, err.into());
+                }
+                if let Some(row) = self.row_mut(&entry) {
+                    row.locked = locked;
+                }
+                Action::None
+            }
+            Message::ToggleEditable(entry, editable) => {
+                // TODO: This should be async
+                if let Err(err) = entry.mod_().and_then(|m| m.set_editable(editable)) {
+                    return self.failed(&entry, err.into());
+                }
+                if let Some(row) = self.row_mut(&entry) {
+                    row.editable = editable;
+                }
                 Action::None
             }
             Message::ModEntryRightClicked(entry, position) => {
@@ -100,49 +322,435 @@ impl ModList {
                 // entry.remove().unwrap();
                 Action::None
             }
+            Message::KeyPressed(key) => self.key_pressed(key),
+            Message::HeaderRightClicked => {
+                self.show_column_chooser = true;
+                Action::None
+            }
+            Message::ColumnChooserClosed => {
+                self.show_column_chooser = false;
+                Action::None
+            }
+            Message::ColumnToggled(column, visible) => {
+                self.columns.set_visible(column, visible);
+                self.save_columns();
+                Action::None
+            }
+            Message::ColumnMoved(column, later) => {
+                self.columns.move_column(column, later);
+                self.save_columns();
+                Action::None
+            }
+            Message::ReinstallPressed(entry) => Action::Run(reinstall(entry, None)),
+            Message::ReinstallSourceMissing(entry) => Action::Run(Task::perform(
+                async {
+                    AsyncFileDialog::new()
+                        .add_filter("Archives", &["zip", "7z", "rar"])
+                        .add_filter("All Files", &["*"])
+                        .pick_file()
+                        .await
+                        .map(|file| file.path().to_path_buf())
+                },
+                move |path| Message::ReinstallSourcePicked(entry.clone(), path),
+            )),
+            Message::ReinstallSourcePicked(entry, Some(path)) => {
+                Action::Run(reinstall(entry, Some(path)))
+            }
+            Message::ReinstallSourcePicked(_, None) => Action::None,
+            Message::Reinstalled(entry) => {
+                if let Some(row) = self.row_mut(&entry) {
+                    row.missing = false;
+                }
+                Action::None
+            }
+            Message::ReinstallFailed(entry, reaction) => self.failed(&entry, reaction),
         }
     }
 
+    /// Keyboard events the list reacts to. Keys already handled by a focused widget, like a text
+    /// input, are left alone.
+    pub fn subscription(&self) -> Subscription<Message> {
+        event::listen_with(|event, status, _window| {
+            if status == event::Status::Captured {
+                return None;
+            }
+
+            let Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(named),
+                ..
+            }) = event
+            else {
+                return None;
+            };
+
+            let key = match named {
+                Named::ArrowUp => Key::Up,
+                Named::ArrowDown => Key::Down,
+                Named::Space => Key::Toggle,
+                Named::Enter => Key::Notes,
+                _ => return None,
+            };
+            Some(Message::KeyPressed(key))
+        })
+    }
+
+    fn key_pressed(&mut self, key: Key) -> Action {
+        let State::Loaded(rows) = &self.state else {
+            return Action::None;
+        };
+        let Some(last) = rows.len().checked_sub(1) else {
+            return Action::None;
+        };
+
+        match key {
+            Key::Up => {
+                self.focused_index = Some(self.focused_index.map_or(last, |i| i.saturating_sub(1)));
+                self.show_notes = false;
+                Action::None
+            }
+            Key::Down => {
+                self.focused_index = Some(self.focused_index.map_or(0, |i| (i + 1).min(last)));
+                self.show_notes = false;
+                Action::None
+            }
+            Key::Toggle => match self.focused_row() {
+                Some(row) if !row.locked => {
+                    let message = Message::ToggleModEntry(row.entry.clone(), !row.enabled);
+                    self.update(message)
+                }
+                _ => Action::None,
+            },
+            Key::Notes => {
+                self.show_notes = self.focused_index.is_some() && !self.show_notes;
+                Action::None
+            }
+        }
+    }
+
+    fn save_columns(&self) {
+        let mut cfg = self.cfg.write();
+        cfg.mod_list.columns = self.columns.clone();
+        cfg.save();
+    }
+
+    fn focused_row(&self) -> Option<&ModRow> {
+        match &self.state {
+            State::Loaded(rows) => self.focused_index.and_then(|i| rows.get(i)),
+            _ => None,
+        }
+    }
+
+    fn row_mut(&mut self, entry: &ModEntry) -> Option<&mut ModRow> {
+        match &mut self.state {
+            State::Loaded(rows) => rows.iter_mut().find(|r| &r.entry == entry),
+            _ => None,
+        }
+    }
+
+    /// Handle a failed operation on `entry`, dropping its row if it no longer exists
+    fn failed(&mut self, entry: &ModEntry, reaction: Reaction) -> Action {
+        if reaction == Reaction::Removed
+            && let State::Loaded(rows) = &mut self.state
+        {
+            rows.retain(|r| &r.entry != entry);
+            if self
+                .context_menu
+                .as_ref()
+                .is_some_and(|m| &m.entry == entry)
+            {
+                self.context_menu = None;
+            }
+        }
+
+        Action::Failed(reaction)
+    }
+
+    /// Header of `column`, sorting the list by `sort` when pressed. Right clicking any header
+    /// opens the column chooser.
+    fn column_header(&self, column: ListColumn, sort: Option<SortColumn>) -> Element<'_, Message> {
+        let header: Element<'_, Message> = match sort {
+            Some(sort) => button(row![text(column.label()), self.sort.icon(sort)])
+                .style(button::subtle)
+                .on_press(Message::SortChanged(sort))
+                .into(),
+            None => text(column.label()).into(),
+        };
+
+        mouse_area(header)
+            .on_right_press(|_| Message::HeaderRightClicked)
+            .into()
+    }
+
+    /// Lets the columns be shown, hidden and reordered
+    fn column_chooser(&self) -> Element<'_, Message> {
+        let title_bar = row![
+            text(t!("main_mod-list_columns")),
+            space::horizontal(),
+            button(icon("close")).on_press(Message::ColumnChooserClosed)
+        ];
+
+        container(
+            column![title_bar]
+                .extend(
+                    self.columns
+                        .columns()
+                        .iter()
+                        .copied()
+                        .map(column_chooser_row),
+                )
+                .spacing(4),
+        )
+        .padding(8)
+        .width(Length::Fill)
+        .style(container::rounded_box)
+        .into()
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
         match &self.state {
-            State::Loading => Spinner::new().into(),
-            State::Error(e) => text(e).into(),
-            State::Loaded(mod_entries) => {
-                let columns = [
-                    table::column(
-                        column_header("Name", &self.sort, SortColumn::Name),
-                        |entry: ModEntry| {
-                            mouse_area(text(entry.name().unwrap())).on_right_press(move |point| {
-                                Message::ModEntryRightClicked(entry.clone(), point)
-                            })
+            State::Loading => loading(),
+            State::Error(e) => error(e),
+            State::NoProfile => empty(t!("no-profile")),
+            State::Loaded(rows) if rows.is_empty() => empty(t!("main_mod-list_empty")),
+            State::Loaded(rows) => {
+                let focused_index = self.focused_index;
+                let columns = self.columns.visible().map(|column| match column {
+                    ListColumn::Name => table::column(
+                        self.column_header(column, Some(SortColumn::Name)),
+                        move |(index, row): (usize, ModRow)| -> Element<'_, Message> {
+                            let entry = row.entry;
+                            let name = container(text(row.name)).padding([0, 4]);
+                            let name = if focused_index == Some(index) {
+                                name.style(container::primary)
+                            } else {
+                                name
+                            };
+                            let name: Element<'_, Message> = if row.conflicts.is_empty() {
+                                name.into()
+                            } else {
+                                tooltip(
+                                    name,
+                                    conflicts_popover(&row.conflicts),
+                                    tooltip::Position::FollowCursor,
+                                )
+                                .into()
+                            };
+
+                            mouse_area(name)
+                                .on_right_press(move |point| {
+                                    Message::ModEntryRightClicked(entry.clone(), point)
+                                })
+                                .into()
+                        },
+                    ),
+                    ListColumn::Category => table::column(
+                        self.column_header(column, Some(SortColumn::Category)),
+                        |(_, row): (usize, ModRow)| -> Element<'_, Message> {
+                            text(row.category).into()
+                        },
+                    ),
+                    ListColumn::Version => table::column(
+                        self.column_header(column, None),
+                        |(_, row): (usize, ModRow)| -> Element<'_, Message> {
+                            text(row.version.unwrap_or_default()).into()
+                        },
+                    ),
+                    ListColumn::Size => table::column(
+                        self.column_header(column, None),
+                        |(_, row): (usize, ModRow)| -> Element<'_, Message> {
+                            text(format_size(row.size)).into()
+                        },
+                    ),
+                    ListColumn::Notes => table::column(
+                        self.column_header(column, None),
+                        |(_, row): (usize, ModRow)| -> Element<'_, Message> {
+                            if row.notes.is_empty() {
+                                return space::horizontal().into();
+                            }
+
+                            tooltip(
+                                icon("note"),
+                                container(text(row.notes))
+                                    .padding(8)
+                                    .max_width(400)
+                                    .style(container::rounded_box),
+                                tooltip::Position::FollowCursor,
+                            )
+                            .into()
                         },
                     ),
-                    table::column(
-                        column_header("Cateogry", &self.sort, SortColumn::Category),
-                        |entry: ModEntry| text("Category"),
+                    ListColumn::Status => table::column(
+                        self.column_header(column, None),
+                        |(_, row): (usize, ModRow)| -> Element<'_, Message> {
+                            let entry = row.entry.clone();
+                            // Locked entries can't be toggled
+                            let toggle = (!row.locked).then_some(move |state| {
+                                Message::ToggleModEntry(entry.clone(), state)
+                            });
+                            let lock_style = if row.locked {
+                                button::primary
+                            } else {
+                                button::subtle
+                            };
+                            let edit_style = if row.editable {
+                                button::primary
+                            } else {
+                                button::subtle
+                            };
+
+                            let reinstall_button = row.missing.then(|| {
+                                tooltip(
+                                    button(text(t!("main_mod-list_reinstall")))
+                                        .style(button::danger)
+                                        .on_press(Message::ReinstallPressed(row.entry.clone())),
+                                    container(text(t!("main_mod-list_files-missing")))
+                                        .padding(8)
+                                        .style(container::rounded_box),
+                                    tooltip::Position::FollowCursor,
+                                )
+                            });
+
+                            row![
+                                checkbox(row.enabled).on_toggle_maybe(toggle),
+                                button(icon("lock")).style(lock_style).on_press(
+                                    Message::ToggleLocked(row.entry.clone(), !row.locked)
+                                ),
+                                tooltip(
+                                    button(icon("edit")).style(edit_style).on_press(
+                                        Message::ToggleEditable(row.entry, !row.editable)
+                                    ),
+                                    container(text(t!("main_mod-list_editable")))
+                                        .padding(8)
+                                        .style(container::rounded_box),
+                                    tooltip::Position::FollowCursor,
+                                ),
+                                reinstall_button,
+                            ]
+                            .into()
+                        },
                     ),
-                    table::column(text("Status"), |entry: ModEntry| {
-                        checkbox(entry.enabled().unwrap())
-                            .on_toggle(move |state| Message::ToggleModEntry(entry.clone(), state))
-                    }),
-                ];
-
-                column![scrollable(
-                    table(columns, mod_entries.clone()).width(Length::Fill)
-                )]
+                });
+
+                let notes = self.focused_row().filter(|_| self.show_notes).map(|row| {
+                    let notes = if row.notes.is_empty() {
+                        t!("main_mod-list_no-notes")
+                    } else {
+                        row.notes.clone()
+                    };
+                    container(text(notes))
+                        .padding(8)
+                        .width(Length::Fill)
+                        .style(container::rounded_box)
+                });
+
+                column![
+                    self.show_column_chooser.then(|| self.column_chooser()),
+                    scrollable(
+                        table(columns, rows.iter().cloned().enumerate()).width(Length::Fill)
+                    )
+                    .id(self.scroll_id.clone())
+                    .on_scroll(|viewport| Message::Scrolled(viewport.absolute_offset())),
+                    notes,
+                ]
                 .into()
             }
         }
     }
 }
 
-fn column_header<'a>(
-    name: &'a str,
-    sort_state: &'a SortState,
-    column: SortColumn,
-) -> Element<'a, Message> {
-    button(row![text(name), sort_state.icon(column)])
-        .style(button::subtle)
-        .on_press(Message::SortChanged(column))
-        .into()
+/// Reinstall the mod of `entry` from `path`, or from where it was installed from. If that's gone
+/// as well, asks for a new source.
+fn reinstall(entry: ModEntry, path: Option<PathBuf>) -> Task<Message> {
+    let mod_entry = entry.clone();
+    task::blocking(move || mod_entry.mod_()?.reinstall(path.as_deref())).map(move |result| {
+        match result {
+            Ok(()) => Message::Reinstalled(entry.clone()),
+            Err(entities::Error::MissingSource) => Message::ReinstallSourceMissing(entry.clone()),
+            Err(err) => Message::ReinstallFailed(entry.clone(), err.into()),
+        }
+    })
+}
+
+/// How many conflicting files the popover lists before summarizing the rest
+const MAX_CONFLICTS_SHOWN: usize = 20;
+
+/// Lists whether a mod wins or loses each of its conflicting files, and to which mod
+fn conflicts_popover(conflicts: &[FileConflict]) -> Element<'static, Message> {
+    let lines = conflicts
+        .iter()
+        .take(MAX_CONFLICTS_SHOWN)
+        .map(conflict_line);
+    let hidden = conflicts.len().saturating_sub(MAX_CONFLICTS_SHOWN);
+
+    container(
+        column![text(
+            t!("main_mod-list_conflicts", { "count" => conflicts.len() })
+        )]
+        .extend(lines)
+        .push((hidden > 0).then(|| {
+            text(t!("main_mod-list_conflicts-more", { "count" => hidden })).style(text::secondary)
+        }))
+        .spacing(4),
+    )
+    .padding(8)
+    .max_width(600)
+    .style(container::rounded_box)
+    .into()
+}
+
+fn conflict_line(conflict: &FileConflict) -> Element<'static, Message> {
+    let path = conflict.path.display().to_string();
+    match &conflict.overridden_by {
+        None => text(t!("main_mod-list_conflict-wins", { "path" => path.as_str() }))
+            .style(text::success)
+            .into(),
+        Some(winner) => text(t!("main_mod-list_conflict-loses", {
+            "path" => path.as_str(),
+            "winner" => winner.as_str()
+        }))
+        .style(text::danger)
+        .into(),
+    }
+}
+
+/// Format `bytes` for display, like `1.5 MiB`
+fn format_size(bytes: u64) -> String {
+    // Scaled in tenths of a unit to keep one decimal without floats
+    let mut tenths = bytes.saturating_mul(10);
+    let mut unit = "B";
+    for next in ["KiB", "MiB", "GiB", "TiB"] {
+        if tenths < 10240 {
+            break;
+        }
+        tenths = tenths.checked_div(1024).unwrap_or_default();
+        unit = next;
+    }
+
+    if unit == "B" {
+        return format!("{bytes} B");
+    }
+    let whole = tenths.checked_div(10).unwrap_or_default();
+    let tenth = tenths.checked_rem(10).unwrap_or_default();
+    format!("{whole}.{tenth} {unit}")
+}
+
+fn column_chooser_row(setting: ColumnSetting) -> Element<'static, Message> {
+    let column = setting.column;
+    let toggle = column
+        .hideable()
+        .then_some(move |visible| Message::ColumnToggled(column, visible));
+
+    row![
+        checkbox(setting.visible)
+            .label(column.label())
+            .on_toggle_maybe(toggle),
+        space::horizontal(),
+        button(icon("arrow_up"))
+            .style(button::subtle)
+            .on_press(Message::ColumnMoved(column, false)),
+        button(icon("arrow_down"))
+            .style(button::subtle)
+            .on_press(Message::ColumnMoved(column, true)),
+    ]
+    .into()
 }
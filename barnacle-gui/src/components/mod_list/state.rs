@@ -75,3 +75,125 @@ impl Default for SortState {
         }
     }
 }
+
+/// A column of the mod list table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListColumn {
+    Name,
+    Category,
+    Version,
+    Notes,
+    Status,
+    Size,
+}
+
+impl ListColumn {
+    pub const ALL: [ListColumn; 6] = [
+        ListColumn::Name,
+        ListColumn::Category,
+        ListColumn::Version,
+        ListColumn::Notes,
+        ListColumn::Status,
+        ListColumn::Size,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ListColumn::Name => "Name",
+            ListColumn::Category => "Category",
+            ListColumn::Version => "Version",
+            ListColumn::Notes => "Notes",
+            ListColumn::Status => "Status",
+            ListColumn::Size => "Size",
+        }
+    }
+
+    /// Whether the column can be hidden. The name column can't, since that's where entries are
+    /// right clicked and focused.
+    pub fn hideable(&self) -> bool {
+        *self != ListColumn::Name
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnSetting {
+    pub column: ListColumn,
+    pub visible: bool,
+}
+
+/// Which columns of the mod list are shown, and in what order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnLayout {
+    columns: Vec<ColumnSetting>,
+}
+
+impl ColumnLayout {
+    /// Returns every column in order, along with whether it's shown
+    pub fn columns(&self) -> &[ColumnSetting] {
+        &self.columns
+    }
+
+    /// Returns the shown columns in order
+    pub fn visible(&self) -> impl Iterator<Item = ListColumn> + use<'_> {
+        self.columns.iter().filter(|s| s.visible).map(|s| s.column)
+    }
+
+    pub fn set_visible(&mut self, column: ListColumn, visible: bool) {
+        if let Some(setting) = self.columns.iter_mut().find(|s| s.column == column)
+            && column.hideable()
+        {
+            setting.visible = visible;
+        }
+    }
+
+    /// Swap `column` with the one before it, or after it if `later` is set
+    pub fn move_column(&mut self, column: ListColumn, later: bool) {
+        let Some(index) = self.columns.iter().position(|s| s.column == column) else {
+            return;
+        };
+        let other = if later {
+            index.checked_add(1)
+        } else {
+            index.checked_sub(1)
+        };
+
+        if let Some(other) = other.filter(|&i| i < self.columns.len()) {
+            self.columns.swap(index, other);
+        }
+    }
+
+    /// Drop duplicate columns and add any missing ones, like after a column is added in a new
+    /// version
+    pub fn normalized(mut self) -> Self {
+        let mut seen = Vec::new();
+        self.columns.retain(|s| {
+            let first = !seen.contains(&s.column);
+            seen.push(s.column);
+            first
+        });
+
+        for column in ListColumn::ALL {
+            if !seen.contains(&column) {
+                self.columns.push(ColumnSetting {
+                    column,
+                    visible: true,
+                });
+            }
+        }
+
+        for setting in &mut self.columns {
+            setting.visible |= !setting.column.hideable();
+        }
+
+        self
+    }
+}
+
+impl Default for ColumnLayout {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+        }
+        .normalized()
+    }
+}
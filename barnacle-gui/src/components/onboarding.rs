@@ -0,0 +1,188 @@
+use barnacle_lib::{
+    Repository,
+    repository::{DeployKind, DetectedGame, Game},
+};
+use fluent_i18n::t;
+use iced::{
+    Element, Length, Task,
+    widget::{button, column, container, row, space, text},
+};
+use strum::IntoEnumIterator;
+use tracing::warn;
+
+use crate::{
+    components::library_manager::{
+        new_game_dialog::{self, NewGame},
+        profiles_tab::new_dialog::{self, NewProfile},
+    },
+    task,
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SkipPressed,
+    /// The scan for installed games finished
+    GamesDetected(Vec<DetectedGame>),
+    DetectedGamePressed(DetectedGame),
+    // Components
+    NewGameDialog(new_game_dialog::Message),
+    NewProfileDialog(new_dialog::Message),
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    CreateGame(NewGame),
+    AddDetectedGame(DetectedGame),
+    CreateProfile {
+        game: Game,
+        new_profile: NewProfile,
+    },
+    /// Onboarding is over, show the normal view
+    Finish,
+}
+
+/// Where the user is in the onboarding flow
+#[derive(Debug, Clone)]
+enum Step {
+    Game,
+    Profile(Game),
+}
+
+/// First run panel shown while the library is empty. Walks the user through adding their first
+/// game, offering the installed ones it finds, then a profile for it.
+pub struct Onboarding {
+    step: Step,
+    /// Installed games that can be added in one click
+    detected: Vec<DetectedGame>,
+    // Components
+    new_game_dialog: new_game_dialog::Dialog,
+    new_profile_dialog: new_dialog::NewDialog,
+}
+
+impl Onboarding {
+    pub fn new(repo: &Repository) -> (Self, Task<Message>) {
+        let (new_game_dialog, new_game_dialog_task) = new_game_dialog::Dialog::new();
+        let (new_profile_dialog, new_profile_dialog_task) = new_dialog::NewDialog::new();
+
+        (
+            Self {
+                step: Step::Game,
+                detected: Vec::new(),
+                new_game_dialog,
+                new_profile_dialog,
+            },
+            Task::batch([
+                new_game_dialog_task.map(Message::NewGameDialog),
+                new_profile_dialog_task.map(Message::NewProfileDialog),
+                task::run_repo(repo, |repo| repo.scan_for_games()).map(|result| {
+                    Message::GamesDetected(result.unwrap_or_else(|err| {
+                        warn!("Failed to scan for installed games: {err}");
+                        Vec::new()
+                    }))
+                }),
+            ]),
+        )
+    }
+
+    /// Move on to adding a profile once the first game has been added
+    pub fn game_added(&mut self, game: Game) {
+        self.step = Step::Profile(game);
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::SkipPressed => Action::Finish,
+            Message::GamesDetected(detected) => {
+                self.detected = detected;
+                Action::None
+            }
+            Message::DetectedGamePressed(detected) => Action::AddDetectedGame(detected),
+            Message::NewGameDialog(message) => match self.new_game_dialog.update(message) {
+                new_game_dialog::Action::None | new_game_dialog::Action::Cancel => Action::None,
+                new_game_dialog::Action::Run(task) => Action::Run(task.map(Message::NewGameDialog)),
+                new_game_dialog::Action::CreateGame(new_game) => Action::CreateGame(new_game),
+            },
+            Message::NewProfileDialog(message) => match self.new_profile_dialog.update(message) {
+                new_dialog::Action::None => Action::None,
+                new_dialog::Action::Run(task) => Action::Run(task.map(Message::NewProfileDialog)),
+                // There's nothing to go back to, skipping is the way out
+                new_dialog::Action::Cancel => Action::Finish,
+                new_dialog::Action::Create(new_profile) => match &self.step {
+                    Step::Profile(game) => Action::CreateProfile {
+                        game: game.clone(),
+                        new_profile,
+                    },
+                    Step::Game => Action::None,
+                },
+            },
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let content = match &self.step {
+            Step::Game => column![
+                text(t!("onboarding_welcome")).size(24),
+                text(t!("onboarding_add-game")),
+                self.detected_games(),
+                deploy_kinds(),
+                self.new_game_dialog.view().map(Message::NewGameDialog),
+            ],
+            Step::Profile(_) => column![
+                text(t!("onboarding_add-profile")),
+                self.new_profile_dialog
+                    .view()
+                    .map(Message::NewProfileDialog),
+                row![
+                    space::horizontal(),
+                    button(text(t!("onboarding_skip")))
+                        .style(button::subtle)
+                        .on_press(Message::SkipPressed),
+                ],
+            ],
+        }
+        .spacing(12)
+        .max_width(600);
+
+        container(content).center(Length::Fill).padding(20).into()
+    }
+
+    /// A button for each installed game that was found, if any were
+    fn detected_games(&self) -> Option<Element<'_, Message>> {
+        if self.detected.is_empty() {
+            return None;
+        }
+
+        let games = column![text(t!("onboarding_detected"))]
+            .extend(self.detected.iter().map(|detected| {
+                button(text(format!(
+                    "{} ({})",
+                    detected.name,
+                    detected.path.display()
+                )))
+                .style(button::secondary)
+                .on_press(Message::DetectedGamePressed(detected.clone()))
+                .into()
+            }))
+            .spacing(4);
+
+        Some(games.into())
+    }
+}
+
+/// Short explanation of each deploy kind, to help pick one for the first game
+fn deploy_kinds<'a>() -> Element<'a, Message> {
+    column(DeployKind::iter().map(|kind| {
+        let description = match kind {
+            DeployKind::Overlay => t!("onboarding_deploy-kind_overlay"),
+            DeployKind::Gamebryo => t!("onboarding_deploy-kind_gamebryo"),
+            DeployKind::CreationEngine => t!("onboarding_deploy-kind_creation-engine"),
+            DeployKind::OpenMW => t!("onboarding_deploy-kind_openmw"),
+            DeployKind::BaldursGate3 => t!("onboarding_deploy-kind_baldurs-gate-3"),
+        };
+        row![text(kind.to_string()).width(160), text(description)].into()
+    }))
+    .spacing(4)
+    .into()
+}
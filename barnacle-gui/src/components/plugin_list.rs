@@ -0,0 +1,148 @@
+use crate::{
+    errors::Reaction,
+    icons::icon,
+    loadable::{empty, error, loading},
+    task,
+};
+use barnacle_lib::repository::Profile;
+use fluent_i18n::t;
+use iced::{
+    Element, Length, Task,
+    widget::{Column, button, column, container, row, rule, scrollable, space, text},
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    StateChanged(State),
+    MoveUp(usize),
+    MoveDown(usize),
+    OrderSaved,
+    Failed(Reaction),
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// An operation failed, and the parent has to react to it
+    Failed(Reaction),
+}
+
+#[derive(Debug, Clone)]
+pub enum State {
+    Loading,
+    Error(String),
+    /// There's no active profile to show the plugins of
+    NoProfile,
+    Loaded {
+        profile: Profile,
+        plugins: Vec<String>,
+    },
+}
+
+/// Panel for rearranging a profile's plugin load order, independently of its mod order
+pub struct PluginList {
+    state: State,
+}
+
+impl PluginList {
+    pub fn new() -> Self {
+        Self {
+            state: State::Loading,
+        }
+    }
+
+    pub fn refresh(&self, profile: Option<&Profile>) -> Task<Message> {
+        let Some(profile) = profile.cloned() else {
+            return Task::done(Message::StateChanged(State::NoProfile));
+        };
+        task::blocking(move || {
+            let plugins = profile.plugin_order()?;
+            Ok(State::Loaded { profile, plugins })
+        })
+        .map(|result| {
+            Message::StateChanged(match result {
+                Ok(state) => state,
+                Err(err) => State::Error(err.to_string()),
+            })
+        })
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::StateChanged(state) => {
+                self.state = state;
+                Action::None
+            }
+            Message::MoveUp(index) => match index.checked_sub(1) {
+                Some(above) => self.swap(above, index),
+                None => Action::None,
+            },
+            Message::MoveDown(index) => self.swap(index, index + 1),
+            Message::OrderSaved => Action::None,
+            Message::Failed(reaction) => Action::Failed(reaction),
+        }
+    }
+
+    /// Swap two plugins and persist the new order
+    fn swap(&mut self, a: usize, b: usize) -> Action {
+        let State::Loaded { profile, plugins } = &mut self.state else {
+            return Action::None;
+        };
+        if a >= plugins.len() || b >= plugins.len() {
+            return Action::None;
+        }
+
+        plugins.swap(a, b);
+
+        let profile = profile.clone();
+        let plugins = plugins.clone();
+        Action::Run(
+            task::blocking(move || profile.set_plugin_order(&plugins)).map(|result| match result {
+                Ok(()) => Message::OrderSaved,
+                Err(err) => Message::Failed(err.into()),
+            }),
+        )
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let content: Element<'_, Message> = match &self.state {
+            State::Loading => loading(),
+            State::Error(e) => error(e),
+            State::NoProfile => empty(t!("no-profile")),
+            State::Loaded { plugins, .. } if plugins.is_empty() => empty(t!("no-plugins")),
+            State::Loaded { plugins, .. } => {
+                let last = plugins.len().saturating_sub(1);
+                let rows = plugins.iter().enumerate().map(|(i, plugin)| {
+                    row![
+                        text(plugin),
+                        space::horizontal(),
+                        button(icon("arrow_up"))
+                            .style(button::subtle)
+                            .on_press_maybe((i > 0).then_some(Message::MoveUp(i))),
+                        button(icon("arrow_down"))
+                            .style(button::subtle)
+                            .on_press_maybe((i < last).then_some(Message::MoveDown(i))),
+                    ]
+                    .into()
+                });
+
+                scrollable(Column::with_children(rows)).into()
+            }
+        };
+
+        container(column![
+            text(t!("plugin", { "count" => 2 })),
+            rule::horizontal(1),
+            content
+        ])
+        .width(Length::Fill)
+        .into()
+    }
+}
+
+impl Default for PluginList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,106 @@
+use std::process::Command;
+
+use barnacle_lib::{Repository, logging};
+use fluent_i18n::t;
+use iced::{
+    Element,
+    widget::{button, checkbox, column, container, row, space, text},
+};
+
+use crate::icons::icon;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    EnableNewModsToggled(bool),
+    EnforceReadonlyToggled(bool),
+    OpenLogButtonPressed,
+    CloseButtonPressed,
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Close,
+}
+
+/// Library wide settings
+pub struct Settings {
+    repo: Repository,
+    enable_new_mods: bool,
+    enforce_readonly: bool,
+    /// Why the log file couldn't be opened
+    log_error: Option<String>,
+}
+
+impl Settings {
+    pub fn new(repo: Repository) -> Self {
+        Self {
+            enable_new_mods: repo.enable_new_mods_by_default(),
+            enforce_readonly: repo.enforce_readonly_mods(),
+            repo,
+            log_error: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::EnableNewModsToggled(enabled) => {
+                self.repo.set_enable_new_mods_by_default(enabled);
+                self.enable_new_mods = enabled;
+                Action::None
+            }
+            Message::EnforceReadonlyToggled(enforce) => {
+                self.repo.set_enforce_readonly_mods(enforce);
+                self.enforce_readonly = enforce;
+                Action::None
+            }
+            Message::OpenLogButtonPressed => {
+                self.log_error = match logging::current_log_file() {
+                    Some(path) => Command::new("xdg-open")
+                        .arg(path)
+                        .spawn()
+                        .err()
+                        .map(|err| err.to_string()),
+                    None => Some(t!("main_settings_no-log")),
+                };
+                Action::None
+            }
+            Message::CloseButtonPressed => Action::Close,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title_bar = row![
+            text(t!("main_settings_title")),
+            space::horizontal(),
+            button(icon("close")).on_press(Message::CloseButtonPressed)
+        ];
+
+        container(
+            column![
+                title_bar,
+                checkbox(self.enable_new_mods)
+                    .label(t!("main_settings_enable-new-mods"))
+                    .on_toggle(Message::EnableNewModsToggled),
+                column![
+                    checkbox(self.enforce_readonly)
+                        .label(t!("main_settings_enforce-readonly"))
+                        .on_toggle(Message::EnforceReadonlyToggled),
+                    text(t!("main_settings_enforce-readonly-hint")).style(text::secondary),
+                ]
+                .spacing(4),
+                row![
+                    button(text(t!("main_settings_open-log")))
+                        .on_press(Message::OpenLogButtonPressed),
+                    self.log_error.as_deref().map(text),
+                ]
+                .spacing(8),
+            ]
+            .spacing(8),
+        )
+        .padding(20)
+        .width(500)
+        .style(container::rounded_box)
+        .into()
+    }
+}
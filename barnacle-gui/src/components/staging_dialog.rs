@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+use barnacle_lib::repository::StagedMod;
+use fluent_i18n::t;
+use iced::{
+    Element, Length,
+    widget::{button, column, container, row, scrollable, space, text},
+};
+
+use crate::{errors::Reaction, icons::icon};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DataRootSelected(PathBuf),
+    InstallButtonPressed,
+    DiscardButtonPressed,
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Commit(StagedMod),
+    Discard(StagedMod),
+    Failed(Reaction),
+}
+
+/// Shows the files of a staged archive, so the directory to install from can be picked before
+/// they're installed
+pub struct StagingDialog {
+    staged: StagedMod,
+    directories: Vec<PathBuf>,
+    /// Files under the current data root
+    files: Vec<PathBuf>,
+}
+
+impl StagingDialog {
+    pub fn new(staged: StagedMod) -> Result<Self, Reaction> {
+        Ok(Self {
+            directories: staged.directories()?,
+            files: staged.files()?,
+            staged,
+        })
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::DataRootSelected(data_root) => {
+                let result = self
+                    .staged
+                    .set_data_root(&data_root)
+                    .and_then(|()| self.staged.files());
+                match result {
+                    Ok(files) => {
+                        self.files = files;
+                        Action::None
+                    }
+                    Err(err) => Action::Failed(err.into()),
+                }
+            }
+            Message::InstallButtonPressed => Action::Commit(self.staged.clone()),
+            Message::DiscardButtonPressed => Action::Discard(self.staged.clone()),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title_bar = row![
+            text(t!("main_staging_title")),
+            space::horizontal(),
+            button(icon("close")).on_press(Message::DiscardButtonPressed)
+        ];
+
+        let data_root = self.staged.data_root();
+        let directories = column(
+            std::iter::once(Path::new(""))
+                .chain(self.directories.iter().map(PathBuf::as_path))
+                .map(|dir| {
+                    let label = if dir.as_os_str().is_empty() {
+                        t!("main_staging_everything")
+                    } else {
+                        dir.display().to_string()
+                    };
+                    let style = if dir == data_root {
+                        button::primary
+                    } else {
+                        button::subtle
+                    };
+
+                    button(text(label))
+                        .style(style)
+                        .on_press(Message::DataRootSelected(dir.to_path_buf()))
+                        .into()
+                }),
+        );
+
+        let files = column(
+            self.files
+                .iter()
+                .map(|file| text(file.display().to_string()).into()),
+        );
+
+        container(
+            column![
+                title_bar,
+                text(t!("main_staging_data-root")),
+                scrollable(directories).height(Length::FillPortion(1)),
+                text(t!("main_staging_files", { "count" => self.files.len() })),
+                scrollable(files).height(Length::FillPortion(2)),
+                row![
+                    space::horizontal(),
+                    button(text(t!("main_staging_discard")))
+                        .on_press(Message::DiscardButtonPressed),
+                    button(text(t!("main_staging_install"))).on_press_maybe(
+                        (!self.files.is_empty()).then_some(Message::InstallButtonPressed)
+                    ),
+                ]
+                .spacing(8),
+            ]
+            .spacing(8),
+        )
+        .padding(20)
+        .width(600)
+        .height(600)
+        .style(container::rounded_box)
+        .into()
+    }
+}
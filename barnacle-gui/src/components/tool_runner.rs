@@ -0,0 +1,299 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    sync::Arc,
+    time::Duration,
+};
+
+use barnacle_lib::{
+    Repository,
+    repository::{Launch, Tool, entities},
+};
+use fluent_i18n::t;
+use iced::{
+    Element, Length, Task,
+    futures::{SinkExt, Stream, channel::mpsc},
+    stream,
+    widget::{button, column, container, row, scrollable, space, text},
+};
+use parking_lot::Mutex;
+use tokio::{sync::mpsc::unbounded_channel, task::spawn_blocking, time::sleep};
+
+use crate::{
+    icons::icon,
+    loadable::{empty, error, loading},
+    task,
+};
+
+/// How many lines of output are kept before the oldest are dropped
+const MAX_LINES: usize = 5000;
+/// How often a tool whose output has ended is checked for having exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    StateChanged(State),
+    RunButtonPressed(Tool),
+    KillButtonPressed,
+    CloseButtonPressed,
+    Run(Event),
+}
+
+#[derive(Debug)]
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum State {
+    Loading,
+    Error(String),
+    /// There's no active game to run the tools of
+    NoGame,
+    Loaded(Vec<ToolRow>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolRow {
+    tool: Tool,
+    name: String,
+}
+
+/// What happened to a running tool
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The launch is waiting for a deployment of the game to finish
+    Deploying,
+    Started(Handle),
+    Line(String),
+    Exited(String),
+    Failed(String),
+}
+
+/// Handle to a running tool, used to kill it
+#[derive(Debug, Clone)]
+pub struct Handle(Arc<Mutex<Launch>>);
+
+/// Runs the active game's tools and shows their output as it comes in
+pub struct ToolRunner {
+    state: State,
+    running: Option<Handle>,
+    /// Whether a launch is waiting for a deployment to finish
+    deploying: bool,
+    output: Vec<String>,
+    /// How the last run ended
+    status: Option<String>,
+}
+
+impl ToolRunner {
+    pub fn new() -> Self {
+        Self {
+            state: State::Loading,
+            running: None,
+            deploying: false,
+            output: Vec::new(),
+            status: None,
+        }
+    }
+
+    /// Load the tools of the active game
+    pub fn refresh(&self, repo: &Repository) -> Task<Message> {
+        task::run_repo(repo, |repo| {
+            let Some(game) = repo.active_game()? else {
+                return Ok(State::NoGame);
+            };
+
+            game.tools()?
+                .into_iter()
+                .map(|tool| {
+                    Ok(ToolRow {
+                        name: tool.name()?,
+                        tool,
+                    })
+                })
+                .collect::<entities::Result<_>>()
+                .map(State::Loaded)
+        })
+        .map(|result| {
+            Message::StateChanged(match result {
+                Ok(state) => state,
+                Err(err) => State::Error(err.to_string()),
+            })
+        })
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::StateChanged(state) => {
+                self.state = state;
+                Action::None
+            }
+            Message::RunButtonPressed(tool) => {
+                self.output.clear();
+                self.status = None;
+                Action::Run(Task::run(run(tool), Message::Run))
+            }
+            Message::KillButtonPressed => {
+                if let Some(Handle(launch)) = &self.running
+                    && let Err(err) = launch.lock().child_mut().kill()
+                {
+                    self.status = Some(err.to_string());
+                }
+                Action::None
+            }
+            Message::CloseButtonPressed => Action::Close,
+            Message::Run(event) => {
+                match event {
+                    Event::Deploying => self.deploying = true,
+                    Event::Started(handle) => {
+                        self.deploying = false;
+                        self.running = Some(handle);
+                    }
+                    Event::Line(line) => {
+                        self.output.push(line);
+                        let excess = self.output.len().saturating_sub(MAX_LINES);
+                        self.output.drain(..excess);
+                    }
+                    Event::Exited(status) | Event::Failed(status) => {
+                        self.deploying = false;
+                        self.running = None;
+                        self.status = Some(status);
+                    }
+                }
+                Action::None
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title_bar = row![
+            text(t!("tool", { "count" => 2 })),
+            space::horizontal(),
+            button(icon("close")).on_press(Message::CloseButtonPressed)
+        ];
+
+        let tools: Element<'_, Message> = match &self.state {
+            State::Loading => loading(),
+            State::Error(e) => error(e),
+            State::NoGame => empty(t!("main_tools_no-game")),
+            State::Loaded(rows) if rows.is_empty() => empty(t!("main_tools_no-tools")),
+            State::Loaded(rows) => column(rows.iter().map(|row| {
+                row![
+                    text(&row.name),
+                    space::horizontal(),
+                    button(icon("play")).on_press_maybe(
+                        (self.running.is_none() && !self.deploying)
+                            .then(|| Message::RunButtonPressed(row.tool.clone()))
+                    ),
+                ]
+                .into()
+            }))
+            .spacing(4)
+            .into(),
+        };
+
+        let status: Element<'_, Message> = match (&self.running, &self.status) {
+            (None, _) if self.deploying => text(t!("main_tools_deploying")).into(),
+            (Some(_), _) => row![
+                text(t!("main_tools_running")),
+                space::horizontal(),
+                button(text(t!("main_tools_kill")))
+                    .style(button::danger)
+                    .on_press(Message::KillButtonPressed),
+            ]
+            .into(),
+            (None, Some(status)) => text(status).into(),
+            (None, None) => space::horizontal().into(),
+        };
+
+        let output = scrollable(column(self.output.iter().map(|line| text(line).into())))
+            .anchor_bottom()
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        container(column![title_bar, tools, output, status].spacing(8))
+            .padding(20)
+            .width(800)
+            .height(600)
+            .style(container::rounded_box)
+            .into()
+    }
+}
+
+/// Launch `tool`, streaming its stdout and stderr line by line and how it exited. The launch
+/// counts towards the active profile's play time, and waits for any deployment of the game to
+/// finish first.
+fn run(tool: Tool) -> impl Stream<Item = Event> {
+    stream::channel(100, |mut output: mpsc::Sender<Event>| async move {
+        let parent = spawn_blocking(move || tool.parent().map(|game| (game, tool)))
+            .await
+            .expect("blocking task shouldn't panic");
+        let (game, tool) = match parent {
+            Ok(parent) => parent,
+            Err(err) => {
+                let _ = output.send(Event::Failed(err.to_string())).await;
+                return;
+            }
+        };
+        if game.is_deploying() {
+            let _ = output.send(Event::Deploying).await;
+        }
+
+        let spawned = spawn_blocking(move || game.launch_captured(&tool))
+            .await
+            .expect("blocking task shouldn't panic");
+        let mut launch = match spawned {
+            Ok(launch) => launch,
+            Err(err) => {
+                let _ = output.send(Event::Failed(err.to_string())).await;
+                return;
+            }
+        };
+
+        // Both pipes are read on their own thread so neither can fill up and stall the tool
+        let (lines_tx, mut lines_rx) = unbounded_channel();
+        let child = launch.child_mut();
+        let pipes: [Option<Box<dyn Read + Send>>; 2] = [
+            child.stdout.take().map(|p| Box::new(p) as _),
+            child.stderr.take().map(|p| Box::new(p) as _),
+        ];
+        for pipe in pipes.into_iter().flatten() {
+            let lines_tx = lines_tx.clone();
+            spawn_blocking(move || {
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    if lines_tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(lines_tx);
+
+        let launch = Arc::new(Mutex::new(launch));
+        let _ = output.send(Event::Started(Handle(launch.clone()))).await;
+
+        while let Some(line) = lines_rx.recv().await {
+            let _ = output.send(Event::Line(line)).await;
+        }
+
+        // The pipes usually close when the tool exits, but a process it started can keep them
+        // open. Polling leaves the lock free for the kill button in the meantime.
+        let status = loop {
+            let polled = launch.lock().try_wait();
+            match polled {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => sleep(EXIT_POLL_INTERVAL).await,
+                Err(err) => break Err(err),
+            }
+        };
+        let event = match status {
+            Ok(status) => {
+                let status = status.to_string();
+                Event::Exited(t!("main_tools_exited", { "status" => status.as_str() }))
+            }
+            Err(err) => Event::Failed(err.to_string()),
+        };
+        let _ = output.send(event).await;
+    })
+}
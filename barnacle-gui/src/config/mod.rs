@@ -3,8 +3,12 @@ use std::{fs, sync::Arc};
 use barnacle_lib::fs::config_dir;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
-use crate::{components::mod_list::state::SortState, config::theme::Theme};
+use crate::{
+    components::mod_list::state::{ColumnLayout, SortState},
+    config::theme::Theme,
+};
 
 mod theme;
 
@@ -24,15 +28,25 @@ pub struct GuiConfig {
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ModList {
     pub sort_state: SortState,
+    #[serde(default)]
+    pub columns: ColumnLayout,
 }
 
 impl GuiConfig {
     pub fn load() -> Self {
-        let path = config_dir().join(FILE_NAME);
+        let path = match config_dir() {
+            Ok(dir) => dir.join(FILE_NAME),
+            Err(err) => {
+                error!("Failed to find the configuration directory: {err}");
+                return Self::default();
+            }
+        };
 
         if path.exists() {
-            let contents = fs::read_to_string(path).unwrap();
-            toml::from_str(&contents).unwrap_or_default()
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|contents| toml::from_str(&contents).ok())
+                .unwrap_or_default()
         } else {
             let cfg = Self::default();
             cfg.save();
@@ -43,10 +57,9 @@ impl GuiConfig {
     pub fn save(&self) {
         let contents = toml::to_string_pretty(self).unwrap();
 
-        // Make sure config_dir exists
-        fs::create_dir_all(config_dir()).unwrap();
-
-        fs::write(config_dir().join(FILE_NAME), contents).unwrap();
+        if let Err(err) = config_dir().and_then(|dir| fs::write(dir.join(FILE_NAME), contents)) {
+            error!("Failed to save the GUI configuration: {err}");
+        }
     }
 
     pub fn theme(&self) -> iced::Theme {
@@ -0,0 +1,42 @@
+//! How the GUI reacts to errors coming out of the library.
+//!
+//! Entities can be removed from under the GUI mid-session, e.g. by the CLI or by deleting the
+//! parent game. Operations on them then fail with [`Error::RemovedEntity`], which isn't worth
+//! showing as an error: the row is dropped, the user gets a short notice, and the views reload.
+
+use barnacle_lib::repository::entities::Error;
+use fluent_i18n::t;
+
+/// What the GUI should do about a failed operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reaction {
+    /// The entity the operation was on no longer exists. Drop it from local state and refresh.
+    Removed,
+    /// Anything else, which is shown to the user as is
+    Report(String),
+}
+
+impl Reaction {
+    /// The message shown to the user in a toast
+    pub fn message(&self) -> String {
+        match self {
+            Reaction::Removed => t!("item-removed"),
+            Reaction::Report(message) => message.clone(),
+        }
+    }
+}
+
+impl From<&Error> for Reaction {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::RemovedEntity => Reaction::Removed,
+            other => Reaction::Report(other.to_string()),
+        }
+    }
+}
+
+impl From<Error> for Reaction {
+    fn from(err: Error) -> Self {
+        Reaction::from(&err)
+    }
+}
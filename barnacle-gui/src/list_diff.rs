@@ -0,0 +1,37 @@
+//! Comparing snapshots of a list, so refreshes can tell what actually changed.
+
+/// Summary of the differences between two snapshots of a list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListDiff {
+    pub added: usize,
+    pub removed: usize,
+    /// Items present in both snapshots whose contents differ
+    pub changed: usize,
+}
+
+impl ListDiff {
+    /// Compare the `old` and `new` snapshots. Items are matched up with `same_item`, and matched
+    /// items are compared with [`PartialEq`] to find the changed ones.
+    pub fn new<T: PartialEq>(old: &[T], new: &[T], same_item: impl Fn(&T, &T) -> bool) -> Self {
+        let mut diff = Self::default();
+
+        for item in new {
+            match old.iter().find(|o| same_item(o, item)) {
+                Some(previous) if previous != item => diff.changed += 1,
+                Some(_) => {}
+                None => diff.added += 1,
+            }
+        }
+
+        diff.removed = old
+            .iter()
+            .filter(|o| !new.iter().any(|n| same_item(o, n)))
+            .count();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.changed == 0
+    }
+}
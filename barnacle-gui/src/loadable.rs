@@ -0,0 +1,79 @@
+//! State that's loaded in the background, rendered the same way by every component.
+//!
+//! Components whose state is nothing but loading, failed or loaded use [`Loadable`] directly.
+//! The ones with extra states, like having no active profile, keep their own enum but render the
+//! shared ones with [`loading`], [`error`] and [`empty`].
+
+use barnacle_lib::repository::entities;
+use iced::{
+    Element, Length,
+    widget::{container, text},
+};
+use iced_aw::Spinner;
+
+#[derive(Debug, Clone)]
+pub enum Loadable<T> {
+    Loading,
+    /// Loading failed, with the message to show
+    Error(String),
+    Loaded(T),
+}
+
+impl<T> Loadable<T> {
+    /// Render the loaded value with `view`, or the loading or error state in its place
+    pub fn view<'a, Message: 'a>(
+        &'a self,
+        view: impl FnOnce(&'a T) -> Element<'a, Message>,
+    ) -> Element<'a, Message> {
+        match self {
+            Loadable::Loading => loading(),
+            Loadable::Error(message) => error(message),
+            Loadable::Loaded(value) => view(value),
+        }
+    }
+}
+
+impl<T> Loadable<Vec<T>> {
+    /// Like [`Loadable::view`], but shows `empty_message` if nothing was loaded
+    pub fn view_list<'a, Message: 'a>(
+        &'a self,
+        empty_message: String,
+        view: impl FnOnce(&'a [T]) -> Element<'a, Message>,
+    ) -> Element<'a, Message> {
+        self.view(|items| {
+            if items.is_empty() {
+                empty(empty_message)
+            } else {
+                view(items.as_slice())
+            }
+        })
+    }
+}
+
+impl<T> From<entities::Result<T>> for Loadable<T> {
+    fn from(result: entities::Result<T>) -> Self {
+        match result {
+            Ok(value) => Loadable::Loaded(value),
+            Err(err) => Loadable::Error(err.to_string()),
+        }
+    }
+}
+
+/// Shown while the state is being loaded
+pub fn loading<'a, Message: 'a>() -> Element<'a, Message> {
+    placeholder(Spinner::new())
+}
+
+/// Shown when loading failed, with why
+pub fn error<'a, Message: 'a>(message: &'a str) -> Element<'a, Message> {
+    placeholder(text(message).style(text::danger))
+}
+
+/// Shown when there's nothing to display, with a hint about why
+pub fn empty<'a, Message: 'a>(message: String) -> Element<'a, Message> {
+    placeholder(text(message).style(text::secondary))
+}
+
+fn placeholder<'a, Message: 'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(content).padding(8).center_x(Length::Fill).into()
+}
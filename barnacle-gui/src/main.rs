@@ -1,3 +1,6 @@
+use std::process;
+
+use barnacle_lib::{Repository, logging};
 use fluent_i18n::i18n;
 use iced::{
     Color, Element,
@@ -6,30 +9,39 @@ use iced::{
     widget::{center, container, mouse_area, opaque, stack},
     window::Settings,
 };
-use tracing::Level;
-use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use tracing::{error, level_filters::LevelFilter};
 
 use crate::components::App;
 
 pub mod components;
 pub mod config;
+pub mod errors;
 pub mod icons;
+pub mod list_diff;
+pub mod loadable;
+pub mod task;
 
 i18n!("locales", fallback = "en-US");
 
 fn main() -> iced::Result {
     human_panic::setup_panic!();
 
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::TRACE)
-        .with_env_filter(EnvFilter::from_default_env())
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    // Kept alive so the log file is flushed on exit
+    let _log_guard = logging::init(LevelFilter::ERROR);
+
+    let repo = match Repository::new() {
+        Ok(repo) => repo,
+        Err(err) => {
+            error!("Failed to open the library: {err}");
+            process::exit(1)
+        }
+    };
 
     let mut settings = Settings::default();
     settings.platform_specific.application_id = App::TITLE.to_string();
 
-    application(App::new, App::update, App::view)
+    application(move || App::new(repo.clone()), App::update, App::view)
+        .subscription(App::subscription)
         .theme(App::theme)
         .title(App::title)
         .window(settings)
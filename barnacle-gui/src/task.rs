@@ -0,0 +1,32 @@
+//! Running library calls off the UI thread.
+//!
+//! Library calls block on the database and the filesystem, so they're run on tokio's blocking
+//! pool. The resulting task resolves to the call's [`Result`], which callers turn into a message,
+//! so a failure ends up in the UI instead of panicking the update loop.
+
+use barnacle_lib::{Repository, repository::entities};
+use iced::Task;
+use tokio::task::spawn_blocking;
+
+/// Run the blocking `f` in the background
+pub fn blocking<T, F>(f: F) -> Task<entities::Result<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> entities::Result<T> + Send + 'static,
+{
+    Task::future(async {
+        spawn_blocking(f)
+            .await
+            .expect("blocking task shouldn't panic")
+    })
+}
+
+/// Run the blocking `f` against its own handle to `repo` in the background
+pub fn run_repo<T, F>(repo: &Repository, f: F) -> Task<entities::Result<T>>
+where
+    T: Send + 'static,
+    F: FnOnce(Repository) -> entities::Result<T> + Send + 'static,
+{
+    let repo = repo.clone();
+    blocking(move || f(repo))
+}
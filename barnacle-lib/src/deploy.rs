@@ -0,0 +1,615 @@
+//! Linking a profile's mod files into a game's target directories.
+//!
+//! Every deployment is recorded in a manifest stored in the game directory. The manifest is what
+//! makes deployments reversible: only the links it lists are ever removed, so real game files are
+//! never touched. It's also compared against the next deployment so only the difference has to be
+//! applied. Game files a link would replace are moved aside and recorded in the manifest too, so
+//! they can be put back when the link is removed.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, ErrorKind},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use parking_lot::{Mutex, ReentrantMutex};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    overlay,
+    repository::{
+        LinkStrategy,
+        entities::{Error, Result},
+    },
+};
+
+pub(crate) const MANIFEST_FILE: &str = "deployment.toml";
+/// File of the game directory describing the deployed profile to other tools, see
+/// [`LoadoutManifest`]
+pub(crate) const LOADOUT_FILE: &str = "loadout.json";
+/// Directory of the game directory that game files replaced by links are moved into
+pub(crate) const VANILLA_DIR: &str = "vanilla";
+
+/// Per game locks, keyed by the game's UID. See [`lock`]. Each opened database has its own, shared
+/// by all of its handles, since UIDs are only unique within one database.
+pub(crate) type Locks = Arc<Mutex<HashMap<u64, Arc<ReentrantMutex<()>>>>>;
+
+/// Returns the lock of `locks` held while the game with the UID `game` is being deployed to.
+///
+/// Deploying and launching the game's tools both take it, so a tool never starts with a half
+/// deployed profile. It's reentrant so a deployment can be made up of other deployments, like
+/// [`Profile::redeploy`](crate::repository::Profile::redeploy) is.
+pub(crate) fn lock(locks: &Locks, game: u64) -> Arc<ReentrantMutex<()>> {
+    locks.lock().entry(game).or_default().clone()
+}
+
+/// Record of a deployed profile
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// UID of the deployed profile
+    pub profile: u64,
+    /// The directories the files were linked into
+    pub targets: Vec<PathBuf>,
+    /// Maps each deployed path, relative to the targets, to the file it links to
+    pub files: BTreeMap<String, DeployedFile>,
+    /// [`Profile::loadout_hash`](crate::repository::Profile::loadout_hash) of the profile when it
+    /// was deployed, in hex
+    #[serde(default)]
+    pub loadout: Option<String>,
+    /// Game files that were moved aside to make room for links
+    #[serde(default)]
+    pub vanilla: Vec<VanillaFile>,
+    /// How the files were linked
+    #[serde(default)]
+    pub link_strategy: LinkStrategy,
+    /// Whether the profile was deployed as an overlay mounted over each target rather than with
+    /// links, see [`crate::overlay`]
+    #[serde(default)]
+    pub overlay: bool,
+}
+
+/// Outward facing description of what's deployed, written as JSON for launch wrappers and other
+/// integrations to read. Unlike [`Manifest`], Barnacle never reads it back. Its schema is
+/// documented on [`Game::deployment_manifest_path`](crate::repository::Game::deployment_manifest_path).
+#[derive(Debug, Serialize)]
+pub(crate) struct LoadoutManifest {
+    pub version: u32,
+    pub game: String,
+    pub profile: String,
+    pub mods: Vec<LoadoutMod>,
+    pub plugins: Vec<String>,
+}
+
+/// A mod listed in a [`LoadoutManifest`]
+#[derive(Debug, Serialize)]
+pub(crate) struct LoadoutMod {
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub category: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+impl LoadoutManifest {
+    pub const VERSION: u32 = 1;
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+}
+
+/// A game file replaced by a link, kept aside until the link is removed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct VanillaFile {
+    /// Where the file was, in one of the targets
+    pub original: PathBuf,
+    /// Where it's kept in the meantime
+    pub backup: PathBuf,
+}
+
+/// A file linked into the targets by a deployment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DeployedFile {
+    pub source: PathBuf,
+    /// Size and modification time of the source, used to tell whether it changed between
+    /// deployments without reading it
+    pub size: u64,
+    pub modified: u64,
+}
+
+impl DeployedFile {
+    pub fn new(source: PathBuf) -> io::Result<Self> {
+        let metadata = fs::metadata(&source)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        Ok(Self {
+            source,
+            size: metadata.len(),
+            modified: u64::try_from(modified).unwrap_or(u64::MAX),
+        })
+    }
+}
+
+/// The links that have to change to get from one [`Manifest`] to another
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Delta {
+    pub removed: Vec<String>,
+    pub added: Vec<(String, PathBuf)>,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Load the manifest at `path`, if there is one
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        toml::from_str(&fs::read_to_string(path)?)
+            .map(Some)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    /// Work out which links have to be removed and created to turn this deployment into `next`.
+    /// A file that now comes from somewhere else, or whose source has been modified, is both
+    /// removed and added.
+    pub fn diff(&self, next: &Manifest) -> Delta {
+        let mut delta = Delta::default();
+
+        for (path, file) in &self.files {
+            if next.files.get(path) != Some(file) {
+                delta.removed.push(path.clone());
+            }
+        }
+
+        for (path, file) in &next.files {
+            if self.files.get(path) != Some(file) {
+                delta.added.push((path.clone(), file.source.clone()));
+            }
+        }
+
+        delta
+    }
+}
+
+/// Resolve `targets` to canonical paths before deploying into them.
+///
+/// Game directories are often symlinks or junctions into another drive. Following them up front
+/// means links are made in the real directory, the symlink itself is never replaced, and two
+/// targets that lead to the same directory are only deployed to once. A target that resolves to
+/// somewhere inside `library_dir` is refused, as deploying there would link mod files into the
+/// library itself.
+pub(crate) fn resolve_targets(targets: &[PathBuf], library_dir: &Path) -> Result<Vec<PathBuf>> {
+    let library_dir = library_dir.canonicalize()?;
+
+    let mut resolved: Vec<PathBuf> = Vec::new();
+    for target in targets {
+        let canonical = target
+            .canonicalize()
+            .map_err(|err| Error::UnresolvableTarget(target.clone(), err))?;
+        if canonical.starts_with(&library_dir) {
+            return Err(Error::TargetInLibrary(target.clone()));
+        }
+
+        if !resolved.contains(&canonical) {
+            resolved.push(canonical);
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Apply `delta` to the deployment `deployed` records, turning it into `next`. `deployed` is kept
+/// up to date as each link is removed or added, so if one fails it still records what's in the
+/// targets, and can be saved for the next deployment or undeployment to carry on from.
+pub(crate) fn apply(
+    deployed: &mut Manifest,
+    next: &Manifest,
+    delta: &Delta,
+    backup_dir: &Path,
+) -> io::Result<()> {
+    for path in &delta.removed {
+        unlink(
+            &deployed.targets,
+            Path::new(path),
+            &mut deployed.vanilla,
+            deployed.link_strategy,
+        )?;
+        deployed.files.remove(path);
+    }
+
+    for (path, source) in &delta.added {
+        let replaced = link(
+            &deployed.targets,
+            Path::new(path),
+            source,
+            backup_dir,
+            deployed.link_strategy,
+        )?;
+        deployed.vanilla.extend(replaced);
+        if let Some(file) = next.files.get(path) {
+            deployed.files.insert(path.clone(), file.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Link `source` to `relative` in each of `targets` with `strategy`, creating any missing parent
+/// directories. A symlink already occupying the destination is replaced. A game file occupying it
+/// is moved into `backup_dir` instead, and returned so [`unlink`] can put it back.
+///
+/// If linking into one of the targets fails, the links already made are removed and the game
+/// files put back, so either every target is linked or none is.
+pub(crate) fn link(
+    targets: &[PathBuf],
+    relative: &Path,
+    source: &Path,
+    backup_dir: &Path,
+    strategy: LinkStrategy,
+) -> io::Result<Vec<VanillaFile>> {
+    let mut vanilla = Vec::new();
+    let mut linked = Vec::new();
+    for (index, target) in targets.iter().enumerate() {
+        let dest = target.join(relative);
+        let backup = backup_dir.join(index.to_string()).join(relative);
+        if let Err(err) = link_one(&dest, source, &backup, strategy, &mut vanilla) {
+            for dest in linked {
+                if let Err(err) = fs::remove_file(&dest) {
+                    warn!("Failed to remove link {}: {err}", dest.display());
+                }
+            }
+            for file in vanilla {
+                if let Err(err) = move_file(&file.backup, &file.original) {
+                    warn!(
+                        "Failed to put back game file {}: {err}",
+                        file.original.display()
+                    );
+                }
+            }
+            return Err(err);
+        }
+        linked.push(dest);
+    }
+
+    Ok(vanilla)
+}
+
+/// Link `source` to `dest` for [`link`], moving a game file in the way to `backup` and recording
+/// it in `vanilla`
+fn link_one(
+    dest: &Path,
+    source: &Path,
+    backup: &Path,
+    strategy: LinkStrategy,
+    vanilla: &mut Vec<VanillaFile>,
+) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if dest.is_symlink() {
+        fs::remove_file(dest)?;
+    } else if dest.is_file() {
+        move_file(dest, backup)?;
+        vanilla.push(VanillaFile {
+            original: dest.to_path_buf(),
+            backup: backup.to_path_buf(),
+        });
+    }
+
+    match strategy {
+        LinkStrategy::Symlink => symlink(source, dest),
+        LinkStrategy::Hardlink => hard_link_or_copy(source, dest),
+    }
+}
+
+/// Hard link `source` to `dest`, or copy it if they're on different filesystems, which hard links
+/// can't span
+fn hard_link_or_copy(source: &Path, dest: &Path) -> io::Result<()> {
+    match fs::hard_link(source, dest) {
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            warn!(
+                "Can't hard link {} across filesystems, copying it instead",
+                source.display()
+            );
+            fs::copy(source, dest)?;
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Remove the link made with `strategy` at `relative` in each of `targets`, putting back the game
+/// file it replaced if it's in `vanilla`, or otherwise removing any directories left empty.
+/// Anything that isn't the kind of file `strategy` makes is left alone.
+pub(crate) fn unlink(
+    targets: &[PathBuf],
+    relative: &Path,
+    vanilla: &mut Vec<VanillaFile>,
+    strategy: LinkStrategy,
+) -> io::Result<()> {
+    for target in targets {
+        let dest = target.join(relative);
+        let linked = match strategy {
+            LinkStrategy::Symlink => dest.is_symlink(),
+            // Hard links and copies look like any other file, only the manifest tells them apart
+            LinkStrategy::Hardlink => !dest.is_symlink() && dest.is_file(),
+        };
+        if !linked {
+            continue;
+        }
+
+        fs::remove_file(&dest)?;
+        match vanilla.iter().position(|v| v.original == dest) {
+            Some(index) => {
+                let file = vanilla.remove(index);
+                move_file(&file.backup, &file.original)?;
+                // The backup is at `<backup dir>/<target index>/<relative>`
+                if let Some(backup_dir) = file.backup.ancestors().nth(relative.iter().count() + 1) {
+                    remove_empty_parents(&file.backup, backup_dir);
+                }
+            }
+            None => remove_empty_parents(&dest, target),
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove every link recorded in `manifest`, putting back the game files they replaced, or
+/// unmount its overlays
+pub(crate) fn undeploy(manifest: &Manifest) -> io::Result<()> {
+    if manifest.overlay {
+        for target in &manifest.targets {
+            overlay::unmount(target)?;
+        }
+        return Ok(());
+    }
+
+    let mut vanilla = manifest.vanilla.clone();
+    for path in manifest.files.keys() {
+        unlink(
+            &manifest.targets,
+            Path::new(path),
+            &mut vanilla,
+            manifest.link_strategy,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Move the file at `from` to `to`, creating any missing parent directories. Falls back to
+/// copying when the two are on different filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+
+    Ok(())
+}
+
+/// Remove the parent directories of `path` up to, but not including, `root` for as long as
+/// they're empty
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn file(source: &str, size: u64) -> DeployedFile {
+        DeployedFile {
+            source: PathBuf::from(source),
+            size,
+            modified: 0,
+        }
+    }
+
+    #[test]
+    fn test_resolve_targets() {
+        let tmp = tempdir().unwrap();
+        let library = tmp.path().join("library");
+        let game = tmp.path().join("drive/skyrim");
+        let link = tmp.path().join("skyrim");
+        fs::create_dir_all(&library).unwrap();
+        fs::create_dir_all(&game).unwrap();
+        symlink(&game, &link).unwrap();
+
+        let game = game.canonicalize().unwrap();
+        // Both lead to the same directory, which is only deployed to once
+        assert_eq!(
+            resolve_targets(&[link.clone(), game.clone()], &library).unwrap(),
+            vec![game]
+        );
+
+        assert!(matches!(
+            resolve_targets(&[tmp.path().join("missing")], &library),
+            Err(Error::UnresolvableTarget(..))
+        ));
+
+        let into_library = tmp.path().join("into_library");
+        symlink(&library, &into_library).unwrap();
+        assert!(matches!(
+            resolve_targets(&[into_library], &library),
+            Err(Error::TargetInLibrary(..))
+        ));
+    }
+
+    #[test]
+    fn test_link_keeps_vanilla_files() {
+        let tmp = tempdir().unwrap();
+        let target = tmp.path().join("game");
+        let backup_dir = tmp.path().join("vanilla");
+        let source = tmp.path().join("armor.nif");
+        fs::create_dir_all(target.join("meshes")).unwrap();
+        fs::write(target.join("meshes/armor.nif"), "vanilla").unwrap();
+        fs::write(&source, "mod").unwrap();
+
+        let targets = [target.clone()];
+        let relative = Path::new("meshes/armor.nif");
+        let mut vanilla = link(
+            &targets,
+            relative,
+            &source,
+            &backup_dir,
+            LinkStrategy::Symlink,
+        )
+        .unwrap();
+        assert_eq!(
+            vanilla,
+            vec![VanillaFile {
+                original: target.join(relative),
+                backup: backup_dir.join("0").join(relative),
+            }]
+        );
+        assert_eq!(fs::read_to_string(target.join(relative)).unwrap(), "mod");
+
+        unlink(&targets, relative, &mut vanilla, LinkStrategy::Symlink).unwrap();
+        assert!(vanilla.is_empty());
+        assert!(!target.join(relative).is_symlink());
+        assert_eq!(
+            fs::read_to_string(target.join(relative)).unwrap(),
+            "vanilla"
+        );
+        // The emptied backup directories are cleaned up
+        assert!(!backup_dir.join("0").exists());
+    }
+
+    #[test]
+    fn test_link_replaces_dangling_symlink() {
+        let tmp = tempdir().unwrap();
+        let target = tmp.path().join("game");
+        let backup_dir = tmp.path().join("vanilla");
+        let source = tmp.path().join("armor.nif");
+        fs::write(&source, "mod").unwrap();
+
+        // Left behind by a deployment whose source has since been removed
+        let relative = Path::new("meshes/armor/iron.nif");
+        fs::create_dir_all(target.join("meshes/armor")).unwrap();
+        symlink(tmp.path().join("gone.nif"), target.join(relative)).unwrap();
+
+        let targets = [target.clone()];
+        let mut vanilla = link(
+            &targets,
+            relative,
+            &source,
+            &backup_dir,
+            LinkStrategy::Symlink,
+        )
+        .unwrap();
+        assert!(vanilla.is_empty());
+        assert_eq!(fs::read_link(target.join(relative)).unwrap(), source);
+
+        // Missing parent directories are created, and removed again once empty
+        let nested = Path::new("textures/armor/iron/iron.dds");
+        link(
+            &targets,
+            nested,
+            &source,
+            &backup_dir,
+            LinkStrategy::Symlink,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(target.join(nested)).unwrap(), "mod");
+
+        unlink(&targets, nested, &mut vanilla, LinkStrategy::Symlink).unwrap();
+        assert!(!target.join("textures").exists());
+        assert!(!backup_dir.exists());
+    }
+
+    #[test]
+    fn test_link_rolls_back() {
+        let tmp = tempdir().unwrap();
+        let first = tmp.path().join("first");
+        let second = tmp.path().join("second");
+        let backup_dir = tmp.path().join("vanilla");
+        let source = tmp.path().join("armor.nif");
+        let relative = Path::new("meshes/armor.nif");
+        fs::create_dir_all(first.join("meshes")).unwrap();
+        fs::write(first.join(relative), "vanilla").unwrap();
+        // A directory in the way can't be replaced by a link
+        fs::create_dir_all(second.join(relative).join("nested")).unwrap();
+        fs::write(&source, "mod").unwrap();
+
+        assert!(
+            link(
+                &[first.clone(), second],
+                relative,
+                &source,
+                &backup_dir,
+                LinkStrategy::Symlink,
+            )
+            .is_err()
+        );
+        // The first target is left as it was
+        assert!(!first.join(relative).is_symlink());
+        assert_eq!(fs::read_to_string(first.join(relative)).unwrap(), "vanilla");
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = Manifest {
+            files: BTreeMap::from([
+                ("a.esp".into(), file("/mods/a/a.esp", 1)),
+                ("b.esp".into(), file("/mods/b/b.esp", 1)),
+                ("c.esp".into(), file("/mods/c/c.esp", 1)),
+            ]),
+            ..Default::default()
+        };
+        let next = Manifest {
+            files: BTreeMap::from([
+                // Modified since the last deployment
+                ("a.esp".into(), file("/mods/a/a.esp", 2)),
+                ("c.esp".into(), file("/mods/c/c.esp", 1)),
+                ("d.esp".into(), file("/mods/d/d.esp", 1)),
+            ]),
+            ..Default::default()
+        };
+
+        let delta = previous.diff(&next);
+
+        assert_eq!(delta.removed, vec!["a.esp", "b.esp"]);
+        assert_eq!(
+            delta.added,
+            vec![
+                ("a.esp".into(), PathBuf::from("/mods/a/a.esp")),
+                ("d.esp".into(), PathBuf::from("/mods/d/d.esp"))
+            ]
+        );
+        assert!(next.diff(&next).is_empty());
+    }
+}
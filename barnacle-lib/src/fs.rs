@@ -1,57 +1,183 @@
 use std::{
     fs::{create_dir_all, set_permissions},
+    io,
+    os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
 };
 
+use heck::ToSnakeCase;
 use walkdir::WalkDir;
 use xdg::BaseDirectories;
 
+/// Names Windows refuses to use for a file or directory, whatever the extension
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Longest directory name, in bytes, that's produced by [`safe_dir_name`]
+const MAX_DIR_NAME_LEN: usize = 200;
+
+/// Owner write permission bit
+const OWNER_WRITE: u32 = 0o200;
+
 #[derive(PartialEq)]
 pub enum Permissions {
     ReadOnly,
-    // ReadWrite,
+    /// Writable by the owner only, rather than by everyone
+    ReadWrite,
 }
 
-pub fn change_dir_permissions(path: &Path, permissions: Permissions) {
+/// Apply `permissions` to `path` and everything under it. Stops at the first entry that can't
+/// be read or changed, leaving the entries before it changed.
+pub fn change_dir_permissions(path: &Path, permissions: Permissions) -> io::Result<()> {
     use Permissions::*;
 
     for entry in WalkDir::new(path) {
-        let mut perms = entry.as_ref().unwrap().metadata().unwrap().permissions();
-        perms.set_readonly(permissions == ReadOnly);
-        set_permissions(entry.unwrap().path(), perms).unwrap();
+        let entry = entry?;
+        let mut perms = entry.metadata()?.permissions();
+        match permissions {
+            ReadOnly => perms.set_readonly(true),
+            ReadWrite => perms.set_mode(perms.mode() | OWNER_WRITE),
+        }
+        set_permissions(entry.path(), perms)?;
     }
+
+    Ok(())
 }
 
-/// Returns the path to the Barnacle configuration directory. If it doesn't exist when this
-/// function is called, it will be created.
-pub fn config_dir() -> PathBuf {
-    let path = xdg_prefix().get_config_home().expect("$HOME must exist");
+/// Turn an entity name into the directory component it's stored under.
+///
+/// The name is converted to snake_case, and anything other than letters, digits, `_` and `-` is
+/// dropped, so unicode letters are kept but path separators and punctuation never make it
+/// through. A name with nothing left after that is encoded as `_` followed by the hex of its
+/// bytes, so it still gets a unique, non-empty directory. Reserved Windows names get a trailing
+/// `_`, and overly long names are truncated.
+///
+/// The result only depends on `name`, so the same name always maps to the same directory.
+/// Different names can map to the same directory though, which callers rule out by comparing
+/// the results for sibling entities.
+pub fn safe_dir_name(name: &str) -> String {
+    let mut dir_name: String = name
+        .to_snake_case()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
 
-    create_dir_all(&path).unwrap();
+    if dir_name.is_empty() {
+        dir_name = std::iter::once("_".to_string())
+            .chain(name.bytes().map(|b| format!("{b:02x}")))
+            .collect();
+    }
 
-    path
+    if RESERVED_NAMES.contains(&dir_name.as_str()) {
+        dir_name.push('_');
+    }
+
+    if dir_name.len() > MAX_DIR_NAME_LEN {
+        let end = (0..=MAX_DIR_NAME_LEN)
+            .rev()
+            .find(|i| dir_name.is_char_boundary(*i))
+            .unwrap_or_default();
+        dir_name.truncate(end);
+    }
+
+    dir_name
+}
+
+/// Returns the path to the Barnacle configuration directory. If it doesn't exist when this
+/// function is called, it will be created.
+pub fn config_dir() -> io::Result<PathBuf> {
+    ensure_dir(xdg_prefix().get_config_home())
 }
 
 /// Returns the path to the Barnacle data directory. If it doesn't exist when this function is
 /// called, it will be created.
-pub fn data_dir() -> PathBuf {
-    let path = xdg_prefix().get_data_home().expect("$HOME must exist");
-
-    create_dir_all(&path).unwrap();
-
-    path
+pub fn data_dir() -> io::Result<PathBuf> {
+    ensure_dir(xdg_prefix().get_data_home())
 }
 
 /// Returns the path to the Barnacle state directory. If it doesn't exist when this function is
 /// called, it will be created.
-pub fn state_dir() -> PathBuf {
-    let path = xdg_prefix().get_state_home().expect("$HOME must exist");
+pub fn state_dir() -> io::Result<PathBuf> {
+    ensure_dir(xdg_prefix().get_state_home())
+}
+
+/// Create the XDG directory at `path`, which is `None` when there's no home directory to put it
+/// in
+fn ensure_dir(path: Option<PathBuf>) -> io::Result<PathBuf> {
+    let path = path.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "$HOME isn't set"))?;
 
-    create_dir_all(&path).unwrap();
+    create_dir_all(&path)?;
 
-    path
+    Ok(path)
 }
 
 fn xdg_prefix() -> BaseDirectories {
     xdg::BaseDirectories::with_prefix("barnacle")
 }
+
+#[cfg(test)]
+mod test {
+    use std::{fs, os::unix::fs::symlink};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_change_dir_permissions() {
+        let tmp = tempdir().unwrap();
+        let file = tmp.path().join("armor.nif");
+        fs::write(&file, "armor").unwrap();
+
+        change_dir_permissions(tmp.path(), Permissions::ReadOnly).unwrap();
+        assert!(fs::metadata(&file).unwrap().permissions().readonly());
+        change_dir_permissions(tmp.path(), Permissions::ReadWrite).unwrap();
+        assert!(!fs::metadata(&file).unwrap().permissions().readonly());
+
+        // A dangling link can't be changed, even by root, since its target doesn't exist
+        symlink(tmp.path().join("missing"), tmp.path().join("link")).unwrap();
+        assert!(change_dir_permissions(tmp.path(), Permissions::ReadOnly).is_err());
+        set_permissions(tmp.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_file(tmp.path().join("link")).unwrap();
+        change_dir_permissions(tmp.path(), Permissions::ReadWrite).unwrap();
+
+        assert!(
+            change_dir_permissions(&tmp.path().join("missing"), Permissions::ReadOnly).is_err()
+        );
+    }
+
+    #[test]
+    fn test_safe_dir_name() {
+        assert_eq!(
+            safe_dir_name("Skyrim Special Edition"),
+            "skyrim_special_edition"
+        );
+        assert_eq!(safe_dir_name("Tränen der Götter"), "tränen_der_götter");
+        assert_eq!(safe_dir_name("../../etc"), "etc");
+    }
+
+    #[test]
+    fn test_safe_dir_name_empty() {
+        assert_eq!(safe_dir_name("!!!"), "_212121");
+        assert_eq!(safe_dir_name(""), "_");
+        assert_ne!(safe_dir_name("!!!"), safe_dir_name("???"));
+    }
+
+    #[test]
+    fn test_safe_dir_name_reserved() {
+        assert_eq!(safe_dir_name("CON"), "con_");
+        assert_eq!(safe_dir_name("Lpt1"), "lpt1_");
+        assert_eq!(safe_dir_name("Console"), "console");
+    }
+
+    #[test]
+    fn test_safe_dir_name_long() {
+        let name = "ö".repeat(MAX_DIR_NAME_LEN);
+        let dir_name = safe_dir_name(&name);
+
+        assert!(dir_name.len() <= MAX_DIR_NAME_LEN);
+        assert!(!dir_name.is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! Minimal INI editing, used to apply per-profile config tweaks at deploy time.
+//!
+//! Only the keys being tweaked are touched. Everything else in the file (comments, ordering,
+//! unknown sections) is preserved as-is, so the original can always be restored byte for byte
+//! from the backup taken before editing.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+const BACKUP_MANIFEST: &str = "backup.toml";
+
+/// A single INI key/value override.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IniTweak {
+    /// Path to the INI file, relative to the directory the tweaks are applied to
+    pub file: PathBuf,
+    /// The section containing the key. An empty section refers to keys before any section header.
+    pub section: String,
+    pub key: String,
+    pub value: String,
+}
+
+impl IniTweak {
+    pub fn new(file: impl Into<PathBuf>, section: &str, key: &str, value: &str) -> Self {
+        Self {
+            file: file.into(),
+            section: section.into(),
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Records which files were touched by [`apply`], so [`restore`] can undo it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Backup {
+    files: Vec<BackedUpFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackedUpFile {
+    path: PathBuf,
+    /// Whether the file existed before the tweaks were applied
+    existed: bool,
+}
+
+/// Set `key` to `value` under `section` in the given INI contents, adding the key or section if
+/// they're missing. Section and key names are matched case-insensitively.
+pub fn set_value(contents: &str, section: &str, key: &str, value: &str) -> String {
+    let newline = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let mut in_section = section.is_empty();
+    // Index just after the last meaningful line of the target section
+    let mut insert_at = in_section.then_some(0);
+    let mut existing = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            in_section = name.trim().eq_ignore_ascii_case(section);
+            if in_section {
+                insert_at = Some(i + 1);
+            }
+            continue;
+        }
+
+        if !in_section || trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+            continue;
+        }
+
+        insert_at = Some(i + 1);
+
+        if let Some((k, _)) = trimmed.split_once('=')
+            && k.trim().eq_ignore_ascii_case(key)
+        {
+            existing = Some((i, k.trim().to_string()));
+            break;
+        }
+    }
+
+    match (existing, insert_at) {
+        (Some((i, original_key)), _) => {
+            if let Some(line) = lines.get_mut(i) {
+                *line = format!("{original_key}={value}");
+            }
+        }
+        (None, Some(i)) => lines.insert(i, format!("{key}={value}")),
+        (None, None) => {
+            lines.push(format!("[{section}]"));
+            lines.push(format!("{key}={value}"));
+        }
+    }
+
+    let mut output = lines.join(newline);
+    output.push_str(newline);
+    output
+}
+
+/// Apply `tweaks` to the INI files under `root`, backing up every touched file into `backup_dir`
+/// first.
+pub(crate) fn apply(root: &Path, backup_dir: &Path, tweaks: &[IniTweak]) -> io::Result<()> {
+    let files: BTreeSet<&PathBuf> = tweaks.iter().map(|t| &t.file).collect();
+    let mut backup = Backup::default();
+
+    fs::create_dir_all(backup_dir)?;
+
+    for file in files {
+        let path = root.join(file);
+        let existed = path.exists();
+
+        let mut contents = if existed {
+            let backup_path = backup_dir.join("files").join(file);
+            if let Some(parent) = backup_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&path, backup_path)?;
+
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+
+        for tweak in tweaks.iter().filter(|t| &t.file == file) {
+            contents = set_value(&contents, &tweak.section, &tweak.key, &tweak.value);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+
+        backup.files.push(BackedUpFile {
+            path: file.clone(),
+            existed,
+        });
+    }
+
+    let manifest = toml::to_string_pretty(&backup).map_err(io::Error::other)?;
+    fs::write(backup_dir.join(BACKUP_MANIFEST), manifest)?;
+
+    Ok(())
+}
+
+/// Restore the files backed up into `backup_dir` by [`apply`]. Does nothing if there is no
+/// backup.
+pub(crate) fn restore(root: &Path, backup_dir: &Path) -> io::Result<()> {
+    let manifest_path = backup_dir.join(BACKUP_MANIFEST);
+    if !manifest_path.exists() {
+        return Ok(());
+    }
+
+    let backup: Backup = toml::from_str(&fs::read_to_string(manifest_path)?)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+    for file in backup.files {
+        let path = root.join(&file.path);
+        if file.existed {
+            fs::copy(backup_dir.join("files").join(&file.path), path)?;
+        } else if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    fs::remove_dir_all(backup_dir)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_value_existing_key() {
+        let contents = "[Display]\niSize W=1280\nbFull Screen=0\n";
+
+        assert_eq!(
+            set_value(contents, "display", "isize w", "1920"),
+            "[Display]\niSize W=1920\nbFull Screen=0\n"
+        );
+    }
+
+    #[test]
+    fn test_set_value_missing_key() {
+        let contents = "[Display]\niSize W=1280\n\n[General]\nsLanguage=ENGLISH\n";
+
+        assert_eq!(
+            set_value(contents, "Display", "iSize H", "720"),
+            "[Display]\niSize W=1280\niSize H=720\n\n[General]\nsLanguage=ENGLISH\n"
+        );
+    }
+
+    #[test]
+    fn test_set_value_missing_section() {
+        assert_eq!(
+            set_value(
+                "[General]\r\nsLanguage=ENGLISH\r\n",
+                "Display",
+                "iSize W",
+                "1920"
+            ),
+            "[General]\r\nsLanguage=ENGLISH\r\n[Display]\r\niSize W=1920\r\n"
+        );
+    }
+}
@@ -5,8 +5,15 @@ use thiserror::Error;
 use crate::repository::entities;
 
 // mod deployers;
+mod deploy;
 pub mod fs;
+pub mod ini;
+pub mod logging;
+mod overlay;
+pub mod progress;
 pub mod repository;
+#[cfg(test)]
+mod test_utils;
 
 pub use repository::Repository;
 
@@ -0,0 +1,136 @@
+//! Logging shared by the frontends.
+//!
+//! Everything is logged to stderr, filtered by the level the frontend asks for, and to a log file
+//! under the state directory, filtered by [`CoreConfig::log_level`]. Log files are rotated daily
+//! and only the last week of them is kept, so there's always a recent log to attach to a bug
+//! report.
+//!
+//! [`CoreConfig::log_level`]: crate::repository::config::CoreConfig::log_level
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::{level_filters::LevelFilter, warn};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{EnvFilter, Layer, fmt, layer::SubscriberExt};
+
+use crate::{fs::state_dir, repository::config::CoreConfig};
+
+const LOG_DIR: &str = "logs";
+const FILE_PREFIX: &str = "barnacle";
+const FILE_SUFFIX: &str = "log";
+/// How many daily log files are kept before the oldest are removed
+const MAX_LOG_FILES: usize = 7;
+
+/// Returns the directory log files are written to
+pub fn log_dir() -> io::Result<PathBuf> {
+    Ok(state_dir()?.join(LOG_DIR))
+}
+
+/// Returns the log file currently being written to, if there is one
+pub fn current_log_file() -> Option<PathBuf> {
+    latest_log_file(&log_dir().ok()?)
+}
+
+/// Install the global subscriber. Stderr gets everything up to `stderr_level`, unless `RUST_LOG`
+/// says otherwise.
+///
+/// The returned guard flushes the log file when dropped, so it has to be kept alive until the
+/// program exits. If the log directory can't be created, only stderr is logged to and `None` is
+/// returned.
+pub fn init(stderr_level: LevelFilter) -> Option<WorkerGuard> {
+    let stderr_filter = EnvFilter::builder()
+        .with_default_directive(stderr_level.into())
+        .from_env_lossy();
+    let stderr_layer = fmt::layer()
+        .with_writer(io::stderr)
+        .with_filter(stderr_filter);
+
+    let file_level = CoreConfig::read()
+        .map(|config| config.log_level().to_string())
+        .unwrap_or_else(|_| "info".into());
+    let appender = log_dir().map_err(|err| err.to_string()).and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(FILE_PREFIX)
+            .filename_suffix(FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(|err| err.to_string())
+    });
+
+    let (file_layer, guard) = match appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let file_filter =
+                EnvFilter::try_new(&file_level).unwrap_or_else(|_| EnvFilter::new("info"));
+            let layer = fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer)
+                .with_filter(file_filter);
+            (Some(layer), Some(guard))
+        }
+        Err(err) => {
+            eprintln!("Failed to open log directory: {err}");
+            (None, None)
+        }
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer);
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+
+    if EnvFilter::try_new(&file_level).is_err() {
+        warn!("Invalid log level {file_level:?}, logging at info to the log file instead");
+    }
+
+    guard
+}
+
+/// Returns the newest log file in `dir`. Their names end with the date, so the newest one sorts
+/// last.
+fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(FILE_PREFIX) && n.ends_with(FILE_SUFFIX))
+        })
+        .max()
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_latest_log_file() {
+        let dir = tempdir().unwrap();
+        assert_eq!(latest_log_file(dir.path()), None);
+
+        for name in [
+            "barnacle.2026-01-30.log",
+            "barnacle.2026-02-01.log",
+            "notes.txt",
+            "barnacle.2026-01-31.log",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            latest_log_file(dir.path()),
+            Some(dir.path().join("barnacle.2026-02-01.log"))
+        );
+    }
+}
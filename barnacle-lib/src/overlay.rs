@@ -0,0 +1,216 @@
+//! Deploying a profile as an OverlayFS mount over a game's target directories.
+//!
+//! Instead of linking each file, the enabled mods' directories are stacked as the lower layers of
+//! an overlay mounted over the target, on top of the target's own files. Whatever the game writes
+//! at runtime lands in an upper directory kept with the profile, so neither the game files nor the
+//! mod store are touched. Mounting needs privileges, so the exact `mount` invocation can be shown
+//! to the user to run themselves, see [`Profile::deploy_command`](crate::repository::Profile::deploy_command).
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tracing::debug;
+
+use crate::repository::entities::{Error, Result};
+
+/// The mounts table of the current process
+const MOUNTS: &str = "/proc/self/mounts";
+
+/// An overlay mounted over one deploy target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OverlayMount {
+    /// The directory the overlay is mounted over, which is also its lowest layer
+    pub target: PathBuf,
+    /// The mod directories layered over the target, in load order. Later ones win.
+    pub mod_dirs: Vec<PathBuf>,
+    /// Where writes made through the overlay end up
+    pub upper_dir: PathBuf,
+    /// Scratch directory OverlayFS needs on the same filesystem as `upper_dir`
+    pub work_dir: PathBuf,
+}
+
+impl OverlayMount {
+    /// The `-o` options of the mount. OverlayFS takes the topmost lower directory first, so the
+    /// mods are listed in reverse load order with the target last.
+    pub fn options(&self) -> String {
+        let lower_dirs: Vec<String> = self
+            .mod_dirs
+            .iter()
+            .rev()
+            .chain([&self.target])
+            .map(|dir| escape_option(dir))
+            .collect();
+
+        format!(
+            "lowerdir={},upperdir={},workdir={}",
+            lower_dirs.join(":"),
+            escape_option(&self.upper_dir),
+            escape_option(&self.work_dir)
+        )
+    }
+
+    /// The arguments of the `mount` invocation that mounts this overlay
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "-t".into(),
+            "overlay".into(),
+            "overlay".into(),
+            "-o".into(),
+            self.options(),
+            self.target.to_string_lossy().into_owned(),
+        ]
+    }
+
+    /// The `mount` invocation as a shell command line, quoted so it can be pasted as is
+    pub fn command_line(&self) -> String {
+        let args: Vec<String> = self.args().iter().map(|arg| shell_quote(arg)).collect();
+        format!("mount {}", args.join(" "))
+    }
+
+    /// Create the upper and work directories the mount needs
+    pub fn create_dirs(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.upper_dir)?;
+        fs::create_dir_all(&self.work_dir)
+    }
+
+    /// Mount the overlay. Fails with [`Error::StaleMount`] if an overlay is already mounted over
+    /// the target, and with [`Error::MountFailed`] if `mount` does, usually for lack of
+    /// privileges.
+    pub fn mount(&self) -> Result<()> {
+        if is_mounted(&self.target)? {
+            return Err(Error::StaleMount(self.target.clone()));
+        }
+        self.create_dirs()?;
+
+        debug!("Running {}", self.command_line());
+        let output = Command::new("mount").args(self.args()).output()?;
+        if !output.status.success() {
+            return Err(Error::MountFailed(
+                self.target.clone(),
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Unmount the overlay over `target`, if there is one
+pub(crate) fn unmount(target: &Path) -> io::Result<()> {
+    if !is_mounted(target)? {
+        return Ok(());
+    }
+
+    let output = Command::new("umount").arg(target).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "Unmounting {} failed: {}",
+            target.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if an overlay is mounted over `target`
+pub(crate) fn is_mounted(target: &Path) -> io::Result<bool> {
+    let mounts = match fs::read_to_string(MOUNTS) {
+        Ok(mounts) => mounts,
+        // Not Linux, so nothing can be mounted
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    Ok(overlay_mount_points(&mounts).any(|mount_point| mount_point == target))
+}
+
+/// The mount points of the overlays in a mounts table, formatted like `/proc/self/mounts`
+fn overlay_mount_points(mounts: &str) -> impl Iterator<Item = PathBuf> {
+    mounts.lines().filter_map(|line| {
+        let mut fields = line.split(' ');
+        let (_, mount_point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+        (fs_type == "overlay").then(|| PathBuf::from(unescape_mount_point(mount_point)))
+    })
+}
+
+/// Undo the octal escapes the mounts table uses for whitespace and backslashes in mount points
+fn unescape_mount_point(mount_point: &str) -> String {
+    mount_point
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
+
+/// Escape the characters OverlayFS treats as separators in its directory options
+fn escape_option(dir: &Path) -> String {
+    dir.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace(':', "\\:")
+        .replace(',', "\\,")
+}
+
+/// Quote `arg` for a POSIX shell, unless it's made of characters that are safe as they are
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let mount = OverlayMount {
+            target: PathBuf::from("/games/Morrowind"),
+            mod_dirs: vec![
+                PathBuf::from("/library/mods/First"),
+                PathBuf::from("/library/mods/Second: The Sequel"),
+            ],
+            upper_dir: PathBuf::from("/library/profiles/Test/overlay/0/upper"),
+            work_dir: PathBuf::from("/library/profiles/Test/overlay/0/work"),
+        };
+
+        // Later mods are layered on top, and the target sits at the bottom
+        assert_eq!(
+            mount.options(),
+            "lowerdir=/library/mods/Second\\: The Sequel:/library/mods/First:/games/Morrowind,\
+             upperdir=/library/profiles/Test/overlay/0/upper,\
+             workdir=/library/profiles/Test/overlay/0/work"
+        );
+        assert_eq!(
+            mount.command_line(),
+            "mount -t overlay overlay -o 'lowerdir=/library/mods/Second\\: The Sequel:\
+             /library/mods/First:/games/Morrowind,upperdir=/library/profiles/Test/overlay/0/upper,\
+             workdir=/library/profiles/Test/overlay/0/work' /games/Morrowind"
+        );
+    }
+
+    #[test]
+    fn test_overlay_mount_points() {
+        let mounts = "proc /proc proc rw,nosuid 0 0\n\
+                      overlay /games/Morrowind overlay rw,lowerdir=/a:/b 0 0\n\
+                      overlay /games/Elder\\040Scrolls overlay rw,lowerdir=/c 0 0\n\
+                      tmpfs /tmp tmpfs rw 0 0\n";
+
+        assert_eq!(
+            overlay_mount_points(mounts).collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("/games/Morrowind"),
+                PathBuf::from("/games/Elder Scrolls")
+            ]
+        );
+    }
+}
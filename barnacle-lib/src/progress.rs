@@ -0,0 +1,87 @@
+//! Progress reporting for long running operations.
+//!
+//! Operations that can take a while accept a callback that's invoked with a [`Progress`] update
+//! every time they make progress. Frontends decide how to render them. Some also accept a
+//! [`Cancel`] token, so they can be stopped from another thread.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use serde::Serialize;
+use strum::Display;
+
+use crate::repository::entities::{Error, Result};
+
+/// The stage a long running operation is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "title_case")]
+pub enum Phase {
+    /// Copying or extracting a mod's files into the library
+    Install,
+    /// Building a mod's file index
+    Index,
+}
+
+/// A single progress update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub phase: Phase,
+    /// How many steps of the phase have been completed
+    pub current: u64,
+    /// How many steps the phase has in total
+    pub total: u64,
+    /// The file or directory currently being worked on, if any
+    pub path: Option<PathBuf>,
+}
+
+impl Progress {
+    pub fn new(phase: Phase, current: u64, total: u64, path: Option<PathBuf>) -> Self {
+        Self {
+            phase,
+            current,
+            total,
+            path,
+        }
+    }
+}
+
+/// Callback invoked with every progress update
+pub type OnProgress<'a> = &'a mut dyn FnMut(Progress);
+
+/// Shared flag for canceling a long running operation. Clones share the flag, so one can be
+/// handed to the operation while another is kept around to cancel it.
+///
+/// Operations check it between steps. Once it's set, they undo what they did so far and fail
+/// with [`Error::Canceled`].
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the operation to stop at its next step
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`Error::Canceled`] if the operation was canceled
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_canceled() {
+            return Err(Error::Canceled);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+//! Exporting the whole repository to a single file and restoring it elsewhere.
+//!
+//! A bundle is a zip archive holding a snapshot of the database, the core configuration, and
+//! the library directory. Deployment manifests are left out, since what's deployed is specific
+//! to the machine the bundle was made on.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tempfile::tempdir;
+use tracing::info;
+use walkdir::WalkDir;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    deploy::{LOADOUT_FILE, MANIFEST_FILE},
+    repository::{
+        Repository,
+        config::CoreConfig,
+        db::{Db, models::CURRENT_MODEL_VERSION},
+        entities::{self, Error},
+    },
+};
+
+const BUNDLE_MANIFEST: &str = "bundle.toml";
+const BUNDLE_DB: &str = "data.db";
+const BUNDLE_CONFIG: &str = "core.toml";
+const BUNDLE_LIBRARY: &str = "library";
+
+/// What [`Repository::export_bundle`] puts in a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleContents {
+    /// Everything, including the files of every mod. For backups and moving to another machine.
+    Full,
+    /// Everything but the files of mods, for sharing a setup. Imported mods are empty until
+    /// they're installed again.
+    MetadataOnly,
+}
+
+/// Describes a bundle, stored alongside its contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct BundleManifest {
+    /// Model version of the bundled database
+    model_version: u64,
+    mod_files: bool,
+}
+
+impl Repository {
+    /// Write the database, configuration and library to a bundle at `out`, which can be
+    /// restored with [`Repository::import_bundle`].
+    pub fn export_bundle(&self, out: &Path, contents: BundleContents) -> entities::Result<()> {
+        let mod_files = contents == BundleContents::Full;
+        let mut zip = ZipWriter::new(File::create(out)?);
+        let options = SimpleFileOptions::default();
+
+        let manifest = BundleManifest {
+            model_version: self.model_version().unwrap_or(CURRENT_MODEL_VERSION),
+            mod_files,
+        };
+        zip.start_file(BUNDLE_MANIFEST, options)?;
+        zip.write_all(toml::to_string_pretty(&manifest)?.as_bytes())?;
+
+        zip.start_file(BUNDLE_CONFIG, options)?;
+        zip.write_all(toml::to_string_pretty(&*self.cfg.read())?.as_bytes())?;
+
+        let snapshot_dir = tempdir()?;
+        let snapshot = snapshot_dir.path().join(BUNDLE_DB);
+        self.db.backup_to(&snapshot)?;
+        zip.start_file(BUNDLE_DB, options)?;
+        io::copy(&mut File::open(&snapshot)?, &mut zip)?;
+
+        let library_dir = self.cfg.read().library_dir().to_path_buf();
+        for entry in WalkDir::new(&library_dir).min_depth(1) {
+            let entry = entry.map_err(io::Error::from)?;
+            let relative = entry
+                .path()
+                .strip_prefix(&library_dir)
+                .expect("walked entries are inside the library");
+            if entry.path_is_symlink()
+                || !should_bundle(relative, entry.file_type().is_dir(), mod_files)
+            {
+                continue;
+            }
+
+            let name = Path::new(BUNDLE_LIBRARY)
+                .join(relative)
+                .to_string_lossy()
+                .into_owned();
+            if entry.file_type().is_dir() {
+                zip.add_directory(name, options)?;
+            } else {
+                zip.start_file(name, options)?;
+                io::copy(&mut File::open(entry.path())?, &mut zip)?;
+            }
+        }
+
+        zip.finish()?;
+
+        info!("Exported bundle to {}", out.display());
+
+        Ok(())
+    }
+
+    /// Restore a bundle written by [`Repository::export_bundle`], with its library placed in
+    /// `library_dir`, which must be empty or not exist yet.
+    ///
+    /// This replaces the database and configuration of this machine, so the current database is
+    /// backed up first. The whole bundle is extracted before either is replaced, so a broken
+    /// bundle leaves them as they were. A bundle from an older version of Barnacle is migrated
+    /// when it's opened. Stored absolute paths, such as deploy targets, still point where they
+    /// did on the machine the bundle came from and can be fixed up with [`Repository::relocate`].
+    pub fn import_bundle(bundle: &Path, library_dir: &Path) -> entities::Result<Self> {
+        let db_path = Db::default_path()?;
+        if db_path.exists() {
+            Db::open_file(&db_path)?.backup()?;
+        }
+
+        let mut cfg = unpack_bundle(bundle, library_dir, &db_path)?;
+        cfg.set_path(CoreConfig::default_path()?);
+        cfg.save();
+
+        info!("Imported bundle from {}", bundle.display());
+
+        Ok(Self {
+            db: Db::new()?,
+            cfg: Arc::new(RwLock::new(cfg)),
+        })
+    }
+}
+
+/// Returns whether the library entry at `relative` belongs in a bundle
+fn should_bundle(relative: &Path, is_dir: bool, mod_files: bool) -> bool {
+    // Both describe a deployment, which doesn't carry over to another machine
+    if relative
+        .file_name()
+        .is_some_and(|name| name == MANIFEST_FILE || name == LOADOUT_FILE)
+    {
+        return false;
+    }
+
+    // Entity directories are laid out as <game>/<kind>/<name>
+    let kind = relative.components().nth(1).map(Component::as_os_str);
+    match kind {
+        // Scratch space for extracted archives, it's recreated on demand
+        Some(kind) if kind == "unpacked" => false,
+        // Keep the mod directories themselves so imported mods still have somewhere to live
+        Some(kind) if kind == "mods" => mod_files || is_dir,
+        _ => true,
+    }
+}
+
+/// Extract `bundle`, putting its library in `library_dir` and its database at `db_path`.
+/// Returns the bundled configuration, pointed at `library_dir`.
+///
+/// Everything is extracted into a staging directory next to `library_dir` first. Only once that
+/// has succeeded is the library moved into place, and the database replaced last.
+fn unpack_bundle(
+    bundle: &Path,
+    library_dir: &Path,
+    db_path: &Path,
+) -> entities::Result<CoreConfig> {
+    if library_dir.exists() && fs::read_dir(library_dir)?.next().is_some() {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} isn't empty", library_dir.display()),
+        )));
+    }
+
+    let mut zip = ZipArchive::new(File::open(bundle)?)?;
+
+    let manifest: BundleManifest = toml::from_str(&read_to_string(&mut zip, BUNDLE_MANIFEST)?)?;
+    if manifest.model_version > CURRENT_MODEL_VERSION {
+        return Err(Error::UnsupportedBundle(manifest.model_version));
+    }
+
+    let mut cfg: CoreConfig = toml::from_str(&read_to_string(&mut zip, BUNDLE_CONFIG)?)?;
+    cfg.set_library_dir(library_dir.to_path_buf());
+
+    // Staged on the same filesystem so the library can be renamed into place
+    let parent = match library_dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    let staging = tempfile::Builder::new()
+        .prefix(".barnacle-import")
+        .tempdir_in(parent)?;
+    let staged_db = staging.path().join(BUNDLE_DB);
+    let staged_library = staging.path().join(BUNDLE_LIBRARY);
+
+    io::copy(&mut zip.by_name(BUNDLE_DB)?, &mut File::create(&staged_db)?)?;
+
+    fs::create_dir_all(&staged_library)?;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let Some(relative) = file
+            .enclosed_name()
+            .and_then(|name| name.strip_prefix(BUNDLE_LIBRARY).ok().map(PathBuf::from))
+        else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let dest = staged_library.join(relative);
+        if file.is_dir() {
+            fs::create_dir_all(&dest)?;
+        } else {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut File::create(&dest)?)?;
+        }
+    }
+
+    // It's empty, as checked above
+    if library_dir.exists() {
+        fs::remove_dir(library_dir)?;
+    }
+    fs::rename(&staged_library, library_dir)?;
+
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&staged_db, db_path)?;
+
+    Ok(cfg)
+}
+
+fn read_to_string(zip: &mut ZipArchive<File>, name: &str) -> entities::Result<String> {
+    let mut contents = String::new();
+    zip.by_name(name)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        repository::DeployKind,
+        test_utils::{assert_tree, write_zip},
+    };
+
+    fn file_repo(db_path: &Path) -> Repository {
+        Repository {
+            db: Db::open_file(db_path).unwrap(),
+            cfg: Arc::new(RwLock::new(CoreConfig::mock())),
+        }
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let tmp = tempdir().unwrap();
+        let repo = file_repo(&tmp.path().join("data.db"));
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        game.add_profile("Default").unwrap();
+        let mod_ = game.add_mod("SkyUI", None).unwrap();
+        fs::write(mod_.dir().unwrap().join("skyui.esp"), "").unwrap();
+        fs::write(game.deployment_manifest().unwrap(), "").unwrap();
+
+        for (contents, expected) in [
+            (
+                BundleContents::Full,
+                &[
+                    "skyrim/",
+                    "skyrim/mods/",
+                    "skyrim/mods/sky_ui/",
+                    "skyrim/mods/sky_ui/skyui.esp",
+                    "skyrim/profiles/",
+                    "skyrim/profiles/default/",
+                ][..],
+            ),
+            (
+                BundleContents::MetadataOnly,
+                &[
+                    "skyrim/",
+                    "skyrim/mods/",
+                    "skyrim/mods/sky_ui/",
+                    "skyrim/profiles/",
+                    "skyrim/profiles/default/",
+                ][..],
+            ),
+        ] {
+            let out = tmp.path().join("bundle.zip");
+            repo.export_bundle(&out, contents).unwrap();
+
+            let target = tempdir().unwrap();
+            let library_dir = target.path().join("library");
+            let db_path = target.path().join("data.db");
+            let cfg = unpack_bundle(&out, &library_dir, &db_path).unwrap();
+
+            assert_eq!(cfg.library_path(), library_dir);
+            assert_tree(&library_dir, expected);
+
+            let imported = Repository {
+                db: Db::open_file(&db_path).unwrap(),
+                cfg: Arc::new(RwLock::new(cfg)),
+            };
+            let games = imported.games().unwrap();
+            assert_eq!(games.len(), 1);
+            assert_eq!(games.first().unwrap().mods().unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_import_into_non_empty_library() {
+        let tmp = tempdir().unwrap();
+        let repo = file_repo(&tmp.path().join("data.db"));
+        let out = tmp.path().join("bundle.zip");
+        repo.export_bundle(&out, BundleContents::MetadataOnly)
+            .unwrap();
+
+        let library_dir = tempdir().unwrap();
+        fs::write(library_dir.path().join("existing"), "").unwrap();
+
+        assert!(unpack_bundle(&out, library_dir.path(), &tmp.path().join("new.db")).is_err());
+    }
+
+    #[test]
+    fn test_import_broken_bundle() {
+        let tmp = tempdir().unwrap();
+        let config = toml::to_string_pretty(&CoreConfig::mock()).unwrap();
+        let manifest = toml::to_string_pretty(&BundleManifest {
+            model_version: CURRENT_MODEL_VERSION,
+            mod_files: true,
+        })
+        .unwrap();
+        // The second file can't be extracted, since the first is in the way of its directory
+        let bundle = tmp.path().join("bundle.zip");
+        write_zip(
+            &bundle,
+            &[
+                (BUNDLE_MANIFEST, manifest.as_str()),
+                (BUNDLE_CONFIG, config.as_str()),
+                (BUNDLE_DB, "new"),
+                ("library/skyrim", ""),
+                ("library/skyrim/mods", ""),
+            ],
+        );
+
+        let library_dir = tmp.path().join("library");
+        let db_path = tmp.path().join("data.db");
+        fs::write(&db_path, "old").unwrap();
+
+        assert!(unpack_bundle(&bundle, &library_dir, &db_path).is_err());
+        // Neither the library nor the database were touched
+        assert!(!library_dir.exists());
+        assert_eq!(fs::read_to_string(&db_path).unwrap(), "old");
+        assert_tree(tmp.path(), &["bundle.zip", "data.db"]);
+    }
+
+    #[test]
+    fn test_should_bundle() {
+        assert!(should_bundle(
+            Path::new("skyrim/profiles/default"),
+            true,
+            false
+        ));
+        assert!(should_bundle(Path::new("skyrim/mods/sky_ui"), true, false));
+        assert!(!should_bundle(
+            Path::new("skyrim/mods/sky_ui/a.esp"),
+            false,
+            false
+        ));
+        assert!(should_bundle(
+            Path::new("skyrim/mods/sky_ui/a.esp"),
+            false,
+            true
+        ));
+        assert!(!should_bundle(
+            Path::new("skyrim/unpacked/sky_ui"),
+            true,
+            true
+        ));
+        assert!(!should_bundle(
+            Path::new("skyrim/deployment.toml"),
+            false,
+            true
+        ));
+    }
+}
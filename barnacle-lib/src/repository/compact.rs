@@ -0,0 +1,92 @@
+//! Reclaiming the space the database file accumulates.
+//!
+//! Removed elements leave holes in the database file that agdb only reuses, rather than gives
+//! back. Libraries where mods are added and removed a lot, such as by reinstalling them, keep
+//! growing until the file is compacted.
+
+use tracing::info;
+
+use crate::repository::{Repository, entities};
+
+/// The size of the database file before and after [`Repository::compact`], in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    pub before: u64,
+    pub after: u64,
+}
+
+impl Compaction {
+    /// Returns how many bytes compacting saved
+    pub fn saved(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl Repository {
+    /// Compact the database file, giving back the space left behind by removed data. A backup of
+    /// the database is written next to it first, named after the time it was taken.
+    pub fn compact(&self) -> entities::Result<Compaction> {
+        let before = self.db.file_size()?;
+
+        if let Some(path) = self.db.path() {
+            let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            let backup = path.with_extension(format!("{timestamp}.bak"));
+            self.db.backup_to(&backup)?;
+            info!("Backed up the database to {}", backup.display());
+        }
+
+        self.db.compact()?;
+        let after = self.db.file_size()?;
+        info!("Compacted the database from {before} to {after} bytes");
+
+        Ok(Compaction { before, after })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::Arc};
+
+    use parking_lot::RwLock;
+    use tempfile::tempdir;
+
+    use crate::{
+        Repository,
+        repository::{DeployKind, config::CoreConfig, db::Db},
+    };
+
+    #[test]
+    fn test_compact() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.db");
+        let repo = Repository {
+            db: Db::open_file(&path).unwrap(),
+            cfg: Arc::new(RwLock::new(CoreConfig::mock())),
+        };
+
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        for i in 0..50 {
+            game.add_mod(&format!("Mod {i}"), None)
+                .unwrap()
+                .remove()
+                .unwrap();
+        }
+        game.add_mod("Kept", None).unwrap();
+
+        let compaction = repo.compact().unwrap();
+        assert_eq!(compaction.after, fs::metadata(&path).unwrap().len());
+        assert!(compaction.after <= compaction.before);
+        assert_eq!(compaction.saved(), compaction.before - compaction.after);
+
+        // The backup sits next to the database
+        let backups: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("data.") && name.ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+
+        // Nothing was lost
+        assert_eq!(game.mods().unwrap().len(), 1);
+    }
+}
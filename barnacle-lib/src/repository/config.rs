@@ -1,11 +1,13 @@
 use std::{
-    fs,
+    fs, io,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use parking_lot::RwLock;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
 use crate::fs::{config_dir, data_dir};
 
@@ -20,29 +22,126 @@ pub(crate) type Cfg = Arc<RwLock<CoreConfig>>;
 pub struct CoreConfig {
     version: u16,
     library_dir: PathBuf,
+    #[serde(default)]
+    category_rules: Vec<CategoryRule>,
+    /// Unix timestamp of the last time category rules were applied
+    #[serde(default)]
+    category_rules_last_run: Option<u64>,
+    /// Name of the profile [`Repository::add_game_with_default_profile`] creates
+    ///
+    /// [`Repository::add_game_with_default_profile`]: crate::Repository::add_game_with_default_profile
+    #[serde(default = "default_profile_name")]
+    default_profile_name: String,
+    /// Whether mods added to a profile start out enabled
+    #[serde(default = "enable_new_mods_by_default")]
+    enable_new_mods_by_default: bool,
+    /// Whether installed mod files are made read-only
+    #[serde(default = "enforce_readonly_mods")]
+    enforce_readonly_mods: bool,
+    /// What's written to the log file, in `RUST_LOG` syntax
+    #[serde(default = "log_level")]
+    log_level: String,
+    /// Where the configuration is saved to
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+fn default_profile_name() -> String {
+    "Default".to_string()
+}
+
+fn enable_new_mods_by_default() -> bool {
+    true
+}
+
+fn enforce_readonly_mods() -> bool {
+    true
+}
+
+fn log_level() -> String {
+    "info".to_string()
+}
+
+/// A saved rule assigning `category` to every mod whose name matches `pattern`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryRule {
+    /// Regular expression matched against mod names
+    pub pattern: String,
+    pub category: String,
+}
+
+impl CategoryRule {
+    /// Compile the rule into the form [`Repository::apply_category_rules`] takes
+    ///
+    /// [`Repository::apply_category_rules`]: crate::Repository::apply_category_rules
+    pub fn compile(&self) -> Result<(Regex, String), regex::Error> {
+        Ok((Regex::new(&self.pattern)?, self.category.clone()))
+    }
 }
 
 impl CoreConfig {
-    pub fn load() -> Self {
-        let path = config_dir().join(FILE_NAME);
+    /// Load the configuration file, writing a default one if there isn't any. Fails if the
+    /// configuration directory can't be found or the file can't be read.
+    pub fn load() -> io::Result<Self> {
+        let cfg = Self::read()?;
+        if !cfg.path.exists() {
+            cfg.save();
+        }
+
+        Ok(cfg)
+    }
 
+    /// Like [`CoreConfig::load`], but a missing configuration file is defaulted without being
+    /// written.
+    pub fn read() -> io::Result<Self> {
+        Self::read_from(Self::default_path()?, data_dir()?.join("library"))
+    }
+
+    /// Returns where the configuration is stored outside of tests
+    pub(crate) fn default_path() -> io::Result<PathBuf> {
+        Ok(config_dir()?.join(FILE_NAME))
+    }
+
+    /// Read the configuration file at `path`, defaulting to one with `library_dir` if it's
+    /// missing or invalid
+    fn read_from(path: PathBuf, library_dir: PathBuf) -> io::Result<Self> {
         if path.exists() {
-            let contents = fs::read_to_string(path).unwrap();
-            toml::from_str(&contents).unwrap_or_default()
-        } else {
-            let cfg = Self::default();
-            cfg.save();
-            cfg
+            let contents = fs::read_to_string(&path)?;
+            if let Ok(cfg) = toml::from_str::<Self>(&contents) {
+                return Ok(Self { path, ..cfg });
+            }
         }
+
+        Ok(Self::new(library_dir, path))
     }
 
+    /// Write the configuration file. Failures are logged rather than returned, as there's
+    /// nothing more the caller could do about them.
     pub fn save(&self) {
         let contents = toml::to_string_pretty(self).unwrap();
 
-        // Make sure config_dir exists
-        fs::create_dir_all(config_dir()).unwrap();
+        let written = match self.path.parent() {
+            Some(dir) => fs::create_dir_all(dir),
+            None => Ok(()),
+        }
+        .and_then(|()| fs::write(&self.path, contents));
+        if let Err(err) = written {
+            error!("Failed to save the core configuration: {err}");
+        }
+    }
 
-        fs::write(config_dir().join(FILE_NAME), contents).unwrap();
+    fn new(library_dir: PathBuf, path: PathBuf) -> Self {
+        Self {
+            version: CURRENT_CONFIG_VERSION,
+            library_dir,
+            category_rules: Vec::new(),
+            category_rules_last_run: None,
+            default_profile_name: default_profile_name(),
+            enable_new_mods_by_default: enable_new_mods_by_default(),
+            enforce_readonly_mods: enforce_readonly_mods(),
+            log_level: log_level(),
+            path,
+        }
     }
 
     /// Returns the path to the Barnacle library directory. This is where
@@ -53,26 +152,93 @@ impl CoreConfig {
         &self.library_dir
     }
 
-    /// Create a new mock [`CoreConfig`] instance for testing
+    /// Like [`CoreConfig::library_dir`], but doesn't create the directory
+    pub(crate) fn library_path(&self) -> &Path {
+        &self.library_dir
+    }
+
+    pub(crate) fn set_library_dir(&mut self, library_dir: PathBuf) {
+        self.library_dir = library_dir;
+    }
+
+    /// Change where [`CoreConfig::save`] writes to, for configurations that weren't read from a
+    /// file
+    pub(crate) fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+
+    pub fn category_rules(&self) -> &[CategoryRule] {
+        &self.category_rules
+    }
+
+    pub fn set_category_rules(&mut self, rules: Vec<CategoryRule>) {
+        self.category_rules = rules;
+    }
+
+    /// Returns when category rules were last applied, as a Unix timestamp
+    pub fn category_rules_last_run(&self) -> Option<u64> {
+        self.category_rules_last_run
+    }
+
+    pub(crate) fn set_category_rules_last_run(&mut self, timestamp: u64) {
+        self.category_rules_last_run = Some(timestamp);
+    }
+
+    pub fn default_profile_name(&self) -> &str {
+        &self.default_profile_name
+    }
+
+    pub fn set_default_profile_name(&mut self, name: String) {
+        self.default_profile_name = name;
+    }
+
+    pub fn enable_new_mods_by_default(&self) -> bool {
+        self.enable_new_mods_by_default
+    }
+
+    pub fn set_enable_new_mods_by_default(&mut self, enabled: bool) {
+        self.enable_new_mods_by_default = enabled;
+    }
+
+    pub fn enforce_readonly_mods(&self) -> bool {
+        self.enforce_readonly_mods
+    }
+
+    pub fn set_enforce_readonly_mods(&mut self, enforce: bool) {
+        self.enforce_readonly_mods = enforce;
+    }
+
+    /// Returns the filter applied to the log file, like `info` or `barnacle_lib=debug`
+    pub fn log_level(&self) -> &str {
+        &self.log_level
+    }
+
+    /// Create a new mock [`CoreConfig`] instance for testing, with its library and file in a
+    /// temporary directory
     #[cfg(test)]
     pub(crate) fn mock() -> Self {
         use tempfile::tempdir;
 
-        Self {
-            version: CURRENT_CONFIG_VERSION,
-            library_dir: tempdir()
-                .expect("temporary directory should exist")
-                .path()
-                .to_path_buf(),
-        }
+        let dir = tempdir()
+            .expect("temporary directory should exist")
+            .path()
+            .to_path_buf();
+        Self::new(dir.join("library"), dir.join(FILE_NAME))
     }
 }
 
-impl Default for CoreConfig {
-    fn default() -> Self {
-        Self {
-            version: CURRENT_CONFIG_VERSION,
-            library_dir: data_dir().join("library"),
-        }
+#[cfg(test)]
+mod test {
+    use super::CoreConfig;
+
+    #[test]
+    fn test_save() {
+        let mut cfg = CoreConfig::mock();
+        cfg.set_default_profile_name("Vanilla".into());
+        cfg.save();
+
+        let read = CoreConfig::read_from(cfg.path.clone(), cfg.library_dir.clone()).unwrap();
+        assert_eq!(read.default_profile_name(), "Vanilla");
+        assert_eq!(read.path, cfg.path);
     }
 }
@@ -0,0 +1,164 @@
+//! Versioned database migrations
+//!
+//! Each migration upgrades the stored data by exactly one model version. When a model changes in
+//! a way that existing data doesn't satisfy (e.g. a new field), bump
+//! [`CURRENT_MODEL_VERSION`](super::models::CURRENT_MODEL_VERSION) and register a migration here
+//! that brings old data up to date.
+
+use agdb::{DbAny, DbError, DbId, QueryBuilder};
+
+use crate::repository::db::models::{
+    GameModel, LinkStrategy, ModEntryModel, ModModel, ProfileModel, StorageKind,
+};
+
+/// A single migration step, which upgrades the database by exactly one model version.
+pub(super) type Migration = fn(&mut DbAny) -> Result<(), DbError>;
+
+/// Ordered list of migrations, keyed by the model version they upgrade from.
+pub(super) const MIGRATIONS: &[(u64, Migration)] = &[
+    (1, add_mod_storage_kind),
+    (2, add_mod_entry_positions),
+    (3, add_mod_entry_locked),
+    (4, add_profile_play_stats),
+    (5, add_game_link_strategy),
+    (6, add_mod_category),
+];
+
+/// v1 -> v2: Mods gained a storage kind. Every existing mod was extracted on add.
+fn add_mod_storage_kind(db: &mut DbAny) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values_uniform([("storage_kind", StorageKind::Loose).into()])
+            .ids(
+                QueryBuilder::search()
+                    .from("mods")
+                    .where_()
+                    .element::<ModModel>()
+                    .query(),
+            )
+            .query(),
+    )?;
+
+    Ok(())
+}
+
+/// v2 -> v3: Mod entries gained an explicit position, taken from the order of the linked list.
+fn add_mod_entry_positions(db: &mut DbAny) -> Result<(), DbError> {
+    let profile_ids: Vec<DbId> = db
+        .exec(
+            QueryBuilder::search()
+                .from("profiles")
+                .where_()
+                .element::<ProfileModel>()
+                .query(),
+        )?
+        .elements
+        .iter()
+        .map(|e| e.id)
+        .collect();
+
+    for profile_id in profile_ids {
+        let entry_ids: Vec<DbId> = db
+            .exec(
+                QueryBuilder::search()
+                    .from(profile_id)
+                    .where_()
+                    .element::<ModEntryModel>()
+                    .query(),
+            )?
+            .elements
+            .iter()
+            .map(|e| e.id)
+            .collect();
+
+        for (position, entry_id) in entry_ids.into_iter().enumerate() {
+            db.exec_mut(
+                QueryBuilder::insert()
+                    .values([[("position", position as u64).into()]])
+                    .ids(entry_id)
+                    .query(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// v3 -> v4: Mod entries gained a locked flag. No existing entry is locked.
+fn add_mod_entry_locked(db: &mut DbAny) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values_uniform([("locked", false).into()])
+            .ids(
+                QueryBuilder::search()
+                    .from("mod_entries")
+                    .where_()
+                    .element::<ModEntryModel>()
+                    .query(),
+            )
+            .query(),
+    )?;
+
+    Ok(())
+}
+
+/// v4 -> v5: Profiles gained launch and play time counters. No existing profile was launched.
+fn add_profile_play_stats(db: &mut DbAny) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values_uniform([
+                ("launch_count", 0_u64).into(),
+                ("play_seconds", 0_u64).into(),
+            ])
+            .ids(
+                QueryBuilder::search()
+                    .from("profiles")
+                    .where_()
+                    .element::<ProfileModel>()
+                    .query(),
+            )
+            .query(),
+    )?;
+
+    Ok(())
+}
+
+/// v5 -> v6: Games gained a link strategy. Every existing game was deployed with symlinks.
+fn add_game_link_strategy(db: &mut DbAny) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values_uniform([("link_strategy", LinkStrategy::Symlink).into()])
+            .ids(
+                QueryBuilder::search()
+                    .from("games")
+                    .where_()
+                    .element::<GameModel>()
+                    .query(),
+            )
+            .query(),
+    )?;
+
+    Ok(())
+}
+
+/// v6 -> v7: Mods gained a category. Mods that were already sorted into one keep it, the rest
+/// are uncategorized.
+fn add_mod_category(db: &mut DbAny) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values_uniform([("category", "").into()])
+            .ids(
+                QueryBuilder::search()
+                    .from("mods")
+                    .where_()
+                    .element::<ModModel>()
+                    .and()
+                    .not()
+                    .keys("category")
+                    .query(),
+            )
+            .query(),
+    )?;
+
+    Ok(())
+}
@@ -1,42 +1,75 @@
-use std::sync::Arc;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use agdb::{DbAny, DbError, QueryBuilder};
 use derive_more::Deref;
 use parking_lot::RwLock;
+use tracing::{error, info};
 
 use crate::{
+    deploy::Locks,
     fs::state_dir,
-    repository::db::models::{CURRENT_MODEL_VERSION, ModelVersion},
+    repository::db::{
+        migrations::{MIGRATIONS, Migration},
+        models::{CURRENT_MODEL_VERSION, ModelVersion},
+    },
 };
 
+mod migrations;
 pub(crate) mod models;
 
 #[derive(Debug, Clone, Deref)]
 pub(crate) struct Db {
     #[deref]
     db: Arc<RwLock<DbAny>>,
+    /// Where the database is stored, `None` if it's only in memory
+    path: Option<PathBuf>,
+    deploy_locks: Locks,
 }
 
 impl Db {
-    pub fn new() -> Self {
-        let path = state_dir().join("data.db");
-        let path_str = path.to_str().unwrap();
+    pub fn new() -> Result<Self, DbError> {
+        let db = Self::open()?;
+
+        if db.needs_migration() {
+            db.migrate()?;
+        }
+
+        Ok(db)
+    }
+
+    /// Open the database without performing any pending migrations
+    pub fn open() -> Result<Self, DbError> {
+        Self::open_file(&Self::default_path()?)
+    }
 
+    /// Returns where the database is stored outside of tests
+    pub(crate) fn default_path() -> io::Result<PathBuf> {
+        Ok(state_dir()?.join("data.db"))
+    }
+
+    /// Open the database stored at `path`, without performing any pending migrations. Fails if
+    /// the file can't be opened, like when it's locked by another process or corrupt.
+    pub(crate) fn open_file(path: &Path) -> Result<Self, DbError> {
         let mut db = Self {
-            db: Arc::new(RwLock::new(DbAny::new_file(path_str).unwrap())),
+            db: Arc::new(RwLock::new(DbAny::new_file(path_str(path)?)?)),
+            path: Some(path.to_path_buf()),
+            deploy_locks: Locks::default(),
         };
 
-        db.init();
+        db.init()?;
 
-        db
+        Ok(db)
     }
 
-    fn init(&mut self) {
+    fn init(&mut self) -> Result<(), DbError> {
         let alias_count = self
             .db
             .read()
-            .exec(QueryBuilder::select().aliases().query())
-            .unwrap()
+            .exec(QueryBuilder::select().aliases().query())?
             .result;
 
         if alias_count == 0 {
@@ -73,34 +106,11 @@ impl Db {
                     )?;
 
                     Ok(())
-                })
-                .unwrap();
+                })?;
         }
 
-        // Fetch the current model version (if any)
-        let result = self
-            .db
-            .read()
-            .exec(
-                QueryBuilder::select()
-                    .elements::<ModelVersion>()
-                    .search()
-                    .from("model_version")
-                    .where_()
-                    .neighbor()
-                    .query(),
-            )
-            .unwrap();
-
-        let model_version: Option<ModelVersion> = result.try_into().into_iter().next();
-
-        if let Some(mv) = model_version {
-            if mv.version() < CURRENT_MODEL_VERSION {
-                self.backup();
-                self.migrate();
-            }
-        } else {
-            // Insert default ModelVersion if missing
+        // Insert default ModelVersion if missing
+        if self.model_version().is_none() {
             self.db
                 .write()
                 .transaction_mut(|t| -> Result<(), agdb::DbError> {
@@ -124,23 +134,131 @@ impl Db {
                     )?;
 
                     Ok(())
-                })
-                .unwrap();
+                })?;
         }
+
+        Ok(())
     }
 
-    /// Perform a backup of the database
-    fn backup(&self) {
+    /// Returns the model version of the stored data, if one has been recorded
+    pub fn model_version(&self) -> Option<u64> {
+        let result = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<ModelVersion>()
+                    .search()
+                    .from("model_version")
+                    .where_()
+                    .neighbor()
+                    .query(),
+            )
+            .unwrap();
+
+        let model_version: Option<ModelVersion> = result.try_into().into_iter().next();
+
+        model_version.map(|mv| mv.version())
+    }
+
+    /// Returns true if the stored data is older than [`CURRENT_MODEL_VERSION`]
+    pub fn needs_migration(&self) -> bool {
+        self.model_version()
+            .is_some_and(|version| version < CURRENT_MODEL_VERSION)
+    }
+
+    /// Make sure everything written so far has reached the disk. Every completed write is already
+    /// in the database file, but may still be sitting in the operating system's cache. Does
+    /// nothing for an in-memory database.
+    pub fn flush(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Held so no write is halfway done while syncing
+        let _guard = self.db.read();
+        fs::File::open(path)?.sync_all()
+    }
+
+    /// Rewrite the database file without the space left behind by removed data
+    pub fn compact(&self) -> Result<(), DbError> {
+        self.db.write().optimize_storage()
+    }
+
+    /// Returns the locks serializing deployments with launching tools, see [`crate::deploy::lock`]
+    pub(crate) fn deploy_locks(&self) -> &Locks {
+        &self.deploy_locks
+    }
+
+    /// Returns where the database is stored, `None` if it's only in memory
+    pub(crate) fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the size of the database file in bytes, 0 if it's only in memory
+    pub(crate) fn file_size(&self) -> io::Result<u64> {
+        match &self.path {
+            Some(path) => Ok(fs::metadata(path)?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// Perform a backup of the database, next to the database file. Returns where the backup
+    /// was written.
+    pub fn backup(&self) -> Result<PathBuf, DbError> {
         let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
-        let path = state_dir().join(format!("data-{}.db.bak", timestamp));
-        let path_str = path.to_str().unwrap();
+        let dir = match self.path.as_deref().and_then(Path::parent) {
+            Some(dir) => dir.to_path_buf(),
+            None => state_dir()?,
+        };
+        let path = dir.join(format!("data-{}.db.bak", timestamp));
+
+        self.backup_to(&path)?;
 
-        self.db.write().backup(path_str).unwrap();
+        Ok(path)
     }
 
-    /// Perform database migrations
-    fn migrate(&self) {
-        todo!()
+    /// Write a copy of the database to `path`
+    pub(crate) fn backup_to(&self, path: &Path) -> Result<(), DbError> {
+        self.db.write().backup(path_str(path)?)
+    }
+
+    /// Perform database migrations, stepping through [`MIGRATIONS`] until the stored data is at
+    /// [`CURRENT_MODEL_VERSION`]. The database is backed up first, see [`Db::migrate_with`].
+    pub fn migrate(&self) -> Result<(), DbError> {
+        self.migrate_with(MIGRATIONS)
+    }
+
+    /// Back up the database, then apply each of `migrations` from the stored model version
+    /// onwards, recording the new version after each step. If a step fails, the database is
+    /// restored from the backup so it's never left half migrated, and the step's error is
+    /// returned. Databases only kept in memory can't be restored.
+    fn migrate_with(&self, migrations: &[(u64, Migration)]) -> Result<(), DbError> {
+        let Some(mut version) = self.model_version() else {
+            return Ok(());
+        };
+        let backup = self.backup()?;
+
+        let mut db = self.db.write();
+        for (from, migration) in migrations {
+            if *from < version {
+                continue;
+            }
+
+            let step = migration(&mut db).and_then(|()| set_model_version(&mut db, from + 1));
+            if let Err(err) = step {
+                error!("Migrating from model version {from} failed: {err}");
+                if let Some(path) = &self.path {
+                    restore(&mut db, &backup, path)?;
+                    info!("Restored the database from {}", backup.display());
+                }
+                return Err(err);
+            }
+            version = from + 1;
+        }
+        info!("Migrated the database to model version {version}");
+
+        Ok(())
     }
 
     /// Create a memory backed database for use in tests
@@ -148,10 +266,107 @@ impl Db {
     pub(crate) fn in_memory() -> Self {
         let mut db = Self {
             db: Arc::new(RwLock::new(DbAny::new_memory("test").unwrap())),
+            path: None,
+            deploy_locks: Locks::default(),
         };
 
-        db.init();
+        db.init().unwrap();
 
         db
     }
 }
+
+/// Returns `path` as a string, which is how the database takes it
+fn path_str(path: &Path) -> Result<&str, DbError> {
+    path.to_str().ok_or_else(|| {
+        let message = format!("{} isn't a valid UTF-8 path", path.display());
+        DbError::from(message.as_str())
+    })
+}
+
+/// Replace the database file at `path`, opened as `db`, with the backup at `backup` and open it
+/// again
+fn restore(db: &mut DbAny, backup: &Path, path: &Path) -> Result<(), DbError> {
+    // Close the database file before overwriting it
+    *db = DbAny::new_memory("restoring")?;
+    fs::copy(backup, path)?;
+    *db = DbAny::new_file(path_str(path)?)?;
+
+    Ok(())
+}
+
+/// Overwrite the stored model version
+fn set_model_version(db: &mut DbAny, version: u64) -> Result<(), DbError> {
+    db.exec_mut(
+        QueryBuilder::insert()
+            .values([[("version", version).into()]])
+            .search()
+            .from("model_version")
+            .where_()
+            .neighbor()
+            .query(),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use agdb::{DbAny, DbError, QueryBuilder};
+    use tempfile::tempdir;
+
+    use super::{Db, migrations::Migration, set_model_version};
+
+    fn rename_tools(db: &mut DbAny) -> Result<(), DbError> {
+        db.exec_mut(
+            QueryBuilder::insert()
+                .aliases("programs")
+                .ids("tools")
+                .query(),
+        )?;
+        Ok(())
+    }
+
+    fn fail_halfway(db: &mut DbAny) -> Result<(), DbError> {
+        rename_tools(db)?;
+        Err(DbError::from("halfway"))
+    }
+
+    fn has_alias(db: &Db, alias: &str) -> bool {
+        db.read()
+            .exec(QueryBuilder::select().ids(alias).query())
+            .is_ok()
+    }
+
+    #[test]
+    fn test_migrate() {
+        let dir = tempdir().unwrap();
+        let db = Db::open_file(&dir.path().join("data.db")).unwrap();
+        set_model_version(&mut db.write(), 1).unwrap();
+
+        let migrations: [(u64, Migration); 1] = [(1, rename_tools)];
+        db.migrate_with(&migrations).unwrap();
+
+        assert_eq!(db.model_version(), Some(2));
+        assert!(has_alias(&db, "programs"));
+        assert!(!has_alias(&db, "tools"));
+        // Steps from older versions are skipped
+        db.migrate_with(&migrations).unwrap();
+        assert_eq!(db.model_version(), Some(2));
+    }
+
+    #[test]
+    fn test_migrate_restores_on_failure() {
+        let dir = tempdir().unwrap();
+        let db = Db::open_file(&dir.path().join("data.db")).unwrap();
+        set_model_version(&mut db.write(), 1).unwrap();
+
+        let migrations: [(u64, Migration); 2] = [(1, rename_tools), (2, fail_halfway)];
+        assert!(db.migrate_with(&migrations).is_err());
+
+        // Back where it started, rather than at version 2 with the first step applied
+        assert_eq!(db.model_version(), Some(1));
+        assert!(has_alias(&db, "tools"));
+        assert!(!has_alias(&db, "programs"));
+    }
+}
@@ -1,7 +1,9 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
 use agdb::{DbElement, DbId, DbSerialize, DbValue};
-use strum::{Display, EnumIter};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use thiserror::Error;
 
 use crate::repository::entities::Uid;
 
@@ -25,6 +27,90 @@ pub enum DeployKind {
     BaldursGate3,
 }
 
+impl DeployKind {
+    /// Returns the file extensions of the plugins games of this kind load, if they have any
+    pub fn plugin_extensions(&self) -> &'static [&'static str] {
+        match self {
+            DeployKind::Gamebryo => &["esp", "esm"],
+            DeployKind::CreationEngine => &["esp", "esm", "esl"],
+            DeployKind::OpenMW => &["esp", "esm", "omwaddon", "omwscripts"],
+            DeployKind::Overlay | DeployKind::BaldursGate3 => &[],
+        }
+    }
+
+    /// Returns the directory of a game's install that mods are deployed into, relative to the
+    /// install directory
+    pub fn data_dir(&self) -> &'static str {
+        match self {
+            DeployKind::Overlay => "",
+            DeployKind::Gamebryo | DeployKind::CreationEngine | DeployKind::BaldursGate3 => "Data",
+            DeployKind::OpenMW => "Data Files",
+        }
+    }
+}
+
+/// How a game's mod files are linked into its targets when a profile is deployed
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    DbValue,
+    DbSerialize,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Display,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "title_case")]
+pub enum LinkStrategy {
+    /// Symbolic links pointing into the mod store.
+    #[default]
+    Symlink,
+    /// Hard links to the files of the mod store, for games that resolve symlinks oddly, like some
+    /// Windows games running through Proton. Files are copied instead when the targets are on
+    /// another filesystem than the library.
+    Hardlink,
+}
+
+/// Returned when parsing a [`DeployKind`] from a string that doesn't name one
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "Unknown deploy kind \"{0}\", expected one of: {}",
+    valid_deploy_kinds()
+)]
+pub struct ParseDeployKindError(pub String);
+
+fn valid_deploy_kinds() -> String {
+    DeployKind::iter()
+        .map(|kind| kind.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl FromStr for DeployKind {
+    type Err = ParseDeployKindError;
+
+    /// Parse a deploy kind from the name it's displayed with. Case, spaces and punctuation are
+    /// ignored, so `Creation Engine`, `creation-engine` and `creationengine` all work.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |name: &str| -> String {
+            name.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+
+        let wanted = normalize(s);
+        DeployKind::iter()
+            .find(|kind| normalize(&kind.to_string()) == wanted)
+            .ok_or_else(|| ParseDeployKindError(s.to_string()))
+    }
+}
+
 #[derive(Debug, Clone, DbElement, PartialEq, PartialOrd)]
 pub(crate) struct GameModel {
     db_id: Option<DbId>,
@@ -32,6 +118,7 @@ pub(crate) struct GameModel {
     name: String,
     targets: Vec<PathBuf>,
     deploy_kind: DeployKind,
+    link_strategy: LinkStrategy,
 }
 
 impl GameModel {
@@ -42,6 +129,37 @@ impl GameModel {
             name: name.to_string(),
             targets: Vec::new(),
             deploy_kind,
+            link_strategy: LinkStrategy::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use strum::IntoEnumIterator;
+
+    use super::{DeployKind, ParseDeployKindError};
+
+    #[test]
+    fn test_parse_deploy_kind() {
+        assert_eq!("Overlay".parse(), Ok(DeployKind::Overlay));
+        assert_eq!("Creation Engine".parse(), Ok(DeployKind::CreationEngine));
+        assert_eq!("creation-engine".parse(), Ok(DeployKind::CreationEngine));
+        assert_eq!("OPENMW".parse(), Ok(DeployKind::OpenMW));
+        assert_eq!("baldurs-gate-3".parse(), Ok(DeployKind::BaldursGate3));
+
+        // Every kind parses back from how it's displayed
+        for kind in DeployKind::iter() {
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+
+        let err = "Frostbite".parse::<DeployKind>().unwrap_err();
+        assert_eq!(err, ParseDeployKindError("Frostbite".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "Unknown deploy kind \"Frostbite\", expected one of: Overlay, Gamebryo, \
+             Creation Engine, OpenMW, Baldur's Gate 3"
+        );
+        assert!("".parse::<DeployKind>().is_err());
+    }
+}
@@ -19,7 +19,8 @@ pub(crate) use mods::*;
 pub(crate) use profiles::*;
 pub(crate) use tools::*;
 
-pub use games::DeployKind;
+pub use games::{DeployKind, LinkStrategy};
+pub use mods::StorageKind;
 
 use agdb::{DbId, DbType};
 
@@ -29,7 +30,7 @@ use agdb::{DbId, DbType};
 /// changes in a way that requires migration. It is independent of the
 /// Barnacle application version and is used solely to determine whether
 /// migrations need to be applied when initializing the database.
-pub(crate) const CURRENT_MODEL_VERSION: u64 = 1;
+pub(crate) const CURRENT_MODEL_VERSION: u64 = 7;
 
 /// Holds the model version of the local database. If this value is lower than
 /// [`CURRENT_MODEL_VERSION`], migrations will be performed until the database
@@ -6,16 +6,23 @@ use crate::repository::entities::Uid;
 pub(crate) struct ModEntryModel {
     db_id: Option<DbId>,
     uid: u64,
+    /// Index of the entry in its profile's load order. This mirrors the order of the linked list
+    /// of entries, so the order survives changes to how the graph is traversed.
+    position: u64,
     enabled: bool,
+    /// Locked entries can't be toggled, moved, or removed
+    locked: bool,
     notes: String,
 }
 
 impl ModEntryModel {
-    pub fn new(uid: Uid) -> Self {
+    pub fn new(uid: Uid, position: u64, enabled: bool) -> Self {
         Self {
             db_id: None,
             uid: uid.0,
-            enabled: true,
+            position,
+            enabled,
+            locked: false,
             notes: "".into(),
         }
     }
@@ -1,21 +1,51 @@
-use agdb::{DbElement, DbId};
+use agdb::{DbElement, DbId, DbSerialize, DbValue};
+use strum::{Display, EnumIter};
 
 use crate::repository::entities::Uid;
 
+/// How a mod's files are kept in the library.
+#[derive(
+    Debug, Clone, Default, DbValue, DbSerialize, Copy, PartialEq, PartialOrd, Display, EnumIter,
+)]
+#[strum(serialize_all = "title_case")]
+pub enum StorageKind {
+    /// The mod's archive is extracted into its directory when it's added.
+    #[default]
+    Loose,
+    /// The mod's archive is stored as-is, and only extracted when it needs to be deployed. This
+    /// saves space and inodes for mods with many files.
+    Archived,
+}
+
 #[derive(Debug, Clone, DbElement, PartialEq, PartialOrd)]
 pub(crate) struct ModModel {
     db_id: Option<DbId>,
     uid: u64,
     /// A human friendly display name
     name: String,
+    storage_kind: StorageKind,
+    /// The category the mod has been sorted into, empty if it hasn't been
+    category: String,
+    /// The version of the mod as its author numbers it. Like the other optional fields, it isn't
+    /// stored when unset, so mods from before it existed need no migration.
+    version: Option<String>,
+    /// Who made the mod
+    author: Option<String>,
+    /// Where the mod was downloaded from, for checking for updates
+    source_url: Option<String>,
 }
 
 impl ModModel {
-    pub fn new(uid: Uid, name: &str) -> Self {
+    pub fn new(uid: Uid, name: &str, storage_kind: StorageKind) -> Self {
         Self {
             db_id: None,
             uid: uid.0,
             name: name.into(),
+            storage_kind,
+            category: String::new(),
+            version: None,
+            author: None,
+            source_url: None,
         }
     }
 
@@ -7,6 +7,12 @@ pub(crate) struct ProfileModel {
     db_id: Option<DbId>,
     uid: u64,
     name: String,
+    /// How many times a tool was launched with this profile active
+    launch_count: u64,
+    /// When this profile was last launched, in seconds since the unix epoch
+    last_played: Option<u64>,
+    /// How long launched tools ran for in total, in seconds
+    play_seconds: u64,
 }
 
 impl ProfileModel {
@@ -15,6 +21,9 @@ impl ProfileModel {
             db_id: None,
             uid: uid.0,
             name: name.to_string(),
+            launch_count: 0,
+            last_played: None,
+            play_seconds: 0,
         }
     }
 
@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use agdb::DbId;
 use derive_more::PartialEq;
 
@@ -6,7 +8,7 @@ use crate::repository::{
     {db::Db, entities::Uid},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct EntityId {
     #[partial_eq(ignore)]
     db_id: DbId,
@@ -23,6 +25,11 @@ impl EntityId {
         })
     }
 
+    /// Get the stable [`Uid`] of the entity
+    pub fn uid(&self) -> Uid {
+        self.uid
+    }
+
     /// Get the underlying [`DbId`]. This will check to make sure it isn't stale before returning.
     pub fn db_id(&self, db: &Db) -> Result<DbId> {
         let uid = Uid::load(db, self.db_id).map_err(|err| {
@@ -49,3 +56,10 @@ impl EntityId {
         Ok(self.db_id)
     }
 }
+
+// Equality ignores the `DbId`, which can go stale, so hashing has to as well
+impl Hash for EntityId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uid.hash(state);
+    }
+}
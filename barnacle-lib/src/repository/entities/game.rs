@@ -1,21 +1,34 @@
 use std::{
     fmt::Debug,
     fs,
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use super::Error;
+use super::{Error, UniqueConstraint};
 use agdb::{CountComparison, DbId, DbValue, QueryBuilder};
-use heck::ToSnakeCase;
+use parking_lot::ReentrantMutex;
 use tracing::info;
 
-use crate::repository::{
-    Cfg,
-    db::{
-        Db,
-        models::{DeployKind, GameModel, ModModel},
+use crate::{
+    deploy::{self, LOADOUT_FILE, MANIFEST_FILE, Manifest, VANILLA_DIR},
+    fs::safe_dir_name,
+    progress::{Cancel, OnProgress},
+    repository::{
+        Cfg,
+        db::{
+            Db,
+            models::{DeployKind, GameModel, LinkStrategy, ModModel, StorageKind},
+        },
+        entities::{
+            EntityId, Result, Uid, get_field, get_optional_field,
+            mod_::Mod,
+            profile::{Profile, ProfileExport},
+            set_field, set_optional_field, sort_by_uid,
+            tool::{Launch, Tool},
+        },
     },
-    entities::{EntityId, Result, Uid, get_field, mod_::Mod, profile::Profile, set_field},
 };
 
 /// Represents a game entity in the Barnacle system.
@@ -40,47 +53,208 @@ impl Game {
         self.get_field("name")
     }
 
-    // TODO: Perform unique violation checking
+    /// Rename this game along with its directory. Fails with [`Error::UniqueViolation`] if
+    /// another game's directory would collide with the new name, and with [`Error::Deployed`]
+    /// while one of its profiles is deployed, as the deployed links lead into the directory.
     pub fn set_name(&self, new_name: &str) -> Result<()> {
         if new_name == self.name()? {
             return Ok(());
         }
+        for game in Game::iter(self.db.clone(), self.cfg.clone())? {
+            let game = game?;
+            if game != *self && safe_dir_name(&game.name()?) == safe_dir_name(new_name) {
+                return Err(Error::UniqueViolation(UniqueConstraint::GameName));
+            }
+        }
+        if self.is_deployed()? {
+            return Err(Error::Deployed);
+        }
 
         let old_dir = self.dir()?;
+        let new_dir = self.cfg.read().library_dir().join(safe_dir_name(new_name));
+        fs::rename(old_dir, new_dir)?;
 
-        self.set_field("name", new_name)?;
-
-        let new_dir = self.dir()?;
-        fs::rename(old_dir, new_dir).unwrap();
-
-        Ok(())
+        self.set_field("name", new_name)
     }
 
+    /// Returns the directories this game's profiles are deployed into, as they were set. They
+    /// may be symlinks or junctions, which are followed when deploying.
     pub fn targets(&self) -> Result<Vec<PathBuf>> {
         self.get_field("targets")
     }
 
+    pub fn set_targets(&self, new_targets: &[PathBuf]) -> Result<()> {
+        self.set_field("targets", new_targets.to_vec())
+    }
+
     pub fn deploy_kind(&self) -> Result<DeployKind> {
         self.get_field("deploy_kind")
     }
 
+    /// Change how this game is deployed. Fails with [`Error::Deployed`] if one of its profiles
+    /// is deployed, since it was deployed the old way.
     pub fn set_deploy_kind(&self, new_deploy_kind: DeployKind) -> Result<()> {
         if new_deploy_kind == self.deploy_kind()? {
             return Ok(());
         }
+        if self.is_deployed()? {
+            return Err(Error::Deployed);
+        }
 
         self.set_field("deploy_kind", new_deploy_kind)
     }
 
+    /// Returns how this game's profiles link mod files into its targets when deployed
+    pub fn link_strategy(&self) -> Result<LinkStrategy> {
+        self.get_field("link_strategy")
+    }
+
+    /// Change how this game's profiles link mod files into its targets. Fails with
+    /// [`Error::Deployed`] if one of its profiles is deployed, since it was linked the old way.
+    pub fn set_link_strategy(&self, new_link_strategy: LinkStrategy) -> Result<()> {
+        if new_link_strategy == self.link_strategy()? {
+            return Ok(());
+        }
+        if self.is_deployed()? {
+            return Err(Error::Deployed);
+        }
+
+        self.set_field("link_strategy", new_link_strategy)
+    }
+
+    /// Returns the directory this game reads its `plugins.txt` from, if it's been set. For
+    /// Gamebryo and Creation Engine games that's their folder under `%LOCALAPPDATA%`, which for
+    /// Proton is inside the prefix, e.g.
+    /// `compatdata/489830/pfx/drive_c/users/steamuser/AppData/Local/Skyrim Special Edition`.
+    pub fn plugins_dir(&self) -> Result<Option<PathBuf>> {
+        get_optional_field(&self.db, self.id, "plugins_dir")
+    }
+
+    /// Set where [`Profile::deploy`] writes the plugin order for the game to read, see
+    /// [`Game::plugins_dir`]. `None` keeps it in the profile's directory instead.
+    pub fn set_plugins_dir(&self, dir: Option<&Path>) -> Result<()> {
+        set_optional_field(&self.db, self.id, "plugins_dir", dir.map(Path::to_path_buf))
+    }
+
+    /// Like [`Game::set_deploy_kind`], but also points the targets at the new kind's
+    /// [`DeployKind::data_dir`]. A target inside the old kind's data directory is taken to be
+    /// the install directory's, anything else is treated as the install directory itself.
+    /// Returns the new targets.
+    pub fn switch_deploy_kind(&self, new_deploy_kind: DeployKind) -> Result<Vec<PathBuf>> {
+        let old_data_dir = self.deploy_kind()?.data_dir();
+        self.set_deploy_kind(new_deploy_kind)?;
+
+        let targets: Vec<PathBuf> = self
+            .targets()?
+            .into_iter()
+            .map(|target| {
+                let install_dir = match target.parent() {
+                    Some(parent) if !old_data_dir.is_empty() && target.ends_with(old_data_dir) => {
+                        parent.to_path_buf()
+                    }
+                    _ => target,
+                };
+                match new_deploy_kind.data_dir() {
+                    "" => install_dir,
+                    data_dir => install_dir.join(data_dir),
+                }
+            })
+            .collect();
+        self.set_targets(&targets)?;
+
+        Ok(targets)
+    }
+
     pub fn dir(&self) -> Result<PathBuf> {
-        Ok(self
-            .cfg
-            .read()
-            .library_dir()
-            .join(self.name()?.to_snake_case()))
+        self.dir_in(self.cfg.read().library_dir())
+    }
+
+    /// Returns the directory this game would have if the library was at `library_dir`
+    pub(crate) fn dir_in(&self, library_dir: &Path) -> Result<PathBuf> {
+        Ok(library_dir.join(safe_dir_name(&self.name()?)))
     }
 
+    /// Returns the path of the manifest recording what is currently deployed for this game
+    pub(crate) fn deployment_manifest(&self) -> Result<PathBuf> {
+        Ok(self.dir()?.join(MANIFEST_FILE))
+    }
+
+    /// Returns the path of the JSON file describing the deployed profile, for launchers and other
+    /// integrations to read. It's rewritten on every deploy and removed on undeploy. This is
+    /// separate from the manifest Barnacle keeps to undo deployments, which isn't meant to be
+    /// read by anything else.
+    ///
+    /// Its schema, at version 1, looks like:
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "game": "Skyrim",
+    ///   "profile": "Default",
+    ///   "mods": [
+    ///     {
+    ///       "name": "SkyUI",
+    ///       "version": "5.2",
+    ///       "author": "schlangster",
+    ///       "category": "Interface",
+    ///       "content_hash": "3f5a…"
+    ///     }
+    ///   ],
+    ///   "plugins": ["SkyUI_SE.esp"]
+    /// }
+    /// ```
+    ///
+    /// `mods` lists the enabled mods in load order, later ones winning conflicts. `version` is
+    /// whatever the mod was labelled with, so `content_hash` is what identifies its exact
+    /// content. `version`, `author`, `category` and `content_hash` are `null` when unknown.
+    /// `plugins` is the plugin load order, empty for games without plugins. `version` is bumped
+    /// whenever a field is changed or removed, but not when one is added.
+    pub fn deployment_manifest_path(&self) -> Result<PathBuf> {
+        Ok(self.dir()?.join(LOADOUT_FILE))
+    }
+
+    /// Where game files replaced by deployed links are kept
+    pub(crate) fn vanilla_dir(&self) -> Result<PathBuf> {
+        Ok(self.dir()?.join(VANILLA_DIR))
+    }
+
+    /// Returns `true` if one of this game's profiles is currently deployed
+    pub fn is_deployed(&self) -> Result<bool> {
+        Ok(self.deployment_manifest()?.exists())
+    }
+
+    /// Tear down the deployment recorded in `manifest`, and put back the config files changed by
+    /// the INI tweaks of the profile it deployed
+    pub(crate) fn undeploy_manifest(&self, manifest: &Manifest) -> Result<()> {
+        deploy::undeploy(manifest)?;
+        if let (Some(profile), Some(root)) = (
+            self.profile_by_uid(manifest.profile)?,
+            manifest.targets.first(),
+        ) {
+            profile.restore_ini_tweaks(root)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` while one of this game's profiles is being deployed or undeployed
+    pub fn is_deploying(&self) -> bool {
+        self.deploy_lock().is_locked()
+    }
+
+    /// The lock serializing deployments of this game with launching its tools
+    pub(crate) fn deploy_lock(&self) -> Arc<ReentrantMutex<()>> {
+        deploy::lock(self.db.deploy_locks(), self.id.uid().0)
+    }
+
+    /// Remove this game along with its profiles, mods and tools. Fails with [`Error::Deployed`]
+    /// while a profile is deployed, since the game files moved aside for it live in the game's
+    /// directory.
     pub fn remove(self) -> Result<()> {
+        if self.is_deployed()? {
+            return Err(Error::Deployed);
+        }
+
         for p in self.profiles()? {
             let profile_name = p.name().unwrap();
             p.remove()
@@ -108,6 +282,10 @@ impl Game {
                 })
         }
 
+        for tool in self.tools()? {
+            tool.remove()?;
+        }
+
         // We have to store these so we can still access them once the game is deleted
         let name = self.name()?;
         let dir = self.dir()?;
@@ -120,7 +298,9 @@ impl Game {
 
         // Bootstrap active game if there isn't one set
         if Game::active(self.db.clone(), self.cfg.clone())?.is_none()
-            && let Some(first_game) = Game::list(self.db.clone(), self.cfg.clone())?.first()
+            && let Some(first_game) = Game::iter(self.db.clone(), self.cfg.clone())?
+                .next()
+                .transpose()?
         {
             first_game.activate()?;
         }
@@ -135,12 +315,73 @@ impl Game {
     }
 
     pub fn profiles(&self) -> Result<Vec<Profile>> {
-        Profile::list(&self.db, &self.cfg, self)
+        self.profiles_iter()?.collect()
+    }
+
+    /// Returns how many profiles this game has, without loading them
+    pub fn profile_count(&self) -> Result<u64> {
+        Profile::count(&self.db, self)
+    }
+
+    /// Returns the profile of this game with the UID `uid`, if there still is one
+    pub(crate) fn profile_by_uid(&self, uid: u64) -> Result<Option<Profile>> {
+        for profile in self.profiles_iter()? {
+            let profile = profile?;
+            if profile.id.uid().0 == uid {
+                return Ok(Some(profile));
+            }
+        }
+
+        Ok(None)
     }
 
+    /// Returns the first of this game's profiles, if it has any
+    pub fn first_profile(&self) -> Result<Option<Profile>> {
+        Profile::first(&self.db, &self.cfg, self)
+    }
+
+    /// Like [`Game::profiles`], but loads each [`Profile`] lazily so callers that stop early
+    /// don't pay for the rest
+    pub fn profiles_iter(&self) -> Result<impl Iterator<Item = Result<Profile>>> {
+        Profile::iter(&self.db, &self.cfg, self)
+    }
+
+    /// Register a tool for this game, run as `path` with `args` split like a shell would
+    pub fn add_tool(&self, name: &str, path: PathBuf, args: Option<&str>) -> Result<Tool> {
+        Tool::add(self.db.clone(), self.cfg.clone(), self, name, path, args)
+    }
+
+    /// Returns the tools registered for this game
+    pub fn tools(&self) -> Result<Vec<Tool>> {
+        Tool::list_for(&self.db, &self.cfg, self)
+    }
+
+    /// Run `tool`, one of this game's tools, like [`Tool::run`]. If the game has an active
+    /// profile, the launch is counted towards it, and waiting on the returned [`Launch`] adds
+    /// how long the tool ran to its play time.
+    pub fn launch(&self, tool: &Tool) -> Result<Launch> {
+        let profile = self.active_profile()?;
+        Ok(Launch::new(tool.run()?, profile))
+    }
+
+    /// Like [`Game::launch`], but with the tool's output piped like [`Tool::run_captured`]
+    pub fn launch_captured(&self, tool: &Tool) -> Result<Launch> {
+        let profile = self.active_profile()?;
+        Ok(Launch::new(tool.run_captured()?, profile))
+    }
+
+    /// Returns this game's mods in the order they were added
     pub fn mods(&self) -> Result<Vec<Mod>> {
+        self.mods_iter()?.collect()
+    }
+
+    /// Like [`Game::mods`], but loads each [`Mod`] lazily so callers that stop early don't pay
+    /// for the rest
+    pub fn mods_iter(&self) -> Result<impl Iterator<Item = Result<Mod>>> {
         let db_id = self.id.db_id(&self.db)?;
-        Ok(self
+        let db = self.db.clone();
+        let cfg = self.cfg.clone();
+        let mut elements = self
             .db
             .read()
             .exec(
@@ -152,23 +393,61 @@ impl Game {
                     .neighbor()
                     .query(),
             )?
-            .elements
-            .iter()
-            .map(|e| Mod::load(e.id, self.db.clone(), self.cfg.clone()).unwrap())
-            .collect())
+            .elements;
+        sort_by_uid(&mut elements);
+
+        Ok(elements
+            .into_iter()
+            .map(move |e| Mod::load(e.id, db.clone(), cfg.clone())))
     }
 
+    /// Add a mod named `name`, with the files of the archive or directory at `path`, or empty if
+    /// no path is given. Directories are copied as they are.
     pub fn add_mod(&self, name: &str, path: Option<&Path>) -> Result<Mod> {
-        Mod::add(self.db.clone(), self.cfg.clone(), self, name, path)
+        self.add_mod_with_progress(name, path, &mut |_| {})
+    }
+
+    /// Like [`Game::add_mod`], reporting progress while the mod is extracted and indexed
+    pub fn add_mod_with_progress(
+        &self,
+        name: &str,
+        path: Option<&Path>,
+        on_progress: OnProgress,
+    ) -> Result<Mod> {
+        self.add_mod_cancelable(name, path, on_progress, &Cancel::new())
+    }
+
+    /// Like [`Game::add_mod_with_progress`], stopping with [`Error::Canceled`] once `cancel` is
+    /// set. The extraction is stopped at the next file and nothing of the mod is left behind.
+    pub fn add_mod_cancelable(
+        &self,
+        name: &str,
+        path: Option<&Path>,
+        on_progress: OnProgress,
+        cancel: &Cancel,
+    ) -> Result<Mod> {
+        Mod::add(self, name, path, StorageKind::Loose, on_progress, cancel)
+    }
+
+    /// Add a new [`Mod`] that keeps the given archive as-is rather than extracting it. See
+    /// [`StorageKind::Archived`].
+    pub fn add_archived_mod(&self, name: &str, archive: &Path) -> Result<Mod> {
+        Mod::add(
+            self,
+            name,
+            Some(archive),
+            StorageKind::Archived,
+            &mut |_| {},
+            &Cancel::new(),
+        )
     }
 
     /// Insert a new [`Game`] into the database. The [`Game`] must have a unique name.
     pub(crate) fn add(db: &Db, cfg: Cfg, name: &str, deploy_kind: DeployKind) -> Result<Self> {
-        if Game::list(db.clone(), cfg.clone())?
-            .iter()
-            .any(|g| g.name().unwrap() == name)
-        {
-            return Err(Error::DuplicateName);
+        for game in Game::iter(db.clone(), cfg.clone())? {
+            if safe_dir_name(&game?.name()?) == safe_dir_name(name) {
+                return Err(Error::UniqueViolation(UniqueConstraint::GameName));
+            }
         }
 
         let model = GameModel::new(Uid::new(db)?, name, deploy_kind);
@@ -199,7 +478,7 @@ impl Game {
 
         // Bootstrap active game if there isn't one set
         if Game::active(db.clone(), cfg.clone())?.is_none()
-            && let Some(first_game) = Game::list(db.clone(), cfg.clone())?.first()
+            && let Some(first_game) = Game::iter(db.clone(), cfg.clone())?.next().transpose()?
         {
             first_game.activate()?;
         }
@@ -210,7 +489,11 @@ impl Game {
     }
 
     pub(crate) fn list(db: Db, cfg: Cfg) -> Result<Vec<Game>> {
-        Ok(db
+        Game::iter(db, cfg)?.collect()
+    }
+
+    pub(crate) fn iter(db: Db, cfg: Cfg) -> Result<impl Iterator<Item = Result<Game>>> {
+        let mut elements = db
             .read()
             .exec(
                 QueryBuilder::select()
@@ -219,15 +502,18 @@ impl Game {
                     .from("games")
                     .query(),
             )?
-            .elements
-            .iter()
-            .map(|e| Game::load(e.id, db.clone(), cfg.clone()).unwrap())
-            .collect())
+            .elements;
+        sort_by_uid(&mut elements);
+
+        Ok(elements
+            .into_iter()
+            .map(move |e| Game::load(e.id, db.clone(), cfg.clone())))
     }
 
-    /// Search for a game by name
+    /// Find the game named `name`, ignoring case if no name matches exactly
     pub(crate) fn search(db: Db, cfg: Cfg, name: &str) -> Result<Option<Game>> {
-        db.read()
+        let exact = db
+            .read()
             .exec(
                 QueryBuilder::select()
                     .element::<GameModel>()
@@ -241,11 +527,40 @@ impl Game {
             .elements
             .first()
             .map(|g| Game::load(g.id, db.clone(), cfg.clone()))
-            .transpose()
+            .transpose()?;
+        if exact.is_some() {
+            return Ok(exact);
+        }
+
+        let name = name.to_lowercase();
+        for game in Game::iter(db, cfg)? {
+            let game = game?;
+            if game.name()?.to_lowercase() == name {
+                return Ok(Some(game));
+            }
+        }
+
+        Ok(None)
     }
 
-    /// Make this game the active one
+    /// Make this game the active one. If none of its profiles are active, the first one is
+    /// activated so there's always a profile to work with. Use
+    /// [`Game::activate_keep_profile`] to switch games without touching its profiles.
     pub fn activate(&self) -> Result<()> {
+        self.activate_keep_profile()?;
+
+        if self.active_profile()?.is_none()
+            && let Some(first_profile) = self.first_profile()?
+        {
+            first_profile.activate_in_game()?;
+        }
+
+        Ok(())
+    }
+
+    /// Make this game the active one, leaving its active profile as it is, even if there isn't
+    /// one
+    pub fn activate_keep_profile(&self) -> Result<()> {
         let db_id = self.id.db_id(&self.db)?;
         self.db.write().transaction_mut(|t| -> Result<()> {
             // Delete existing active_game, if it exists
@@ -297,11 +612,115 @@ impl Game {
             .transpose()
     }
 
+    /// Clear this game's active profile, leaving none of its profiles active. This doesn't
+    /// undeploy anything that's already deployed. It stays cleared until a profile is activated,
+    /// see [`Game::active_profile_cleared`].
+    pub fn clear_active_profile(&self) -> Result<()> {
+        let db_id = self.id.db_id(&self.db)?;
+        self.db.write().exec_mut(
+            QueryBuilder::remove()
+                .values("active")
+                .search()
+                .from(db_id)
+                .where_()
+                .edge()
+                .query(),
+        )?;
+
+        self.set_field("profile_cleared", true)
+    }
+
+    /// Returns `true` if this game has no active profile because it was cleared with
+    /// [`Game::clear_active_profile`], rather than because none was ever chosen
+    pub fn active_profile_cleared(&self) -> Result<bool> {
+        Ok(get_optional_field(&self.db, self.id, "profile_cleared")?.unwrap_or(false))
+    }
+
+    /// Returns this game's active profile, if it has one. Like [`Game::is_active`], this never
+    /// writes to the database.
     pub fn active_profile(&self) -> Result<Option<Profile>> {
         Profile::active(self.db.clone(), self.cfg.clone(), self.clone())
     }
 
-    /// Search for the given profile by name
+    /// Write the active profile's plugin order to `path` for external tools like LOOT. See
+    /// [`Profile::export_plugin_order`].
+    pub fn export_plugin_load_order(&self, path: &Path) -> Result<()> {
+        self.active_profile()?
+            .ok_or(Error::NoActiveProfile)?
+            .export_plugin_order(path)
+    }
+
+    /// Replace the active profile's plugin order with the one at `path`, like the
+    /// `loadorder.txt` LOOT writes. Returns the plugins no enabled mod provides. See
+    /// [`Profile::import_plugin_order`].
+    pub fn import_plugin_load_order(&self, path: &Path) -> Result<Vec<String>> {
+        self.active_profile()?
+            .ok_or(Error::NoActiveProfile)?
+            .import_plugin_order(path)
+    }
+
+    /// Add a profile named `new_name` with the load order in the file at `path`, written by
+    /// [`Profile::export`]. Entries use this game's mod of the same name, or an empty mod with
+    /// the exported metadata to install the files into if there's none. If importing fails
+    /// partway, the profile and placeholder mods added so far are removed again.
+    pub fn import_profile(&self, path: &Path, new_name: &str) -> Result<Profile> {
+        let export: ProfileExport = toml::from_str(&fs::read_to_string(path)?)?;
+        let mods = self
+            .mods()?
+            .into_iter()
+            .map(|mod_| Ok((mod_.name()?, mod_)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let profile = self.add_profile(new_name)?;
+        let mut placeholders = Vec::new();
+        if let Err(err) = self.import_entries(&profile, export, &mods, &mut placeholders) {
+            profile.remove()?;
+            for mod_ in placeholders {
+                mod_.remove()?;
+            }
+            return Err(err);
+        }
+
+        Ok(profile)
+    }
+
+    /// Add the entries of `export` to `profile` for [`Game::import_profile`], recording the
+    /// placeholder mods it adds in `placeholders`
+    fn import_entries(
+        &self,
+        profile: &Profile,
+        export: ProfileExport,
+        mods: &[(String, Mod)],
+        placeholders: &mut Vec<Mod>,
+    ) -> Result<()> {
+        let new_name = profile.name()?;
+        for exported in export.mods {
+            let existing = mods.iter().find(|(name, _)| *name == exported.name);
+            let mod_ = match existing {
+                Some((_, mod_)) => mod_.clone(),
+                None => {
+                    let mod_ = self.add_mod(&exported.name, None)?;
+                    placeholders.push(mod_.clone());
+                    mod_.set_version(exported.version.as_deref())?;
+                    mod_.set_author(exported.author.as_deref())?;
+                    mod_.set_source_url(exported.source_url.as_deref())?;
+                    info!(
+                        "Added placeholder mod {} for profile {new_name}",
+                        exported.name
+                    );
+                    mod_
+                }
+            };
+
+            let entry = profile.add_mod_entry(mod_)?;
+            entry.set_enabled_force(exported.enabled)?;
+            entry.set_notes(&exported.notes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Search for the given profile by name, ignoring case if no name matches exactly
     pub fn search_profile(&self, name: &str) -> Result<Option<Profile>> {
         Profile::search(self.db.clone(), self.cfg.clone(), self, name)
     }
@@ -328,8 +747,20 @@ impl PartialEq for Game {
     }
 }
 
+impl Eq for Game {}
+
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
+    use tempfile::tempdir;
+
     use crate::Repository;
 
     use super::*;
@@ -345,13 +776,60 @@ mod test {
 
         assert!(game1.dir().unwrap().exists());
         assert_eq!(games.len(), 2);
-        assert_eq!(games.first().unwrap().name().unwrap(), "Morrowind");
+        // Games are listed in the order they were added
+        assert_eq!(games.first().unwrap().name().unwrap(), "Skyrim");
         assert_eq!(
             games.last().unwrap().deploy_kind().unwrap(),
-            DeployKind::CreationEngine
+            DeployKind::OpenMW
         );
     }
 
+    #[test]
+    fn test_mods_order() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let other = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+
+        let names = ["Zebra", "Apple", "Mango"];
+        for name in names {
+            game.add_mod(name, None).unwrap();
+            other.add_mod(name, None).unwrap();
+        }
+
+        // Mods are listed in the order they were added, not by name
+        let mods: Vec<String> = game
+            .mods()
+            .unwrap()
+            .iter()
+            .map(|m| m.name().unwrap())
+            .collect();
+        assert_eq!(mods, names);
+        assert_eq!(
+            game.mods_iter()
+                .unwrap()
+                .map(|m| m.unwrap().name().unwrap())
+                .collect::<Vec<_>>(),
+            names
+        );
+    }
+
+    #[test]
+    fn test_iter() {
+        let repo = Repository::mock();
+
+        repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let morrowind = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+
+        assert_eq!(repo.games_iter().unwrap().count(), 2);
+
+        let found = repo
+            .games_iter()
+            .unwrap()
+            .map(Result::unwrap)
+            .find(|g| g.deploy_kind().unwrap() == DeployKind::OpenMW);
+        assert_eq!(found, Some(morrowind));
+    }
+
     #[test]
     fn test_add_duplicate() {
         let repo = Repository::mock();
@@ -360,10 +838,24 @@ mod test {
 
         assert!(matches!(
             repo.add_game("Morrowind", DeployKind::OpenMW),
-            Err(Error::DuplicateName)
+            Err(Error::UniqueViolation(UniqueConstraint::GameName))
         ));
     }
 
+    #[test]
+    fn test_add_colliding_dir() {
+        let repo = Repository::mock();
+
+        repo.add_game("Skyrim SE", DeployKind::CreationEngine)
+            .unwrap();
+
+        assert!(matches!(
+            repo.add_game("skyrim_se", DeployKind::CreationEngine),
+            Err(Error::UniqueViolation(UniqueConstraint::GameName))
+        ));
+        assert!(repo.add_game("!!!", DeployKind::Overlay).is_ok());
+    }
+
     #[test]
     fn test_remove() {
         let repo = Repository::mock();
@@ -386,6 +878,27 @@ mod test {
         assert_eq!(repo.games().unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_remove_deployed() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+        profile.deploy().unwrap();
+
+        let dir = game.dir().unwrap();
+        assert!(matches!(game.clone().remove(), Err(Error::Deployed)));
+        assert!(dir.exists());
+        assert_eq!(repo.games().unwrap().len(), 1);
+
+        profile.undeploy().unwrap();
+        game.remove().unwrap();
+        assert!(!dir.exists());
+        assert_eq!(repo.games().unwrap().len(), 0);
+    }
+
     #[test]
     fn test_remove_made_next_game_active() {
         let repo = Repository::mock();
@@ -410,6 +923,19 @@ mod test {
         assert_eq!(repo.games().unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_search() {
+        let repo = Repository::mock();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let oblivion = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+
+        assert_eq!(repo.search_game("Skyrim").unwrap(), Some(skyrim));
+        assert_eq!(repo.search_game("OBLIVION").unwrap(), Some(oblivion));
+        assert_eq!(repo.search_game("Morrowind").unwrap(), None);
+        // Only whole names match
+        assert_eq!(repo.search_game("sky").unwrap(), None);
+    }
+
     #[test]
     fn test_name() {
         let repo = Repository::mock();
@@ -432,6 +958,15 @@ mod test {
         game.set_name("Skyrim 3: Electric Boogaloo").unwrap();
 
         assert_eq!(game.name().unwrap(), "Skyrim 3: Electric Boogaloo");
+        assert!(game.dir().unwrap().exists());
+
+        // Renaming the directory would break the deployed links
+        let profile = game.add_profile("Test").unwrap();
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+        profile.deploy().unwrap();
+        assert!(matches!(game.set_name("Skyrim"), Err(Error::Deployed)));
+        assert_eq!(game.name().unwrap(), "Skyrim 3: Electric Boogaloo");
     }
 
     #[test]
@@ -445,6 +980,44 @@ mod test {
         game.deploy_kind().unwrap();
     }
 
+    #[test]
+    fn test_switch_deploy_kind() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Morrowind", DeployKind::Gamebryo).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let install_dir = tempdir().unwrap();
+        let data_dir = install_dir.path().join("Data");
+        fs::create_dir_all(&data_dir).unwrap();
+        game.set_targets(std::slice::from_ref(&data_dir)).unwrap();
+
+        let targets = game.switch_deploy_kind(DeployKind::OpenMW).unwrap();
+        assert_eq!(targets, vec![install_dir.path().join("Data Files")]);
+        assert_eq!(game.targets().unwrap(), targets);
+        assert_eq!(game.deploy_kind().unwrap(), DeployKind::OpenMW);
+
+        // Overlays deploy into the install directory itself
+        let targets = game.switch_deploy_kind(DeployKind::Overlay).unwrap();
+        assert_eq!(targets, vec![install_dir.path().to_path_buf()]);
+        let targets = game.switch_deploy_kind(DeployKind::Gamebryo).unwrap();
+        assert_eq!(targets, vec![data_dir]);
+
+        profile.deploy().unwrap();
+        assert!(matches!(
+            game.set_deploy_kind(DeployKind::CreationEngine),
+            Err(Error::Deployed)
+        ));
+        assert!(matches!(
+            game.switch_deploy_kind(DeployKind::OpenMW),
+            Err(Error::Deployed)
+        ));
+        assert_eq!(game.deploy_kind().unwrap(), DeployKind::Gamebryo);
+
+        profile.undeploy().unwrap();
+        game.set_deploy_kind(DeployKind::CreationEngine).unwrap();
+        assert_eq!(game.deploy_kind().unwrap(), DeployKind::CreationEngine);
+    }
+
     #[test]
     fn test_dir() {
         let repo = Repository::mock();
@@ -457,7 +1030,7 @@ mod test {
             .cfg
             .read()
             .library_dir()
-            .join(game.name().unwrap().to_snake_case());
+            .join(safe_dir_name(&game.name().unwrap()));
 
         assert_eq!(game.dir().unwrap(), expected_dir);
     }
@@ -473,4 +1046,66 @@ mod test {
         assert!(game.is_active().unwrap());
         assert_eq!(repo.active_game().unwrap().unwrap(), game);
     }
+
+    #[test]
+    fn test_activate_bootstraps_profile() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Default").unwrap();
+        game.clear_active_profile().unwrap();
+        assert_eq!(game.active_profile().unwrap(), None);
+
+        game.activate().unwrap();
+
+        assert!(game.is_active().unwrap());
+        assert_eq!(game.active_profile().unwrap(), Some(profile));
+    }
+
+    #[test]
+    fn test_profile_count() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        assert_eq!(game.profile_count().unwrap(), 0);
+        assert_eq!(game.first_profile().unwrap(), None);
+
+        game.add_profile("First").unwrap();
+        game.add_profile("Second").unwrap();
+        // Mods hang off the game too, and mustn't be counted
+        game.add_mod("Some Mod", None).unwrap();
+
+        assert_eq!(game.profile_count().unwrap(), 2);
+        assert_eq!(
+            game.first_profile().unwrap(),
+            game.profiles().unwrap().into_iter().next()
+        );
+    }
+
+    #[test]
+    fn test_activate_keep_profile() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        game.add_profile("Default").unwrap();
+        game.clear_active_profile().unwrap();
+
+        game.activate_keep_profile().unwrap();
+
+        assert!(game.is_active().unwrap());
+        assert_eq!(game.active_profile().unwrap(), None);
+    }
+
+    #[test]
+    fn test_hash() {
+        let repo = Repository::mock();
+        repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let mut games: HashSet<Game> = repo.games().unwrap().into_iter().collect();
+        // The same game, loaded a second time
+        games.insert(repo.search_game("Skyrim").unwrap().unwrap());
+
+        assert_eq!(games.len(), 2);
+    }
 }
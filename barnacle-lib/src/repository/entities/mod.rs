@@ -5,9 +5,13 @@
 //! these elements, handling all necessary operations behind the scenes.
 
 use crate::entities::entity_id::EntityId;
-use std::fmt::Debug;
+use std::{
+    fmt::{self, Debug},
+    io,
+    path::PathBuf,
+};
 
-use agdb::{DbId, DbValue, QueryBuilder};
+use agdb::{DbElement, DbId, DbValue, QueryBuilder};
 use derive_more::PartialEq;
 use thiserror::Error;
 
@@ -18,13 +22,15 @@ mod game;
 mod mod_;
 mod mod_entry;
 mod profile;
+mod staged_mod;
 mod tool;
 
 pub use game::Game;
 pub use mod_::Mod;
-pub use mod_entry::ModEntry;
+pub use mod_entry::{LoadOrderPlan, ModEntry};
 pub use profile::Profile;
-pub use tool::Tool;
+pub use staged_mod::StagedMod;
+pub use tool::{Launch, Tool};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -34,11 +40,90 @@ pub enum Error {
     Internal(#[from] agdb::DbError),
     #[error("This entity has been deleted")]
     RemovedEntity,
-    #[error("An entity with the given name already exists")]
-    DuplicateName,
+    #[error("This entity is no longer attached to its parent")]
+    Orphaned,
+    #[error("A {0} with the given name already exists")]
+    UniqueViolation(UniqueConstraint),
+    #[error("The given mod is already in this profile")]
+    DuplicateEntry,
+    #[error("The mod entry is locked")]
+    Locked,
+    #[error("Mods can't require each other in a cycle")]
+    DependencyCycle,
+    #[error("Profiles can't inherit from each other in a cycle")]
+    InheritanceCycle,
+    #[error("The profile belongs to another game")]
+    ForeignProfile,
+    #[error("The mod belongs to another game")]
+    ForeignMod,
+    #[error("The mod is required by {}", .0.join(", "))]
+    RequiredBy(Vec<String>),
+    #[error("The {0} field holds a value of another type than expected: {1}")]
+    FieldType(String, String),
+    #[error("Data root {0} is not a directory of the staged files")]
+    InvalidDataRoot(PathBuf),
+    #[error("The mod's source archive can't be found")]
+    MissingSource,
+    #[error("Archive entry {0} would be extracted outside of the mod directory")]
+    UnsafeArchivePath(String),
+    #[error("The archive doesn't contain any files")]
+    EmptyArchive,
+    #[error("The operation was canceled")]
+    Canceled,
+    #[error("The game is deployed, undeploy it first")]
+    Deployed,
+    #[error("The game has no deploy targets")]
+    NoTargets,
+    #[error("The game has no active profile")]
+    NoActiveProfile,
+    #[error("Deploy target {0} can't be resolved: {1}")]
+    UnresolvableTarget(PathBuf, #[source] io::Error),
+    #[error("Deploy target {0} is inside the library")]
+    TargetInLibrary(PathBuf),
+    #[error("An overlay is already mounted on {0}, unmount it first")]
+    StaleMount(PathBuf),
+    #[error("Mounting the overlay on {0} failed: {1}")]
+    MountFailed(PathBuf, String),
+    #[error("The tool arguments {0} have an unterminated quote")]
+    UnterminatedQuote(String),
+    #[error("The library directory {0} does not exist")]
+    MissingLibrary(PathBuf),
+    #[error("The bundle was made by a newer version of Barnacle (model version {0})")]
+    UnsupportedBundle(u64),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Archive error: {0}")]
+    Archive(#[from] compress_tools::Error),
+    #[error("Bundle error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Failed to parse TOML: {0}")]
+    TomlDeserialize(#[from] toml::de::Error),
+    #[error("Failed to serialize TOML: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+/// The naming rule an entity would break, see [`Error::UniqueViolation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniqueConstraint {
+    /// No two games share a name
+    GameName,
+    /// No two profiles of a game share a name
+    ProfileName,
+    /// No two mods of a game share a name
+    ModName,
+}
+
+impl fmt::Display for UniqueConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UniqueConstraint::GameName => "game",
+            UniqueConstraint::ProfileName => "profile",
+            UniqueConstraint::ModName => "mod",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd)]
 pub struct Uid(pub u64);
 
 impl Uid {
@@ -86,6 +171,19 @@ impl Uid {
     }
 }
 
+/// Sort elements, selected along with their values, by their UID. UIDs are handed out in
+/// increasing order, so this is the order the elements were added in.
+pub(crate) fn sort_by_uid(elements: &mut [DbElement]) {
+    let uid_key = DbValue::from("uid");
+    elements.sort_by_cached_key(|e| {
+        e.values
+            .iter()
+            .find(|kv| kv.key == uid_key)
+            .and_then(|kv| kv.value.to_u64().ok())
+            .unwrap_or_default()
+    });
+}
+
 pub(crate) fn get_field<T>(db: &Db, id: EntityId, field: &str) -> Result<T>
 where
     T: TryFrom<DbValue>,
@@ -103,7 +201,40 @@ where
         .expect("the given field must have a value")
         .value;
 
-    Ok(T::try_from(value).expect("conversion from a `DbValue` must succeed"))
+    convert(field, value)
+}
+
+/// Like [`get_field`], but returns `None` if the field has never been set on the element. This is
+/// used for values that live alongside a model rather than inside it.
+pub(crate) fn get_optional_field<T>(db: &Db, id: EntityId, field: &str) -> Result<Option<T>>
+where
+    T: TryFrom<DbValue>,
+    T::Error: Debug,
+{
+    let db_id = id.db_id(db)?;
+    let key = DbValue::from(field);
+    let value = db
+        .read()
+        .exec(QueryBuilder::select().ids(db_id).query())?
+        .elements
+        .pop()
+        .expect("the given element must exist")
+        .values
+        .into_iter()
+        .find(|kv| kv.key == key)
+        .map(|kv| kv.value);
+
+    value.map(|v| convert(field, v)).transpose()
+}
+
+/// Convert the stored value of `field`, failing with [`Error::FieldType`] if it holds another
+/// type than the one asked for
+fn convert<T>(field: &str, value: DbValue) -> Result<T>
+where
+    T: TryFrom<DbValue>,
+    T::Error: Debug,
+{
+    T::try_from(value).map_err(|err| Error::FieldType(field.to_string(), format!("{err:?}")))
 }
 
 pub(crate) fn set_field<T>(db: &Db, id: EntityId, field: &str, value: T) -> Result<()>
@@ -120,3 +251,56 @@ where
 
     Ok(())
 }
+
+/// Set `field` to `value`, or remove it if `value` is `None` so it reads as unset with
+/// [`get_optional_field`]
+pub(crate) fn set_optional_field<T>(
+    db: &Db,
+    id: EntityId,
+    field: &str,
+    value: Option<T>,
+) -> Result<()>
+where
+    T: Into<DbValue>,
+{
+    let Some(value) = value else {
+        let db_id = id.db_id(db)?;
+        db.write()
+            .exec_mut(QueryBuilder::remove().values(field).ids(db_id).query())?;
+        return Ok(());
+    };
+
+    set_field(db, id, field, value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Repository,
+        repository::{
+            DeployKind,
+            entities::{Error, get_field, get_optional_field, set_field},
+        },
+    };
+
+    #[test]
+    fn test_field_type_mismatch() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        assert!(matches!(
+            get_field::<bool>(&game.db, game.id, "name"),
+            Err(Error::FieldType(field, _)) if field == "name"
+        ));
+
+        set_field(&game.db, game.id, "extra", "not a bool").unwrap();
+        assert!(matches!(
+            get_optional_field::<bool>(&game.db, game.id, "extra"),
+            Err(Error::FieldType(field, _)) if field == "extra"
+        ));
+        assert_eq!(
+            get_optional_field::<String>(&game.db, game.id, "extra").unwrap(),
+            Some("not a bool".to_string())
+        );
+    }
+}
@@ -1,23 +1,34 @@
 use std::{
     fmt::Debug,
     fs::{self, File},
-    path::{Path, PathBuf},
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use agdb::{DbId, DbValue, QueryBuilder, QueryId};
-use compress_tools::{Ownership, uncompress_archive};
-use heck::ToSnakeCase;
-use tracing::info;
+use agdb::{CountComparison, DbElement, DbId, DbValue, QueryBuilder, QueryId};
+use compress_tools::{
+    ArchiveContents, ArchiveIterator, Ownership, list_archive_files, uncompress_archive,
+};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+use walkdir::WalkDir;
 
 use crate::{
-    fs::{Permissions, change_dir_permissions},
+    fs::{Permissions, change_dir_permissions, safe_dir_name},
+    progress::{Cancel, OnProgress, Phase, Progress},
     repository::{
         Cfg,
         db::{
             Db,
-            models::{GameModel, ModModel},
+            models::{GameModel, ModEntryModel, ModModel, StorageKind},
+        },
+        entities::{
+            EntityId, Error, Result, Uid, UniqueConstraint, game::Game, get_field,
+            get_optional_field, mod_entry::ModEntry, profile::Profile, set_field,
+            set_optional_field, staged_mod::StagedMod,
         },
-        entities::{EntityId, Error, Result, Uid, game::Game, get_field, set_field},
     },
 };
 
@@ -38,19 +49,363 @@ impl Mod {
         Ok(Self { id, db, cfg })
     }
 
+    /// Load a [`Mod`] by its [`Uid`], if it still exists
+    pub(crate) fn load_by_uid(db: Db, cfg: Cfg, uid: Uid) -> Result<Option<Self>> {
+        db.read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<ModModel>()
+                    .search()
+                    .from("mods")
+                    .where_()
+                    .key("uid")
+                    .value(uid.0)
+                    .query(),
+            )?
+            .elements
+            .first()
+            .map(|m| Mod::load(m.id, db.clone(), cfg.clone()))
+            .transpose()
+    }
+
     pub fn name(&self) -> Result<String> {
         self.get_field("name")
     }
 
+    /// Rename this mod, moving its files along. Fails with [`Error::UniqueViolation`] if another
+    /// mod of the game would collide with the new name, and with [`Error::Deployed`] while the
+    /// game is deployed, since the deployed links point at the old location.
+    pub fn set_name(&self, new_name: &str) -> Result<()> {
+        if new_name == self.name()? {
+            return Ok(());
+        }
+
+        let game = self.parent()?;
+        for mod_ in game.mods_iter()? {
+            let mod_ = mod_?;
+            if mod_ != *self && safe_dir_name(&mod_.name()?) == safe_dir_name(new_name) {
+                return Err(Error::UniqueViolation(UniqueConstraint::ModName));
+            }
+        }
+        if game.is_deployed()? {
+            return Err(Error::Deployed);
+        }
+
+        self.clear_unpacked()?;
+        let old_path = self.source_path()?;
+        let new_path = match self.storage_kind()? {
+            StorageKind::Loose => old_path.with_file_name(safe_dir_name(new_name)),
+            StorageKind::Archived => old_path
+                .with_file_name(safe_dir_name(new_name))
+                .with_extension("archive"),
+        };
+        // Moved first, so a failed rename leaves the mod pointing at its files
+        if old_path.exists() {
+            fs::rename(old_path, new_path)?;
+        }
+
+        self.set_field("name", new_name)
+    }
+
     pub fn dir(&self) -> Result<PathBuf> {
         Ok(self
             .parent()?
             .dir()?
             .join("mods")
-            .join(self.name()?.to_snake_case()))
+            .join(safe_dir_name(&self.name()?)))
+    }
+
+    pub fn storage_kind(&self) -> Result<StorageKind> {
+        self.get_field("storage_kind")
     }
 
-    /// Returns the parent [`Game`] of this [`Mod`]
+    /// Returns the path to the stored archive of an [`StorageKind::Archived`] mod
+    pub fn archive_path(&self) -> Result<PathBuf> {
+        Ok(self.dir()?.with_extension("archive"))
+    }
+
+    /// Returns the directory containing this mod's files, ready to be deployed. Archived mods
+    /// are extracted on demand into a scratch directory, which can be cleaned up again with
+    /// [`Mod::clear_unpacked`].
+    pub fn content_dir(&self) -> Result<PathBuf> {
+        match self.storage_kind()? {
+            StorageKind::Loose => self.dir(),
+            StorageKind::Archived => {
+                let dir = self.unpacked_dir()?;
+                if !dir.exists() {
+                    let archive_path = self.archive_path()?;
+                    check_archive(&archive_path)?;
+                    let archive = File::open(archive_path)?;
+                    uncompress_archive(archive, &dir, Ownership::Preserve)?;
+                }
+
+                Ok(dir)
+            }
+        }
+    }
+
+    /// Remove the extracted files of an archived mod, if any
+    pub fn clear_unpacked(&self) -> Result<()> {
+        let dir = self.unpacked_dir()?;
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn unpacked_dir(&self) -> Result<PathBuf> {
+        Ok(self
+            .parent()?
+            .dir()?
+            .join("unpacked")
+            .join(safe_dir_name(&self.name()?)))
+    }
+
+    /// Returns the relative paths of every file in this mod, in sorted order.
+    ///
+    /// The list comes from an index stored in the database, so the mod directory only has to be
+    /// listed again when something in it has changed since it was last indexed.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let indexed_at: Option<u64> = get_optional_field(&self.db, self.id, "indexed_at")?;
+        if indexed_at.is_none() || indexed_at != Some(tree_modified(&self.source_path()?)) {
+            self.reindex()?;
+        }
+
+        let files: Vec<String> = self.get_field("files")?;
+
+        Ok(files.into_iter().map(PathBuf::from).collect())
+    }
+
+    /// Returns the plugin files this mod provides, based on the plugin extensions of the parent
+    /// game's [`DeployKind`](crate::repository::DeployKind). Only files at the root of the mod
+    /// are considered, since that's the only place games load them from.
+    ///
+    /// This is derived from the file index, so it's as cheap as [`Mod::files`].
+    pub fn plugins(&self) -> Result<Vec<PathBuf>> {
+        let extensions = self.parent()?.deploy_kind()?.plugin_extensions();
+        if extensions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .files()?
+            .into_iter()
+            .filter(|file| file.components().count() == 1)
+            .filter(|file| {
+                file.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| extensions.iter().any(|p| p.eq_ignore_ascii_case(e)))
+            })
+            .collect())
+    }
+
+    /// Rebuild the file index for this mod by walking its directory, or listing its archive
+    pub fn reindex(&self) -> Result<()> {
+        let source = self.source_path()?;
+
+        let mut files = Vec::new();
+        match self.storage_kind()? {
+            StorageKind::Loose => {
+                for entry in WalkDir::new(&source) {
+                    let entry = entry.map_err(io::Error::from)?;
+                    if entry.file_type().is_dir() {
+                        continue;
+                    }
+
+                    if let Ok(relative) = entry.path().strip_prefix(&source) {
+                        files.push(relative.to_string_lossy().into_owned());
+                    }
+                }
+            }
+            StorageKind::Archived => {
+                files = list_archive_files(File::open(&source)?)?
+                    .into_iter()
+                    .filter(|f| !f.ends_with('/'))
+                    .collect();
+            }
+        }
+        files.sort();
+
+        debug!("Indexed {} files for mod: {}", files.len(), self.name()?);
+
+        self.set_field("files", files)?;
+        self.set_field("indexed_at", tree_modified(&source))
+    }
+
+    /// Returns the hash of the mod's content recorded when it was installed, or last verified
+    pub fn content_hash(&self) -> Result<Option<String>> {
+        get_optional_field(&self.db, self.id, "content_hash")
+    }
+
+    /// Hash the mod's content and check it against the stored [`Mod::content_hash`]. Returns
+    /// `false` if any file was changed, added or removed since. A mod without a stored hash has
+    /// its current one recorded and is considered intact.
+    pub fn verify(&self) -> Result<bool> {
+        let hash = self.hash_content()?;
+        match self.content_hash()? {
+            Some(stored) => Ok(stored == hash),
+            None => {
+                self.set_field("content_hash", hash)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Compute a SHA-256 hash over the path and contents of every file in the mod, or over the
+    /// archive itself for archived mods
+    pub(crate) fn hash_content(&self) -> Result<String> {
+        let source = self.source_path()?;
+        let mut hasher = Sha256::new();
+
+        match self.storage_kind()? {
+            StorageKind::Loose => {
+                let entries = WalkDir::new(&source).sort_by_file_name();
+                for entry in entries {
+                    let entry = entry.map_err(io::Error::from)?;
+                    if entry.file_type().is_dir() {
+                        continue;
+                    }
+
+                    if let Ok(relative) = entry.path().strip_prefix(&source) {
+                        hasher.update(relative.to_string_lossy().as_bytes());
+                        hasher.update([0]);
+                    }
+                    io::copy(&mut File::open(entry.path())?, &mut hasher)?;
+                }
+            }
+            StorageKind::Archived => {
+                io::copy(&mut File::open(&source)?, &mut hasher)?;
+            }
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Returns the path the mod's files are stored at, which is either its directory or its
+    /// archive
+    fn source_path(&self) -> Result<PathBuf> {
+        match self.storage_kind()? {
+            StorageKind::Loose => self.dir(),
+            StorageKind::Archived => self.archive_path(),
+        }
+    }
+
+    /// Returns the category this mod has been sorted into, or an empty string if it hasn't been
+    pub fn category(&self) -> Result<String> {
+        self.get_field("category")
+    }
+
+    pub fn set_category(&self, category: &str) -> Result<()> {
+        self.set_field("category", category)
+    }
+
+    /// Returns the version of this mod, if it's known
+    pub fn version(&self) -> Result<Option<String>> {
+        get_optional_field(&self.db, self.id, "version")
+    }
+
+    pub fn set_version(&self, version: Option<&str>) -> Result<()> {
+        set_optional_field(&self.db, self.id, "version", version)
+    }
+
+    /// Returns who made this mod, if it's known
+    pub fn author(&self) -> Result<Option<String>> {
+        get_optional_field(&self.db, self.id, "author")
+    }
+
+    pub fn set_author(&self, author: Option<&str>) -> Result<()> {
+        set_optional_field(&self.db, self.id, "author", author)
+    }
+
+    /// Returns the URL of the page this mod was downloaded from, if it's known
+    pub fn source_url(&self) -> Result<Option<String>> {
+        get_optional_field(&self.db, self.id, "source_url")
+    }
+
+    pub fn set_source_url(&self, url: Option<&str>) -> Result<()> {
+        set_optional_field(&self.db, self.id, "source_url", url)
+    }
+
+    /// Returns whether this mod's files are left writable even though
+    /// [`Repository::enforce_readonly_mods`](crate::Repository::enforce_readonly_mods) is on
+    pub fn editable(&self) -> Result<bool> {
+        Ok(get_optional_field(&self.db, self.id, "editable")?.unwrap_or(false))
+    }
+
+    /// Leave this mod's files writable so they can be edited in place, or make them read-only
+    /// again. The files on disk are updated right away. Edits aren't reflected in
+    /// [`Mod::content_hash`], so [`Mod::verify`] reports edited mods as changed.
+    pub fn set_editable(&self, editable: bool) -> Result<()> {
+        self.set_field("editable", editable)?;
+
+        let dir = self.dir()?;
+        if self.storage_kind()? == StorageKind::Loose && dir.exists() {
+            let permissions = if self.read_only()? {
+                Permissions::ReadOnly
+            } else {
+                Permissions::ReadWrite
+            };
+            change_dir_permissions(&dir, permissions)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether this mod's files should be made read-only when they're installed
+    pub(crate) fn read_only(&self) -> Result<bool> {
+        Ok(self.cfg.read().enforce_readonly_mods() && !self.editable()?)
+    }
+
+    /// Returns the file name and size of the archive this mod was installed from, or `None` if
+    /// it was created empty
+    pub fn source_archive(&self) -> Result<Option<(String, u64)>> {
+        let name: Option<String> = get_optional_field(&self.db, self.id, "source_name")?;
+        let size: Option<u64> = get_optional_field(&self.db, self.id, "source_size")?;
+
+        Ok(name.zip(size))
+    }
+
+    /// Returns the path of the archive or directory this mod was installed from, or `None` if it
+    /// was created empty or installed before sources were recorded. The source may have been
+    /// moved or deleted since.
+    pub fn source(&self) -> Result<Option<PathBuf>> {
+        let path: Option<String> = get_optional_field(&self.db, self.id, "source_path")?;
+
+        Ok(path.map(PathBuf::from))
+    }
+
+    /// Returns how many bytes this mod takes up in the library: the size of its files, or of its
+    /// archive for archived mods. A mod whose files are missing takes up nothing.
+    pub fn size(&self) -> Result<u64> {
+        let path = self.source_path()?;
+        if !path.exists() {
+            return Ok(0);
+        }
+
+        let mut size = 0;
+        for entry in WalkDir::new(path) {
+            let metadata = entry
+                .map_err(io::Error::from)?
+                .metadata()
+                .map_err(io::Error::from)?;
+            if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Returns `true` if this mod's directory, or archive for archived mods, is gone. Such a mod
+    /// can be brought back with [`Mod::reinstall`].
+    pub fn files_missing(&self) -> Result<bool> {
+        Ok(!self.source_path()?.exists())
+    }
+
+    /// Returns the parent [`Game`] of this [`Mod`]. Only a game linked to the mod itself counts,
+    /// not one reached through the profiles using it, so a mod whose game link is gone is
+    /// [`Error::Orphaned`].
     pub fn parent(&self) -> Result<Game> {
         let db_id = self.id.db_id(&self.db)?;
         let parent_game_id = self
@@ -63,35 +418,220 @@ impl Mod {
                     // Reverse search to parent game from mod
                     .to(db_id)
                     .limit(1)
+                    .where_()
+                    .element::<GameModel>()
+                    .and()
+                    // Past the edge leading into the mod
+                    .distance(CountComparison::Equal(2))
                     .query(),
             )?
             .elements
             .pop()
-            .expect("a Mod should have a parent Game")
+            .ok_or(Error::Orphaned)?
             .id;
 
         Game::load(parent_game_id, self.db.clone(), self.cfg.clone())
     }
 
+    /// Returns the entries for this mod, across all profiles
+    pub(crate) fn entries(&self) -> Result<Vec<ModEntry>> {
+        let db_id = self.id.db_id(&self.db)?;
+        let entry_ids: Vec<DbId> = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::search()
+                    // Reverse search to the entries pointing at the mod
+                    .to(db_id)
+                    .where_()
+                    .element::<ModEntryModel>()
+                    .and()
+                    .distance(CountComparison::Equal(2))
+                    .query(),
+            )?
+            .elements
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+
+        entry_ids
+            .into_iter()
+            .map(|entry_id| ModEntry::load(entry_id, db_id, self.db.clone(), self.cfg.clone()))
+            .collect()
+    }
+
+    /// Returns the profiles with an entry for this mod, in the order they were added
+    pub fn used_by(&self) -> Result<Vec<Profile>> {
+        let mut profiles = Vec::new();
+        for entry in self.entries()? {
+            let profile = entry.parent()?;
+            if !profiles.contains(&profile) {
+                profiles.push(profile);
+            }
+        }
+        profiles.sort_by_key(|p| p.id.uid().0);
+
+        Ok(profiles)
+    }
+
+    /// Returns the mods this mod requires.
+    ///
+    /// Requirements are stored as edges from a mod to the mods it requires. They're the only
+    /// edges going out of a mod.
+    pub fn requires(&self) -> Result<Vec<Mod>> {
+        let db_id = self.id.db_id(&self.db)?;
+        let elements = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::search()
+                    .from(db_id)
+                    .where_()
+                    .neighbor()
+                    .and()
+                    .element::<ModModel>()
+                    .query(),
+            )?
+            .elements;
+
+        self.load_all(&elements)
+    }
+
+    /// Returns the mods that require this mod
+    pub fn required_by(&self) -> Result<Vec<Mod>> {
+        let db_id = self.id.db_id(&self.db)?;
+        let elements = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::search()
+                    .to(db_id)
+                    .where_()
+                    .neighbor()
+                    .and()
+                    .element::<ModModel>()
+                    .query(),
+            )?
+            .elements;
+
+        self.load_all(&elements)
+    }
+
+    fn load_all(&self, elements: &[DbElement]) -> Result<Vec<Mod>> {
+        elements
+            .iter()
+            .map(|e| Mod::load(e.id, self.db.clone(), self.cfg.clone()))
+            .collect()
+    }
+
+    /// Record that this mod requires `other`. Fails with [`Error::ForeignMod`] if `other`
+    /// belongs to another game, and with [`Error::DependencyCycle`] if `other` already requires
+    /// this mod, directly or through other mods.
+    pub fn add_requirement(&self, other: &Mod) -> Result<()> {
+        if self.parent()? != other.parent()? {
+            return Err(Error::ForeignMod);
+        }
+
+        let db_id = self.id.db_id(&self.db)?;
+        let other_id = other.id.db_id(&self.db)?;
+
+        // Everything reachable from `other` is something it requires, including itself
+        let cycle = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::search()
+                    .from(other_id)
+                    .where_()
+                    .element::<ModModel>()
+                    .query(),
+            )?
+            .elements
+            .iter()
+            .any(|e| e.id == db_id);
+        if cycle {
+            return Err(Error::DependencyCycle);
+        }
+
+        if self.requires()?.contains(other) {
+            return Ok(());
+        }
+
+        self.db.write().exec_mut(
+            QueryBuilder::insert()
+                .edges()
+                .from(db_id)
+                .to(other_id)
+                .query(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn remove_requirement(&self, other: &Mod) -> Result<()> {
+        let db_id = self.id.db_id(&self.db)?;
+        let other_id = other.id.db_id(&self.db)?;
+        self.db.write().exec_mut(
+            QueryBuilder::remove()
+                .search()
+                .from(db_id)
+                .to(other_id)
+                .where_()
+                .edge()
+                .query(),
+        )?;
+
+        Ok(())
+    }
+
+    /// List every mod in the library, across all games
+    pub(crate) fn list(db: Db, cfg: Cfg) -> Result<Vec<Mod>> {
+        db.read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<ModModel>()
+                    .search()
+                    .from("mods")
+                    .query(),
+            )?
+            .elements
+            .iter()
+            .map(|e| Mod::load(e.id, db.clone(), cfg.clone()))
+            .collect()
+    }
+
     pub(crate) fn add(
-        db: Db,
-        cfg: Cfg,
         game: &Game,
         name: &str,
         path: Option<&Path>,
+        storage_kind: StorageKind,
+        on_progress: OnProgress,
+        cancel: &Cancel,
     ) -> Result<Self> {
-        let model = ModModel::new(Uid::new(&db)?, name);
-        if game
-            .mods()?
-            .iter()
-            .any(|m: &Mod| m.name().unwrap() == model.name())
-        {
-            return Err(Error::DuplicateName);
+        let db = game.db.clone();
+        let cfg = game.cfg.clone();
+
+        // There's nothing to keep archived without a source archive
+        let storage_kind = if path.is_some_and(Path::is_file) {
+            storage_kind
+        } else {
+            StorageKind::Loose
+        };
+
+        let model = ModModel::new(Uid::new(&db)?, name, storage_kind);
+        for mod_ in game.mods_iter()? {
+            if safe_dir_name(&mod_?.name()?) == safe_dir_name(model.name()) {
+                return Err(Error::UniqueViolation(UniqueConstraint::ModName));
+            }
+        }
+
+        if let Some(path) = path.filter(|path| !path.is_dir()) {
+            check_archive(path)?;
         }
 
         let game_id = game.id.db_id(&db)?;
 
-        let model = ModModel::new(Uid::new(&db)?, name);
+        let model = ModModel::new(Uid::new(&db)?, name, storage_kind);
         let mod_id = db.write().transaction_mut(|t| -> Result<DbId> {
             let mod_id = t
                 .exec_mut(QueryBuilder::insert().element(model).query())?
@@ -114,29 +654,205 @@ impl Mod {
 
         let mod_ = Mod::load(mod_id, db.clone(), cfg.clone())?;
 
-        // TODO: Only attempt to open the archive if the input_path is an archive
-        if let Some(path) = path {
-            let archive = File::open(path).unwrap();
-            uncompress_archive(archive, &mod_.dir()?, Ownership::Preserve).unwrap();
-            change_dir_permissions(&mod_.dir()?, Permissions::ReadOnly);
-        } else {
-            let path = mod_.dir()?;
-            fs::create_dir_all(path).unwrap();
+        match path {
+            Some(path) => {
+                if let Err(err) = mod_.install_from(path, on_progress, cancel) {
+                    // Don't leave a mod without any files behind
+                    db.write()
+                        .exec_mut(QueryBuilder::remove().ids(mod_id).query())?;
+                    return Err(err);
+                }
+            }
+            None => {
+                on_progress(Progress::new(Phase::Install, 0, 1, None));
+                if let Err(err) = fs::create_dir_all(mod_.dir()?) {
+                    db.write()
+                        .exec_mut(QueryBuilder::remove().ids(mod_id).query())?;
+                    return Err(err.into());
+                }
+                on_progress(Progress::new(Phase::Install, 1, 1, None));
+            }
         };
 
+        if cancel.is_canceled() {
+            mod_.remove()?;
+            return Err(Error::Canceled);
+        }
+
+        let dir = Some(mod_.dir()?);
+        on_progress(Progress::new(Phase::Index, 0, 1, dir.clone()));
+        mod_.reindex()?;
+        mod_.set_field("content_hash", mod_.hash_content()?)?;
+        on_progress(Progress::new(Phase::Index, 1, 1, dir));
+
         Ok(mod_)
     }
 
+    /// Extract or copy the archive at `path` into this mod's storage, and record it as the
+    /// mod's source. A directory is copied into the mod's directory instead. Fails with
+    /// [`Error::EmptyArchive`] if there are no files to install, or with [`Error::Canceled`] once
+    /// `cancel` is set, leaving no directory behind either way.
+    fn install_from(&self, path: &Path, on_progress: OnProgress, cancel: &Cancel) -> Result<()> {
+        match self.storage_kind()? {
+            StorageKind::Loose => {
+                let dir = self.dir()?;
+                let installed = if path.is_dir() {
+                    copy_dir(path, &dir, on_progress, cancel)
+                } else {
+                    extract(path, &dir, on_progress, cancel)
+                };
+                let extracted = installed.and_then(|()| {
+                    // Some malformed archives extract without an error, but also without any
+                    // files
+                    if contains_files(&dir) {
+                        Ok(())
+                    } else {
+                        Err(Error::EmptyArchive)
+                    }
+                });
+                if let Err(err) = extracted {
+                    if dir.exists() {
+                        fs::remove_dir_all(&dir)?;
+                    }
+                    return Err(err);
+                }
+                if self.read_only()? {
+                    change_dir_permissions(&dir, Permissions::ReadOnly)?;
+                }
+            }
+            StorageKind::Archived => {
+                let has_files = list_archive_files(File::open(path)?)?
+                    .iter()
+                    .any(|f| !f.ends_with('/'));
+                if !has_files {
+                    return Err(Error::EmptyArchive);
+                }
+                cancel.check()?;
+
+                let source = Some(path.to_path_buf());
+                on_progress(Progress::new(Phase::Install, 0, 1, source.clone()));
+                let archive_path = self.archive_path()?;
+                fs::create_dir_all(archive_path.parent().expect("archive path has a parent"))?;
+                fs::copy(path, archive_path)?;
+                on_progress(Progress::new(Phase::Install, 1, 1, source));
+            }
+        }
+
+        self.record_source(path)
+    }
+
+    /// Record the archive or directory at `path` as the one this mod was installed from. Only
+    /// archives have a name and size, see [`Mod::source_archive`].
+    pub(crate) fn record_source(&self, path: &Path) -> Result<()> {
+        if path.is_dir() {
+            set_optional_field::<String>(&self.db, self.id, "source_name", None)?;
+            set_optional_field::<u64>(&self.db, self.id, "source_size", None)?;
+            return self.set_field("source_path", path.to_string_lossy().into_owned());
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.set_field("source_name", name)?;
+        self.set_field("source_size", fs::metadata(path)?.len())?;
+        self.set_field("source_path", path.to_string_lossy().into_owned())
+    }
+
+    /// Extract the archive at `path`, or copy the directory, into a staging directory, where its
+    /// layout can be looked at and adjusted before it replaces this mod's files with
+    /// [`StagedMod::commit`]. Anything left in the staging directory from an earlier attempt is
+    /// removed first.
+    pub fn stage(&self, path: &Path) -> Result<StagedMod> {
+        let dir = self.staging_dir()?;
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        if path.is_dir() {
+            copy_dir(path, &dir, &mut |_| {}, &Cancel::new())?;
+        } else {
+            check_archive(path)?;
+            uncompress_archive(File::open(path)?, &dir, Ownership::Preserve)?;
+        }
+
+        Ok(StagedMod::new(self.clone(), dir, path.to_path_buf()))
+    }
+
+    pub(crate) fn staging_dir(&self) -> Result<PathBuf> {
+        Ok(self
+            .parent()?
+            .dir()?
+            .join("staging")
+            .join(safe_dir_name(&self.name()?)))
+    }
+
+    /// Reinstall this mod from the archive or directory at `path`, or from [`Mod::source`] if
+    /// not given, replacing whatever is left of its files. Meant for when they were moved or
+    /// deleted outside of Barnacle, see [`Mod::files_missing`]. Fails with
+    /// [`Error::MissingSource`] if no path is given and the recorded source is gone too.
+    pub fn reinstall(&self, path: Option<&Path>) -> Result<()> {
+        let source = match path {
+            Some(path) => path.to_path_buf(),
+            None => self
+                .source()?
+                .filter(|source| source.exists())
+                .ok_or(Error::MissingSource)?,
+        };
+        if source.is_dir() {
+            // There's nothing to keep archived without a source archive
+            self.set_field("storage_kind", StorageKind::Loose)?;
+        } else {
+            check_archive(&source)?;
+        }
+
+        let stored = self.source_path()?;
+        if stored.is_dir() {
+            fs::remove_dir_all(&stored)?;
+        } else if stored.is_file() {
+            fs::remove_file(&stored)?;
+        }
+        self.clear_unpacked()?;
+
+        self.install_from(&source, &mut |_| {}, &Cancel::new())?;
+        self.reindex()?;
+        self.set_field("content_hash", self.hash_content()?)?;
+
+        info!("Reinstalled mod: {}", self.name()?);
+
+        Ok(())
+    }
+
+    /// Remove this mod from the database, leaving its files alone
+    pub(crate) fn remove_node(self) -> Result<()> {
+        let db_id = self.id.db_id(&self.db)?;
+        self.db
+            .write()
+            .exec_mut(QueryBuilder::remove().ids(db_id).query())?;
+
+        Ok(())
+    }
+
     pub fn remove(self) -> Result<()> {
         let name = self.name()?;
+        let storage_kind = self.storage_kind()?;
         let dir = self.dir()?;
+        let archive_path = self.archive_path()?;
+        let unpacked_dir = self.unpacked_dir()?;
 
         let db_id = self.id.db_id(&self.db)?;
         self.db
             .write()
             .exec_mut(QueryBuilder::remove().ids(db_id).query())?;
 
-        fs::remove_dir_all(dir).unwrap();
+        match storage_kind {
+            StorageKind::Loose => fs::remove_dir_all(dir).unwrap(),
+            StorageKind::Archived => {
+                fs::remove_file(archive_path).unwrap();
+                if unpacked_dir.exists() {
+                    fs::remove_dir_all(unpacked_dir)?;
+                }
+            }
+        }
 
         info!("Removed mod: {name}");
 
@@ -159,17 +875,200 @@ impl Mod {
     }
 }
 
+/// Make sure none of the entries in the archive at `path` would be extracted outside of the
+/// directory it's extracted into
+fn check_archive(path: &Path) -> Result<()> {
+    for entry in list_archive_files(File::open(path)?)? {
+        if escapes_root(Path::new(&entry)) {
+            return Err(Error::UnsafeArchivePath(entry));
+        }
+    }
+
+    Ok(())
+}
+
+/// Mask of the file type bits of `st_mode`, and the types [`extract`] handles
+const S_IFMT: u32 = 0o170_000;
+const S_IFDIR: u32 = 0o040_000;
+const S_IFREG: u32 = 0o100_000;
+
+/// Extract the archive at `path` into `dir` one entry at a time, so `cancel` can stop it in
+/// between. Every entry counts as a step of [`Phase::Install`]. Only directories and regular
+/// files are extracted, links and other special files are skipped.
+fn extract(path: &Path, dir: &Path, on_progress: OnProgress, cancel: &Cancel) -> Result<()> {
+    let total = list_archive_files(File::open(path)?)?.len() as u64;
+    on_progress(Progress::new(
+        Phase::Install,
+        0,
+        total,
+        Some(path.to_path_buf()),
+    ));
+    fs::create_dir_all(dir)?;
+
+    let mut current = 0;
+    let mut target = None;
+    let mut file = None;
+    for contents in ArchiveIterator::from_read(File::open(path)?)? {
+        match contents {
+            ArchiveContents::StartOfEntry(name, stat) => {
+                cancel.check()?;
+
+                let entry_path = dir.join(&name);
+                match stat.st_mode & S_IFMT {
+                    S_IFDIR => fs::create_dir_all(&entry_path)?,
+                    S_IFREG => {
+                        if let Some(parent) = entry_path.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        file = Some(File::create(&entry_path)?);
+                    }
+                    _ => debug!("Skipping special archive entry: {name}"),
+                }
+                target = Some(entry_path);
+            }
+            ArchiveContents::DataChunk(chunk) => {
+                if let Some(file) = &mut file {
+                    file.write_all(&chunk)?;
+                }
+            }
+            ArchiveContents::EndOfEntry => {
+                file = None;
+                current += 1;
+                on_progress(Progress::new(Phase::Install, current, total, target.take()));
+            }
+            ArchiveContents::Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy everything under `source` into `dir`, keeping its layout. Every entry counts as a step of
+/// [`Phase::Install`]. Like [`extract`], only directories and regular files are copied, links and
+/// other special files are skipped.
+fn copy_dir(source: &Path, dir: &Path, on_progress: OnProgress, cancel: &Cancel) -> Result<()> {
+    let entries = WalkDir::new(source)
+        .min_depth(1)
+        .into_iter()
+        .collect::<walkdir::Result<Vec<_>>>()
+        .map_err(io::Error::from)?;
+    let total = entries.len() as u64;
+    on_progress(Progress::new(
+        Phase::Install,
+        0,
+        total,
+        Some(source.to_path_buf()),
+    ));
+    fs::create_dir_all(dir)?;
+
+    for (current, entry) in (1..).zip(entries) {
+        cancel.check()?;
+
+        let relative = entry
+            .path()
+            .strip_prefix(source)
+            .expect("walked entries are inside the walked directory");
+        let target = dir.join(relative);
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if file_type.is_file() {
+            // Directories are walked before their contents, so the parent already exists
+            fs::copy(entry.path(), &target)?;
+        } else {
+            debug!("Skipping special file: {}", entry.path().display());
+        }
+        on_progress(Progress::new(Phase::Install, current, total, Some(target)));
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if there's at least one file somewhere under `dir`
+fn contains_files(dir: &Path) -> bool {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| !e.file_type().is_dir())
+}
+
+/// Returns `true` if the relative `path` is absolute or climbs above where it starts from
+fn escapes_root(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return true,
+            },
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+
+    false
+}
+
+/// Returns the modification time of `path` in milliseconds, used to detect a stale file index. A
+/// missing path is treated as never modified.
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .and_then(|d| u64::try_from(d.as_millis()).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the latest modification time of `path` and every directory under it. Adding, removing
+/// or renaming a file changes the modification time of the directory holding it, wherever that is
+/// in the tree, so this tells whether the files under `path` are still the same without listing
+/// them.
+fn tree_modified(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.depth() == 0 || entry.file_type().is_dir())
+        .map(|entry| modified(entry.path()))
+        .max()
+        .unwrap_or_default()
+}
+
 impl PartialEq for Mod {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
     }
 }
 
+impl Eq for Mod {}
+
+impl Hash for Mod {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::{
+        fs::{self, File},
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    };
+
+    use regex::Regex;
+    use tempfile::tempdir;
+
+    use super::escapes_root;
     use crate::{
         Repository,
-        repository::{DeployKind, entities::Error},
+        progress::{Cancel, Phase},
+        repository::{
+            DeployKind, Mod, StorageKind,
+            entities::{Error, UniqueConstraint},
+        },
+        test_utils::{assert_tree, write_tar, write_zip},
     };
 
     #[test]
@@ -182,6 +1081,82 @@ mod test {
         assert!(mod_.dir().unwrap().exists());
     }
 
+    #[test]
+    fn test_add_from_dir() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let source = tmp.path().join("SkyUI");
+        fs::create_dir_all(source.join("interface/skyui")).unwrap();
+        fs::create_dir_all(source.join("empty")).unwrap();
+        fs::write(source.join("SkyUI_SE.esp"), "plugin").unwrap();
+        fs::write(source.join("interface/skyui/config.txt"), "config").unwrap();
+
+        let mod_ = game.add_mod("SkyUI", Some(&source)).unwrap();
+        let dir = mod_.dir().unwrap();
+        assert_eq!(mod_.storage_kind().unwrap(), StorageKind::Loose);
+        assert_tree(
+            &dir,
+            &[
+                "SkyUI_SE.esp",
+                "empty/",
+                "interface/",
+                "interface/skyui/",
+                "interface/skyui/config.txt",
+            ],
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("interface/skyui/config.txt")).unwrap(),
+            "config"
+        );
+        // Copied files are locked like extracted ones, and the source is left alone
+        assert!(
+            fs::metadata(dir.join("SkyUI_SE.esp"))
+                .unwrap()
+                .permissions()
+                .readonly()
+        );
+        assert!(source.join("SkyUI_SE.esp").exists());
+        // Directories are recorded as sources too, but only archives have a name and size
+        assert_eq!(mod_.source().unwrap(), Some(source.clone()));
+        assert_eq!(mod_.source_archive().unwrap(), None);
+
+        // A directory can't be kept archived
+        let archived = game.add_archived_mod("Archived", &source).unwrap();
+        assert_eq!(archived.storage_kind().unwrap(), StorageKind::Loose);
+
+        let empty = tmp.path().join("Empty");
+        fs::create_dir_all(empty.join("textures")).unwrap();
+        assert!(matches!(
+            game.add_mod("Empty", Some(&empty)),
+            Err(Error::EmptyArchive)
+        ));
+        assert_eq!(game.mods().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_add_from_tar() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.tar");
+        write_tar(
+            &archive,
+            &[("meshes/armor.nif", "armor"), ("Armor.esp", "plugin")],
+        );
+
+        let mod_ = game.add_mod("Armor", Some(&archive)).unwrap();
+        let dir = mod_.dir().unwrap();
+        assert_tree(&dir, &["Armor.esp", "meshes/", "meshes/armor.nif"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("meshes/armor.nif")).unwrap(),
+            "armor"
+        );
+        assert_eq!(mod_.source().unwrap(), Some(archive));
+    }
+
     #[test]
     fn test_add_duplicate() {
         let repo = Repository::mock();
@@ -191,7 +1166,7 @@ mod test {
 
         assert!(matches!(
             game.add_mod("Test", None),
-            Err(Error::DuplicateName)
+            Err(Error::UniqueViolation(UniqueConstraint::ModName))
         ));
     }
 
@@ -245,4 +1220,511 @@ mod test {
             .name()
             .unwrap();
     }
+
+    #[test]
+    fn test_files() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let mod_ = game.add_mod("Test", None).unwrap();
+
+        assert!(mod_.files().unwrap().is_empty());
+
+        let dir = mod_.dir().unwrap();
+        fs::create_dir_all(dir.join("meshes")).unwrap();
+        fs::write(dir.join("meshes/armor.nif"), "").unwrap();
+        fs::write(dir.join("test.esp"), "TES4").unwrap();
+        mod_.reindex().unwrap();
+
+        assert_tree(&dir, &["meshes/", "meshes/armor.nif", "test.esp"]);
+        assert_eq!(mod_.size().unwrap(), 4);
+        assert_eq!(
+            mod_.files().unwrap(),
+            vec![PathBuf::from("meshes/armor.nif"), PathBuf::from("test.esp")]
+        );
+
+        // A file added below the root is picked up too, even though only its own directory
+        // changed. The time is set explicitly so it's sure to differ from the indexed one.
+        fs::write(dir.join("meshes/boots.nif"), "").unwrap();
+        File::open(dir.join("meshes"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            mod_.files().unwrap(),
+            vec![
+                PathBuf::from("meshes/armor.nif"),
+                PathBuf::from("meshes/boots.nif"),
+                PathBuf::from("test.esp")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_archived() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.zip");
+        write_zip(&archive, &[("meshes/armor.nif", "armor")]);
+
+        let mod_ = game.add_archived_mod("Test", &archive).unwrap();
+
+        assert_eq!(mod_.storage_kind().unwrap(), StorageKind::Archived);
+        assert_eq!(
+            mod_.size().unwrap(),
+            fs::metadata(mod_.archive_path().unwrap()).unwrap().len()
+        );
+        assert!(mod_.archive_path().unwrap().exists());
+        assert!(!mod_.dir().unwrap().exists());
+        assert_eq!(
+            mod_.files().unwrap(),
+            vec![PathBuf::from("meshes/armor.nif")]
+        );
+
+        let content_dir = mod_.content_dir().unwrap();
+        assert_tree(&content_dir, &["meshes/", "meshes/armor.nif"]);
+
+        mod_.clear_unpacked().unwrap();
+        assert!(!content_dir.exists());
+    }
+
+    #[test]
+    fn test_reinstall() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.zip");
+        write_zip(&archive, &[("meshes/armor.nif", "armor")]);
+
+        let mod_ = game.add_mod("Test", Some(&archive)).unwrap();
+        assert_eq!(mod_.source().unwrap(), Some(archive.clone()));
+        assert!(!mod_.files_missing().unwrap());
+
+        fs::remove_dir_all(mod_.dir().unwrap()).unwrap();
+        assert!(mod_.files_missing().unwrap());
+
+        mod_.reinstall(None).unwrap();
+        assert!(!mod_.files_missing().unwrap());
+        assert_tree(&mod_.dir().unwrap(), &["meshes/", "meshes/armor.nif"]);
+        assert!(mod_.verify().unwrap());
+
+        // Once the source is gone too, a new one has to be given
+        fs::remove_dir_all(mod_.dir().unwrap()).unwrap();
+        let moved = tmp.path().join("moved.zip");
+        fs::rename(&archive, &moved).unwrap();
+        assert!(matches!(mod_.reinstall(None), Err(Error::MissingSource)));
+
+        mod_.reinstall(Some(&moved)).unwrap();
+        assert_eq!(mod_.source().unwrap(), Some(moved));
+        assert!(!mod_.files_missing().unwrap());
+    }
+
+    #[test]
+    fn test_reinstall_directory() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let source = tmp.path().join("source");
+        fs::create_dir_all(source.join("meshes")).unwrap();
+        fs::write(source.join("meshes/armor.nif"), "armor").unwrap();
+
+        let mod_ = game.add_mod("Test", Some(&source)).unwrap();
+        fs::remove_dir_all(mod_.dir().unwrap()).unwrap();
+        assert!(mod_.files_missing().unwrap());
+
+        // The directory is copied again, and left alone
+        mod_.reinstall(None).unwrap();
+        assert_tree(&mod_.dir().unwrap(), &["meshes/", "meshes/armor.nif"]);
+        assert!(mod_.verify().unwrap());
+        assert!(source.join("meshes/armor.nif").exists());
+
+        // A directory can be staged like an archive
+        fs::write(source.join("meshes/boots.nif"), "boots").unwrap();
+        mod_.stage(&source).unwrap().commit().unwrap();
+        assert_tree(
+            &mod_.dir().unwrap(),
+            &["meshes/", "meshes/armor.nif", "meshes/boots.nif"],
+        );
+
+        fs::remove_dir_all(&source).unwrap();
+        assert!(matches!(mod_.reinstall(None), Err(Error::MissingSource)));
+    }
+
+    #[test]
+    fn test_readonly_enforcement() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.zip");
+        write_zip(&archive, &[("meshes/armor.nif", "armor")]);
+        let read_only = |mod_: &Mod| {
+            fs::metadata(mod_.dir().unwrap().join("meshes/armor.nif"))
+                .unwrap()
+                .permissions()
+                .readonly()
+        };
+
+        let locked = game.add_mod("Locked", Some(&archive)).unwrap();
+        assert!(read_only(&locked));
+
+        // Marking a mod editable unlocks its files, and unmarking it locks them again
+        locked.set_editable(true).unwrap();
+        assert!(locked.editable().unwrap());
+        assert!(!read_only(&locked));
+        locked.set_editable(false).unwrap();
+        assert!(read_only(&locked));
+
+        repo.set_enforce_readonly_mods(false);
+        let loose = game.add_mod("Loose", Some(&archive)).unwrap();
+        assert!(!loose.editable().unwrap());
+        assert!(!read_only(&loose));
+    }
+
+    #[test]
+    fn test_used_by() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let vanilla = game.add_profile("Vanilla").unwrap();
+        let modded = game.add_profile("Modded").unwrap();
+        let survival = game.add_profile("Survival").unwrap();
+
+        let mod_ = game.add_mod("Test", None).unwrap();
+        assert!(mod_.used_by().unwrap().is_empty());
+
+        survival.add_mod_entry(mod_.clone()).unwrap();
+        let other = game.add_mod("Other", None).unwrap();
+        vanilla.add_mod_entry(other.clone()).unwrap();
+        // Behind another entry, so further along the profile's chain of entries
+        modded.add_mod_entry(other).unwrap();
+        modded.add_mod_entry(mod_.clone()).unwrap();
+        assert_eq!(mod_.used_by().unwrap(), vec![modded.clone(), survival]);
+        assert!(modded.contains_mod(&mod_).unwrap());
+        assert!(!vanilla.contains_mod(&mod_).unwrap());
+    }
+
+    #[test]
+    fn test_add_with_progress() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let mut updates = Vec::new();
+        game.add_mod_with_progress("Test", None, &mut |p| updates.push((p.phase, p.current)))
+            .unwrap();
+
+        assert_eq!(
+            updates,
+            vec![
+                (Phase::Install, 0),
+                (Phase::Install, 1),
+                (Phase::Index, 0),
+                (Phase::Index, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_unsafe_archive() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("nested/mod.zip");
+        fs::create_dir_all(archive.parent().unwrap()).unwrap();
+        write_zip(&archive, &[("textures/ok.dds", ""), ("../../evil", "")]);
+
+        assert!(matches!(
+            game.add_mod("Test", Some(&archive)),
+            Err(Error::UnsafeArchivePath(entry)) if entry == "../../evil"
+        ));
+        assert!(game.mods().unwrap().is_empty());
+        assert_tree(tmp.path(), &["nested/", "nested/mod.zip"]);
+    }
+
+    #[test]
+    fn test_add_empty_archive() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("empty.zip");
+        write_zip(&archive, &[]);
+
+        assert!(matches!(
+            game.add_mod("Empty", Some(&archive)),
+            Err(Error::EmptyArchive)
+        ));
+        assert!(matches!(
+            game.add_archived_mod("Empty", &archive),
+            Err(Error::EmptyArchive)
+        ));
+        assert!(game.mods().unwrap().is_empty());
+        assert!(!game.dir().unwrap().join("mods/empty").exists());
+
+        // The name is free to use again
+        game.add_mod("Empty", None).unwrap();
+    }
+
+    #[test]
+    fn test_add_canceled() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.zip");
+        write_zip(
+            &archive,
+            &[
+                ("meshes/a.nif", "a"),
+                ("meshes/b.nif", "b"),
+                ("meshes/c.nif", "c"),
+            ],
+        );
+
+        // Cancel once the first file is extracted
+        let cancel = Cancel::new();
+        let mut extracted = 0;
+        let result = game.add_mod_cancelable(
+            "Test",
+            Some(&archive),
+            &mut |p| {
+                if p.phase == Phase::Install && p.current == 1 {
+                    cancel.cancel();
+                }
+                extracted = extracted.max(p.current);
+            },
+            &cancel,
+        );
+
+        assert!(matches!(result, Err(Error::Canceled)));
+        assert_eq!(extracted, 1);
+        assert!(game.mods().unwrap().is_empty());
+        assert!(!game.dir().unwrap().join("mods/test").exists());
+
+        // Nothing is left in the way of trying again
+        let mod_ = game.add_mod("Test", Some(&archive)).unwrap();
+        assert_eq!(mod_.files().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_escapes_root() {
+        assert!(!escapes_root(Path::new("meshes/armor.nif")));
+        assert!(!escapes_root(Path::new("meshes/../armor.nif")));
+        assert!(escapes_root(Path::new("../armor.nif")));
+        assert!(escapes_root(Path::new("meshes/../../armor.nif")));
+        assert!(escapes_root(Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn test_plugins() {
+        let repo = Repository::mock();
+
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let mod_ = skyrim.add_mod("Test", None).unwrap();
+        let dir = mod_.dir().unwrap();
+        fs::create_dir_all(dir.join("meshes")).unwrap();
+        for file in [
+            "Test.esp",
+            "Test - Textures.BSA",
+            "Patch.ESL",
+            "meshes/stray.esp",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        mod_.reindex().unwrap();
+
+        assert_eq!(
+            mod_.plugins().unwrap(),
+            vec![PathBuf::from("Patch.ESL"), PathBuf::from("Test.esp")]
+        );
+
+        let overlay = repo.add_game("Generic", DeployKind::Overlay).unwrap();
+        let mod_ = overlay.add_mod("Test", None).unwrap();
+        fs::write(mod_.dir().unwrap().join("Test.esp"), "").unwrap();
+        mod_.reindex().unwrap();
+
+        assert!(mod_.plugins().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_all_mods() {
+        let repo = Repository::mock();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let fallout = repo
+            .add_game("Fallout 4", DeployKind::CreationEngine)
+            .unwrap();
+
+        let tmp = tempdir().unwrap();
+        let shared = tmp.path().join("shared.zip");
+        write_zip(&shared, &[("shared.esp", "")]);
+        let other = tmp.path().join("other.zip");
+        write_zip(&other, &[("other.esp", "")]);
+
+        skyrim.add_mod("Shared", Some(&shared)).unwrap();
+        skyrim.add_mod("Other", Some(&other)).unwrap();
+        fallout.add_mod("Shared", Some(&shared)).unwrap();
+        fallout.add_mod("Empty", None).unwrap();
+
+        let all = repo.all_mods().unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all.iter().filter(|m| m.game == skyrim).count(), 2);
+
+        let duplicates = repo.duplicate_installs().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        let mut games: Vec<String> = duplicates
+            .first()
+            .unwrap()
+            .iter()
+            .map(|m| m.game.name().unwrap())
+            .collect();
+        games.sort();
+        assert_eq!(games, vec!["Fallout 4", "Skyrim"]);
+    }
+
+    #[test]
+    fn test_verify() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let mod_ = game.add_mod("Test", None).unwrap();
+        assert!(mod_.content_hash().unwrap().is_some());
+        assert!(mod_.verify().unwrap());
+
+        fs::write(mod_.dir().unwrap().join("test.esp"), "plugin").unwrap();
+        assert!(!mod_.verify().unwrap());
+    }
+
+    #[test]
+    fn test_find_duplicate_mods() {
+        let repo = Repository::mock();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("mod.zip");
+        write_zip(&archive, &[("test.esp", "plugin")]);
+
+        let original = skyrim.add_mod("Original", Some(&archive)).unwrap();
+        let copy = skyrim.add_mod("Copy", Some(&archive)).unwrap();
+        // Mods without files hash the same without being duplicates
+        skyrim.add_mod("Empty", None).unwrap();
+        skyrim.add_mod("Also Empty", None).unwrap();
+
+        let duplicates = repo.find_duplicate_mods().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        let group = duplicates.first().unwrap();
+        assert_eq!(group.len(), 2);
+        assert!(group.contains(&original) && group.contains(&copy));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let mod_ = game.add_mod("SkyUI", None).unwrap();
+
+        assert_eq!(mod_.version().unwrap(), None);
+        assert_eq!(mod_.author().unwrap(), None);
+        assert_eq!(mod_.source_url().unwrap(), None);
+
+        mod_.set_version(Some("5.2")).unwrap();
+        mod_.set_author(Some("schlangster")).unwrap();
+        mod_.set_source_url(Some(
+            "https://www.nexusmods.com/skyrimspecialedition/mods/12604",
+        ))
+        .unwrap();
+        assert_eq!(mod_.version().unwrap().as_deref(), Some("5.2"));
+        assert_eq!(mod_.author().unwrap().as_deref(), Some("schlangster"));
+        assert_eq!(
+            mod_.source_url().unwrap().as_deref(),
+            Some("https://www.nexusmods.com/skyrimspecialedition/mods/12604")
+        );
+
+        mod_.set_version(Some("5.2.1")).unwrap();
+        assert_eq!(mod_.version().unwrap().as_deref(), Some("5.2.1"));
+
+        // Clearing a field leaves the others alone
+        mod_.set_author(None).unwrap();
+        assert_eq!(mod_.author().unwrap(), None);
+        assert_eq!(mod_.version().unwrap().as_deref(), Some("5.2.1"));
+        mod_.set_author(None).unwrap();
+        assert_eq!(mod_.author().unwrap(), None);
+    }
+
+    #[test]
+    fn test_apply_category_rules() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let enb = game.add_mod("Rudy ENB", None).unwrap();
+        let textures = game.add_mod("Skyrim 2020 Textures", None).unwrap();
+        let other = game.add_mod("SkyUI", None).unwrap();
+
+        let rules = [
+            (Regex::new("ENB").unwrap(), "Graphics".to_string()),
+            (
+                Regex::new("(?i)textures|enb").unwrap(),
+                "Textures".to_string(),
+            ),
+            (Regex::new("Armor").unwrap(), "Armor".to_string()),
+        ];
+        let matched = repo.apply_category_rules(&game, &rules).unwrap();
+
+        assert_eq!(matched, vec![1, 1, 0]);
+        assert_eq!(enb.category().unwrap(), "Graphics");
+        assert_eq!(textures.category().unwrap(), "Textures");
+        assert_eq!(other.category().unwrap(), "");
+        assert!(repo.category_rules_last_run().is_some());
+    }
+
+    #[test]
+    fn test_requirements() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let skse = game.add_mod("SKSE", None).unwrap();
+        let skyui = game.add_mod("SkyUI", None).unwrap();
+        let menu = game.add_mod("MCM Helper", None).unwrap();
+
+        skyui.add_requirement(&skse).unwrap();
+        menu.add_requirement(&skyui).unwrap();
+        // Adding it twice is a no-op
+        menu.add_requirement(&skyui).unwrap();
+
+        assert_eq!(skyui.requires().unwrap(), vec![skse.clone()]);
+        assert_eq!(menu.requires().unwrap(), vec![skyui.clone()]);
+        assert_eq!(skse.required_by().unwrap(), vec![skyui.clone()]);
+        assert!(skse.requires().unwrap().is_empty());
+
+        // Requirements don't show up as mods of the game or throw off the parent lookup
+        assert_eq!(game.mods().unwrap().len(), 3);
+        assert_eq!(skyui.parent().unwrap(), game);
+
+        assert!(matches!(
+            skse.add_requirement(&menu),
+            Err(Error::DependencyCycle)
+        ));
+        assert!(matches!(
+            skse.add_requirement(&skse),
+            Err(Error::DependencyCycle)
+        ));
+
+        let foreign = repo
+            .add_game("Oblivion", DeployKind::Gamebryo)
+            .unwrap()
+            .add_mod("OBSE", None)
+            .unwrap();
+        assert!(matches!(
+            skyui.add_requirement(&foreign),
+            Err(Error::ForeignMod)
+        ));
+        assert_eq!(skyui.requires().unwrap(), vec![skse.clone()]);
+
+        menu.remove_requirement(&skyui).unwrap();
+        assert!(menu.requires().unwrap().is_empty());
+        skse.add_requirement(&menu).unwrap();
+    }
 }
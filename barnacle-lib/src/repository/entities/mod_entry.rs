@@ -1,6 +1,9 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
-use agdb::{DbId, DbValue, QueryBuilder, QueryId};
+use agdb::{CountComparison, DbId, DbValue, QueryBuilder, QueryId};
 
 use crate::repository::{
     Mod, Profile,
@@ -9,7 +12,7 @@ use crate::repository::{
         Db,
         models::{ModEntryModel, ModModel, ProfileModel},
     },
-    entities::{EntityId, Result, Uid, get_field, set_field},
+    entities::{EntityId, Error, Result, Uid, get_field, set_field},
 };
 
 /// Represents a mod entry in the Barnacle system.
@@ -26,6 +29,16 @@ pub struct ModEntry {
     pub(crate) cfg: Cfg,
 }
 
+/// The outcome of reordering a profile's entries by a list of mod names, see
+/// [`Profile::load_order_plan`].
+#[derive(Debug, Clone)]
+pub struct LoadOrderPlan {
+    /// All of the profile's entries in their new order
+    pub entries: Vec<ModEntry>,
+    /// The names that didn't match any entry
+    pub unmatched: Vec<String>,
+}
+
 impl ModEntry {
     pub(crate) fn load(entry_db_id: DbId, mod_db_id: DbId, db: Db, cfg: Cfg) -> Result<Self> {
         Ok(Self {
@@ -40,18 +53,96 @@ impl ModEntry {
         self.get_mod_field("name")
     }
 
+    /// Returns the category of the mod this entry points to, empty if it has none
+    pub fn category(&self) -> Result<String> {
+        self.get_mod_field("category")
+    }
+
+    /// Returns the [`Mod`] this entry points to
+    pub fn mod_(&self) -> Result<Mod> {
+        Mod::load(
+            self.mod_id.db_id(&self.db)?,
+            self.db.clone(),
+            self.cfg.clone(),
+        )
+    }
+
     pub fn enabled(&self) -> Result<bool> {
         self.get_entry_field("enabled")
     }
 
+    /// Enable or disable this entry. Fails with [`Error::Locked`] if the entry is locked, and
+    /// with [`Error::RequiredBy`] when disabling an entry that other enabled entries require.
     pub fn set_enabled(&self, value: bool) -> Result<()> {
+        if !value {
+            let required_by = self.enabled_dependents()?;
+            if !required_by.is_empty() {
+                return Err(Error::RequiredBy(
+                    required_by
+                        .iter()
+                        .map(ModEntry::name)
+                        .collect::<Result<_>>()?,
+                ));
+            }
+        }
+
+        self.set_enabled_force(value)
+    }
+
+    /// Like [`ModEntry::set_enabled`], but disables the entry even if other entries require it
+    pub fn set_enabled_force(&self, value: bool) -> Result<()> {
+        self.ensure_unlocked()?;
         self.set_entry_field("enabled", value)
     }
 
+    /// Returns the enabled entries of the same profile whose mods require this entry's mod
+    pub fn enabled_dependents(&self) -> Result<Vec<ModEntry>> {
+        let required_by = self.mod_()?.required_by()?;
+        if required_by.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut dependents = Vec::new();
+        for entry in self.parent()?.mod_entries()? {
+            if required_by.contains(&entry.mod_()?) && entry.enabled()? {
+                dependents.push(entry);
+            }
+        }
+
+        Ok(dependents)
+    }
+
+    /// Returns `true` if this entry is locked against being toggled, moved, or removed
+    pub fn locked(&self) -> Result<bool> {
+        self.get_entry_field("locked")
+    }
+
+    pub fn set_locked(&self, value: bool) -> Result<()> {
+        self.set_entry_field("locked", value)
+    }
+
+    fn ensure_unlocked(&self) -> Result<()> {
+        if self.locked()? {
+            return Err(Error::Locked);
+        }
+
+        Ok(())
+    }
+
     pub fn notes(&self) -> Result<String> {
         self.get_entry_field("notes")
     }
 
+    /// Replace the free-form notes kept on this entry. Locked entries can still be annotated.
+    pub fn set_notes(&self, notes: &str) -> Result<()> {
+        self.set_entry_field("notes", notes)
+    }
+
+    /// Returns the index of this entry in its profile's load order
+    pub fn position(&self) -> Result<u64> {
+        self.get_entry_field("position")
+    }
+
     /// Returns the parent [`Profile`] of this [`ModEntry`]
     pub fn parent(&self) -> Result<Profile> {
         let parent_profile_id = self
@@ -68,22 +159,26 @@ impl ModEntry {
             )?
             .elements
             .pop()
-            .expect("a ModEntry should have a parent Profile")
+            .ok_or(Error::Orphaned)?
             .id;
 
         Profile::load(parent_profile_id, self.db.clone(), self.cfg.clone())
     }
 
     pub(crate) fn add(db: &Db, cfg: &Cfg, profile: &Profile, mod_: Mod) -> Result<Self> {
-        let model = ModEntryModel::new(Uid::new(db)?);
+        if profile.contains_mod(&mod_)? {
+            return Err(Error::DuplicateEntry);
+        }
+
+        let entries = profile.mod_entries()?;
+
+        let enabled = cfg.read().enable_new_mods_by_default();
+        let model = ModEntryModel::new(Uid::new(db)?, entries.len() as u64, enabled);
 
         let profile_id = profile.id.db_id(db)?;
         let mod_id = mod_.id.db_id(db)?;
 
-        let maybe_last_entry_id = profile
-            .mod_entries()?
-            .last()
-            .map(|e| e.entry_id.db_id(db).unwrap());
+        let maybe_last_entry_id = entries.last().map(|e| e.entry_id.db_id(db).unwrap());
 
         let entry_id = db.write().transaction_mut(|t| -> Result<DbId> {
             let entry_id = t
@@ -132,26 +227,207 @@ impl ModEntry {
         ModEntry::load(entry_id, mod_id, db.clone(), cfg.clone())
     }
 
-    /// Remove the given [`ModEntry`] from the list
+    /// Remove the given [`ModEntry`] from the list. Fails with [`Error::Locked`] if the entry
+    /// is locked.
     pub fn remove(self) -> Result<()> {
-        let id = self.entry_id.db_id(&self.db)?;
-        let profile_id = self.parent()?.id.db_id(&self.db)?;
-        let entry_ids: Vec<DbId> = self
-            .db
-            .read()
-            .exec(
-                QueryBuilder::select()
-                    .elements::<ModEntryModel>()
+        self.ensure_unlocked()?;
+        self.remove_unchecked()
+    }
+
+    /// Point this entry at `new_mod`, keeping its position, whether it's enabled and its notes.
+    /// Used when merging duplicate mods or replacing a mod with an updated one. Fails with
+    /// [`Error::Locked`] if the entry is locked, with [`Error::ForeignMod`] if `new_mod` belongs
+    /// to another game, and with [`Error::DuplicateEntry`] if the profile already has an entry
+    /// for `new_mod`.
+    pub fn repoint(&mut self, new_mod: &Mod) -> Result<()> {
+        if new_mod.id == self.mod_id {
+            return Ok(());
+        }
+        self.ensure_unlocked()?;
+        let profile = self.parent()?;
+        if new_mod.parent()? != profile.parent()? {
+            return Err(Error::ForeignMod);
+        }
+        if profile.contains_mod(new_mod)? {
+            return Err(Error::DuplicateEntry);
+        }
+
+        let entry_id = self.entry_id.db_id(&self.db)?;
+        let old_mod_id = self.mod_id.db_id(&self.db)?;
+        let new_mod_id = new_mod.id.db_id(&self.db)?;
+        self.db.write().transaction_mut(|t| -> Result<()> {
+            t.exec_mut(
+                QueryBuilder::remove()
                     .search()
-                    .from(profile_id)
+                    .from(entry_id)
+                    .to(old_mod_id)
+                    .where_()
+                    .edge()
+                    .and()
+                    .distance(CountComparison::Equal(1))
                     .query(),
-            )?
-            .elements
+            )?;
+            t.exec_mut(
+                QueryBuilder::insert()
+                    .edges()
+                    .from(entry_id)
+                    .to(new_mod_id)
+                    .query(),
+            )?;
+
+            Ok(())
+        })?;
+        self.mod_id = new_mod.id;
+
+        Ok(())
+    }
+
+    /// Move this entry to `position` in its profile's load order, shifting the entries in
+    /// between. Fails with [`Error::Locked`] if this entry is locked, or if the move would shift
+    /// a locked entry.
+    pub(crate) fn move_to(&self, position: usize) -> Result<()> {
+        self.move_with(|_| position)
+    }
+
+    /// Move this entry `delta` places later in the load order, or earlier if it's negative,
+    /// stopping at either end. Fails like [`ModEntry::move_to`].
+    pub(crate) fn nudge(&self, delta: i32) -> Result<()> {
+        let delta =
+            isize::try_from(delta).unwrap_or(if delta < 0 { isize::MIN } else { isize::MAX });
+        self.move_with(|index| index.saturating_add_signed(delta))
+    }
+
+    /// Move this entry one place earlier in its profile's load order. Does nothing if it's
+    /// already first. Fails like [`ModEntry::move_to`].
+    pub fn move_up(&self) -> Result<()> {
+        self.nudge(-1)
+    }
+
+    /// Move this entry one place later in its profile's load order. Does nothing if it's
+    /// already last. Fails like [`ModEntry::move_to`].
+    pub fn move_down(&self) -> Result<()> {
+        self.nudge(1)
+    }
+
+    /// Move this entry to the position `target` returns for its current index. The list is
+    /// relinked and renumbered in a single transaction.
+    fn move_with(&self, target: impl FnOnce(usize) -> usize) -> Result<()> {
+        self.ensure_unlocked()?;
+
+        let id = self.entry_id.db_id(&self.db)?;
+        let profile_id = self.parent()?.id.db_id(&self.db)?;
+        let old_ids = ModEntry::list_ids(&self.db, profile_id)?;
+        let index = old_ids
             .iter()
-            .map(|e| e.id)
-            .collect();
+            .position(|&e| e == id)
+            .ok_or(Error::RemovedEntity)?;
+        let position = target(index);
+
+        let mut new_ids: Vec<DbId> = old_ids.iter().copied().filter(|&e| e != id).collect();
+        new_ids.insert(position.min(new_ids.len()), id);
+        if new_ids == old_ids {
+            return Ok(());
+        }
+
+        for (old, new) in old_ids.iter().zip(&new_ids) {
+            if old != new && self.load_sibling(*old)?.locked()? {
+                return Err(Error::Locked);
+            }
+        }
+
+        relink(&self.db, profile_id, &old_ids, &new_ids)
+    }
+
+    /// Reorder `profile`'s entries so the ones whose mods are named in `order` come first, in
+    /// that order, followed by the rest in their current order. Returns the names that didn't
+    /// match an entry. Fails with [`Error::Locked`] if a locked entry would be shifted.
+    pub(crate) fn apply_load_order(
+        db: &Db,
+        cfg: &Cfg,
+        profile: &Profile,
+        order: &[String],
+    ) -> Result<Vec<String>> {
+        let profile_id = profile.id.db_id(db)?;
+        let (old_ids, new_ids, unmatched) = ModEntry::plan_ids(db, cfg, profile_id, order)?;
+        if new_ids != old_ids {
+            relink(db, profile_id, &old_ids, &new_ids)?;
+        }
+
+        Ok(unmatched)
+    }
+
+    /// Work out what [`ModEntry::apply_load_order`] would do without changing anything
+    pub(crate) fn plan_load_order(
+        db: &Db,
+        cfg: &Cfg,
+        profile: &Profile,
+        order: &[String],
+    ) -> Result<LoadOrderPlan> {
+        let profile_id = profile.id.db_id(db)?;
+        let (_, new_ids, unmatched) = ModEntry::plan_ids(db, cfg, profile_id, order)?;
+
+        Ok(LoadOrderPlan {
+            entries: ModEntry::iter_ids(db, cfg, new_ids).collect::<Result<_>>()?,
+            unmatched,
+        })
+    }
+
+    /// The current and reordered entry IDs of a profile along with the names in `order` that
+    /// didn't match an entry
+    fn plan_ids(
+        db: &Db,
+        cfg: &Cfg,
+        profile_id: DbId,
+        order: &[String],
+    ) -> Result<(Vec<DbId>, Vec<DbId>, Vec<String>)> {
+        let old_ids = ModEntry::list_ids(db, profile_id)?;
+        let mut remaining = Vec::with_capacity(old_ids.len());
+        let mut locked = Vec::new();
+        for (id, entry) in old_ids
+            .iter()
+            .zip(ModEntry::iter_ids(db, cfg, old_ids.clone()))
+        {
+            let entry = entry?;
+            if entry.locked()? {
+                locked.push(*id);
+            }
+            remaining.push((*id, entry.name()?));
+        }
+
+        let mut new_ids = Vec::with_capacity(old_ids.len());
+        let mut unmatched = Vec::new();
+        for name in order {
+            match remaining.iter().position(|(_, n)| n == name) {
+                Some(index) => new_ids.push(remaining.remove(index).0),
+                None => unmatched.push(name.clone()),
+            }
+        }
+        new_ids.extend(remaining.iter().map(|(id, _)| *id));
+
+        for (old, new) in old_ids.iter().zip(&new_ids) {
+            if old != new && locked.contains(old) {
+                return Err(Error::Locked);
+            }
+        }
+
+        Ok((old_ids, new_ids, unmatched))
+    }
+
+    /// Load another entry of the same profile by its ID
+    fn load_sibling(&self, entry_id: DbId) -> Result<Self> {
+        ModEntry::iter_ids(&self.db, &self.cfg, vec![entry_id])
+            .next()
+            .expect("one ID was given")
+    }
 
-        let mut iter = entry_ids.into_iter().peekable();
+    /// Like [`ModEntry::remove`], but ignores whether the entry is locked. Used when the whole
+    /// profile is being removed.
+    pub(crate) fn remove_unchecked(self) -> Result<()> {
+        let id = self.entry_id.db_id(&self.db)?;
+        let profile_id = self.parent()?.id.db_id(&self.db)?;
+        let entry_ids = ModEntry::list_ids(&self.db, profile_id)?;
+
+        let mut iter = entry_ids.iter().copied().peekable();
         let mut prev = None;
         while let Some(curr) = iter.next() {
             if curr == id {
@@ -198,46 +474,86 @@ impl ModEntry {
             prev = Some(curr);
         }
 
+        let remaining: Vec<DbId> = entry_ids.into_iter().filter(|&e| e != id).collect();
+        set_positions(&self.db, &remaining)?;
+
         Ok(())
     }
 
     pub(crate) fn list(db: &Db, cfg: &Cfg, profile: &Profile) -> Result<Vec<Self>> {
-        let db_id = profile.id.db_id(db)?;
-        let mod_entry_ids: Vec<DbId> = db
+        ModEntry::iter(db, cfg, profile)?.collect()
+    }
+
+    pub(crate) fn iter(
+        db: &Db,
+        cfg: &Cfg,
+        profile: &Profile,
+    ) -> Result<impl Iterator<Item = Result<Self>> + use<>> {
+        let profile_id = profile.id.db_id(db)?;
+        let entry_ids = ModEntry::list_ids(db, profile_id)?;
+
+        Ok(ModEntry::iter_ids(db, cfg, entry_ids))
+    }
+
+    /// Lazily load the entries with the given IDs
+    fn iter_ids(
+        db: &Db,
+        cfg: &Cfg,
+        entry_ids: Vec<DbId>,
+    ) -> impl Iterator<Item = Result<Self>> + use<> {
+        let (db, cfg) = (db.clone(), cfg.clone());
+
+        entry_ids.into_iter().map(move |entry_id| {
+            let mod_id = db
+                .read()
+                .exec(
+                    QueryBuilder::search()
+                        .from(entry_id)
+                        .limit(1)
+                        .where_()
+                        .neighbor()
+                        .and()
+                        .element::<ModModel>()
+                        .query(),
+                )?
+                .elements
+                .pop()
+                .expect("a ModEntry should point to a Mod")
+                .id;
+
+            ModEntry::load(entry_id, mod_id, db.clone(), cfg.clone())
+        })
+    }
+
+    /// Returns the IDs of the entries in the given profile, sorted by their position
+    fn list_ids(db: &Db, profile_id: DbId) -> Result<Vec<DbId>> {
+        let position_key = DbValue::from("position");
+        let mut entries: Vec<(u64, DbId)> = db
             .read()
             .exec(
                 QueryBuilder::select()
                     .elements::<ModEntryModel>()
                     .search()
-                    .from(db_id)
+                    .from(profile_id)
                     .query(),
             )?
             .elements
-            .iter()
-            .map(|e| e.id)
+            .into_iter()
+            .map(|e| {
+                let position = e
+                    .values
+                    .iter()
+                    .find(|kv| kv.key == position_key)
+                    .and_then(|kv| kv.value.to_u64().ok())
+                    .unwrap_or_default();
+                (position, e.id)
+            })
             .collect();
 
-        let mod_ids: Vec<DbId> = db
-            .read()
-            .exec(
-                QueryBuilder::select()
-                    .elements::<ModModel>()
-                    .search()
-                    .from(db_id)
-                    .query(),
-            )?
-            .elements
-            .iter()
-            .map(|e| e.id)
-            .collect();
+        // Stable, so traversal order breaks any ties
+        entries.sort_by_key(|(position, _)| *position);
 
-        Ok(mod_entry_ids
-            .into_iter()
-            .zip(mod_ids)
-            .map(|(entry_db_id, mod_db_id)| {
-                ModEntry::load(entry_db_id, mod_db_id, db.clone(), cfg.clone()).unwrap()
-            })
-            .collect())
+        Ok(entries.into_iter().map(|(_, id)| id).collect())
     }
 
     fn get_entry_field<T>(&self, field: &str) -> Result<T>
@@ -286,14 +602,76 @@ impl ModEntry {
     }
 }
 
+/// Relink `profile_id`'s list of entries from `old_ids` to `new_ids` and renumber them, in a
+/// single transaction
+fn relink(db: &Db, profile_id: DbId, old_ids: &[DbId], new_ids: &[DbId]) -> Result<()> {
+    db.write().transaction_mut(|t| -> Result<()> {
+        let old_chain = std::iter::once(profile_id).chain(old_ids.iter().copied());
+        for (from, to) in old_chain.clone().zip(old_chain.skip(1)) {
+            t.exec_mut(
+                QueryBuilder::remove()
+                    .search()
+                    .from(from)
+                    .to(to)
+                    .where_()
+                    .edge()
+                    .query(),
+            )?;
+        }
+
+        let new_chain = std::iter::once(profile_id).chain(new_ids.iter().copied());
+        for (from, to) in new_chain.clone().zip(new_chain.skip(1)) {
+            t.exec_mut(QueryBuilder::insert().edges().from(from).to(to).query())?;
+        }
+
+        for (position, id) in new_ids.iter().enumerate() {
+            t.exec_mut(
+                QueryBuilder::insert()
+                    .values([[("position", position as u64).into()]])
+                    .ids(*id)
+                    .query(),
+            )?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Renumber the given entries so their positions match their order in `ids`
+fn set_positions(db: &Db, ids: &[DbId]) -> Result<()> {
+    db.write().transaction_mut(|t| -> Result<()> {
+        for (position, id) in ids.iter().enumerate() {
+            t.exec_mut(
+                QueryBuilder::insert()
+                    .values([[("position", position as u64).into()]])
+                    .ids(*id)
+                    .query(),
+            )?;
+        }
+
+        Ok(())
+    })
+}
+
 impl PartialEq for ModEntry {
     fn eq(&self, other: &Self) -> bool {
         self.entry_id == other.entry_id && self.mod_id == other.mod_id
     }
 }
 
+impl Eq for ModEntry {}
+
+impl Hash for ModEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entry_id.hash(state);
+        self.mod_id.hash(state);
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
     use super::*;
     use crate::{Repository, repository::DeployKind};
 
@@ -313,6 +691,23 @@ mod test {
         assert_eq!(profile.mod_entries().unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_add_duplicate() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let mod_ = game.add_mod("Super Duper Mod", None).unwrap();
+
+        profile.add_mod_entry(mod_.clone()).unwrap();
+
+        assert!(matches!(
+            profile.add_mod_entry(mod_),
+            Err(Error::DuplicateEntry)
+        ));
+        assert_eq!(profile.mod_entries().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_remove() {
         let repo = Repository::mock();
@@ -400,4 +795,421 @@ mod test {
 
         assert!(!entry.enabled().unwrap());
     }
+
+    #[test]
+    fn test_notes() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entry = profile
+            .add_mod_entry(game.add_mod("SkyUI", None).unwrap())
+            .unwrap();
+        assert_eq!(entry.notes().unwrap(), "");
+
+        entry.set_notes("Needs SKSE\nLoad after USSEP").unwrap();
+        assert_eq!(entry.notes().unwrap(), "Needs SKSE\nLoad after USSEP");
+
+        entry.set_locked(true).unwrap();
+        entry.set_notes("").unwrap();
+        assert_eq!(entry.notes().unwrap(), "");
+    }
+
+    #[test]
+    fn test_enable_new_mods_by_default() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        assert!(repo.enable_new_mods_by_default());
+
+        let enabled = profile
+            .add_mod_entry(game.add_mod("Enabled", None).unwrap())
+            .unwrap();
+        assert!(enabled.enabled().unwrap());
+
+        repo.set_enable_new_mods_by_default(false);
+        let disabled = profile
+            .add_mod_entry(game.add_mod("Disabled", None).unwrap())
+            .unwrap();
+        assert!(!disabled.enabled().unwrap());
+        // Existing entries are left alone
+        assert!(enabled.enabled().unwrap());
+    }
+
+    #[test]
+    fn test_positions_stay_contiguous() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        // Simple LCG so the sequence of operations is random but reproducible
+        let mut seed: u64 = 0x5eed;
+        let mut next = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            usize::try_from(seed >> 33).unwrap() % bound
+        };
+
+        let mut expected: Vec<ModEntry> = Vec::new();
+        for i in 0..40 {
+            if expected.is_empty() || next(3) != 0 {
+                let mod_ = game.add_mod(&format!("Mod{i}"), None).unwrap();
+                expected.push(profile.add_mod_entry(mod_).unwrap());
+            } else {
+                let entry = expected.remove(next(expected.len()));
+                entry.remove().unwrap();
+            }
+
+            let entries = profile.mod_entries().unwrap();
+            assert_eq!(entries, expected);
+
+            let positions: Vec<u64> = entries.iter().map(|e| e.position().unwrap()).collect();
+            assert_eq!(positions, (0..entries.len() as u64).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let mod_ = game.add_mod("Super Duper Mod", None).unwrap();
+        profile.add_mod_entry(mod_).unwrap();
+
+        let mut entries: HashSet<ModEntry> = profile.mod_entries().unwrap().into_iter().collect();
+        entries.extend(profile.mod_entries().unwrap());
+
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_move() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entries: Vec<ModEntry> = ["A", "B", "C", "D", "E", "F"]
+            .iter()
+            .map(|name| {
+                let mod_ = game.add_mod(name, None).unwrap();
+                profile.add_mod_entry(mod_).unwrap()
+            })
+            .collect();
+        let [a, b, c, d, e, f] = entries.try_into().unwrap();
+
+        // The tail to the head
+        profile.move_mod_entry(&f, 0).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![
+                f.clone(),
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                d.clone(),
+                e.clone()
+            ]
+        );
+
+        // The head to the middle
+        profile.move_mod_entry(&f, 3).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                f.clone(),
+                d.clone(),
+                e.clone()
+            ]
+        );
+
+        // Out of range clamps to the end
+        profile.move_mod_entry(&b, 100).unwrap();
+        // Onto its own position
+        profile.move_mod_entry(&d, 3).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![
+                a.clone(),
+                c.clone(),
+                f.clone(),
+                d.clone(),
+                e.clone(),
+                b.clone()
+            ]
+        );
+        let positions: Vec<u64> = profile
+            .mod_entries()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.position().unwrap())
+            .collect();
+        assert_eq!(positions, vec![0, 1, 2, 3, 4, 5]);
+
+        // The list has to stay intact after moving entries around
+        profile.remove_mod_entry(f).unwrap();
+        profile.move_mod_entry(&a, 4).unwrap();
+        assert_eq!(profile.mod_entries().unwrap(), vec![c, d, e, b, a]);
+    }
+
+    #[test]
+    fn test_repoint() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entries: Vec<ModEntry> = ["A", "B", "C"]
+            .iter()
+            .map(|name| {
+                let mod_ = game.add_mod(name, None).unwrap();
+                profile.add_mod_entry(mod_).unwrap()
+            })
+            .collect();
+        let [a, mut b, c] = entries.try_into().unwrap();
+        b.set_enabled(false).unwrap();
+
+        let updated = game.add_mod("B v2", None).unwrap();
+        b.repoint(&updated).unwrap();
+        assert_eq!(b.name().unwrap(), "B v2");
+        assert_eq!(b.mod_().unwrap(), updated);
+        assert!(!b.enabled().unwrap());
+        assert_eq!(b.position().unwrap(), 1);
+        assert!(profile.contains_mod(&updated).unwrap());
+
+        let entries = profile.mod_entries().unwrap();
+        assert_eq!(entries, vec![a.clone(), b.clone(), c]);
+        assert_eq!(entries.get(1).unwrap().name().unwrap(), "B v2");
+
+        // The profile can't end up with two entries for one mod
+        assert!(matches!(
+            b.repoint(&a.mod_().unwrap()),
+            Err(Error::DuplicateEntry)
+        ));
+
+        let foreign = repo
+            .add_game("Oblivion", DeployKind::Gamebryo)
+            .unwrap()
+            .add_mod("B v3", None)
+            .unwrap();
+        assert!(matches!(b.repoint(&foreign), Err(Error::ForeignMod)));
+        assert_eq!(b.mod_().unwrap(), updated);
+    }
+
+    #[test]
+    fn test_nudge() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entries: Vec<ModEntry> = ["A", "B", "C"]
+            .iter()
+            .map(|name| {
+                let mod_ = game.add_mod(name, None).unwrap();
+                profile.add_mod_entry(mod_).unwrap()
+            })
+            .collect();
+        let [a, b, c] = entries.try_into().unwrap();
+
+        profile.nudge(&a, 1).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![b.clone(), a.clone(), c.clone()]
+        );
+        assert_eq!(a.position().unwrap(), 1);
+
+        // Past the end
+        profile.nudge(&b, 10).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![a.clone(), c.clone(), b.clone()]
+        );
+
+        // Past the start
+        profile.nudge(&b, i32::MIN).unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![b.clone(), a.clone(), c.clone()]
+        );
+        assert_eq!(b.position().unwrap(), 0);
+        assert_eq!(c.position().unwrap(), 2);
+
+        // Already at the start
+        profile.nudge(&b, -1).unwrap();
+        assert_eq!(profile.mod_entries().unwrap(), vec![b, a, c]);
+    }
+
+    #[test]
+    fn test_move_up_down() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entries: Vec<ModEntry> = ["A", "B", "C"]
+            .iter()
+            .map(|name| {
+                let mod_ = game.add_mod(name, None).unwrap();
+                profile.add_mod_entry(mod_).unwrap()
+            })
+            .collect();
+        let [a, b, c] = entries.try_into().unwrap();
+
+        b.move_up().unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![b.clone(), a.clone(), c.clone()]
+        );
+        b.move_down().unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![a.clone(), b.clone(), c.clone()]
+        );
+        b.move_down().unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![a.clone(), c.clone(), b.clone()]
+        );
+
+        // Nothing happens at either end
+        a.move_up().unwrap();
+        a.move_up().unwrap();
+        b.move_down().unwrap();
+        b.move_down().unwrap();
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![a.clone(), c, b.clone()]
+        );
+        assert_eq!(a.position().unwrap(), 0);
+        assert_eq!(b.position().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_apply_load_order() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let entries: Vec<ModEntry> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|name| {
+                let mod_ = game.add_mod(name, None).unwrap();
+                profile.add_mod_entry(mod_).unwrap()
+            })
+            .collect();
+        let [a, b, c, d] = entries.try_into().unwrap();
+
+        let order = ["C", "Missing", "A"].map(String::from);
+        let plan = profile.load_order_plan(&order).unwrap();
+        assert_eq!(plan.unmatched, vec!["Missing"]);
+        assert_eq!(
+            plan.entries,
+            vec![c.clone(), a.clone(), b.clone(), d.clone()]
+        );
+        // Planning doesn't reorder anything
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![a.clone(), b.clone(), c.clone(), d.clone()]
+        );
+
+        assert_eq!(profile.apply_load_order(&order).unwrap(), vec!["Missing"]);
+        // Unlisted entries keep their order after the listed ones
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![c.clone(), a.clone(), b.clone(), d.clone()]
+        );
+        assert_eq!(d.position().unwrap(), 3);
+
+        d.set_locked(true).unwrap();
+        let order = ["D", "C"].map(String::from);
+        assert!(matches!(
+            profile.load_order_plan(&order),
+            Err(Error::Locked)
+        ));
+        assert!(matches!(
+            profile.apply_load_order(&order),
+            Err(Error::Locked)
+        ));
+        assert_eq!(profile.mod_entries().unwrap(), vec![c, a, b, d]);
+    }
+
+    #[test]
+    fn test_locked() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let first = profile
+            .add_mod_entry(game.add_mod("First", None).unwrap())
+            .unwrap();
+        let second = profile
+            .add_mod_entry(game.add_mod("Second", None).unwrap())
+            .unwrap();
+        let third = profile
+            .add_mod_entry(game.add_mod("Third", None).unwrap())
+            .unwrap();
+
+        second.set_locked(true).unwrap();
+        assert!(second.locked().unwrap());
+
+        assert!(matches!(second.set_enabled(false), Err(Error::Locked)));
+        assert!(matches!(
+            profile.move_mod_entry(&second, 0),
+            Err(Error::Locked)
+        ));
+        // Moving another entry across it would shift it too
+        assert!(matches!(
+            profile.move_mod_entry(&third, 0),
+            Err(Error::Locked)
+        ));
+        assert!(matches!(
+            profile.remove_mod_entry(second.clone()),
+            Err(Error::Locked)
+        ));
+        assert_eq!(
+            profile.mod_entries().unwrap(),
+            vec![first.clone(), second.clone(), third.clone()]
+        );
+
+        // Entries that don't cross it can still move
+        profile.move_mod_entry(&first, 0).unwrap();
+
+        second.set_locked(false).unwrap();
+        second.set_enabled(false).unwrap();
+
+        second.set_locked(true).unwrap();
+        profile.remove().unwrap();
+    }
+
+    #[test]
+    fn test_required_by() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let skse = game.add_mod("SKSE", None).unwrap();
+        let skyui = game.add_mod("SkyUI", None).unwrap();
+        skyui.add_requirement(&skse).unwrap();
+
+        let skse = profile.add_mod_entry(skse).unwrap();
+        let skyui = profile.add_mod_entry(skyui).unwrap();
+
+        assert_eq!(skse.enabled_dependents().unwrap(), vec![skyui.clone()]);
+        assert!(matches!(
+            skse.set_enabled(false),
+            Err(Error::RequiredBy(names)) if names == ["SkyUI"]
+        ));
+        assert!(skse.enabled().unwrap());
+
+        skse.set_enabled_force(false).unwrap();
+        assert!(!skse.enabled().unwrap());
+
+        // Nothing enabled depends on it once the dependent is disabled too
+        skse.set_enabled(true).unwrap();
+        skyui.set_enabled(false).unwrap();
+        skse.set_enabled(false).unwrap();
+    }
 }
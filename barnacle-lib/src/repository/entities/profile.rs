@@ -1,21 +1,76 @@
-use std::{fmt::Debug, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    fs,
+    hash::{Hash, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use super::Error;
+use super::{Error, UniqueConstraint};
 use agdb::{DbId, DbValue, QueryBuilder, QueryId};
-use heck::ToSnakeCase;
-use tracing::info;
-
-use crate::repository::{
-    Cfg,
-    db::{
-        Db,
-        models::{GameModel, ProfileModel},
-    },
-    entities::{
-        EntityId, Result, Uid, game::Game, get_field, mod_::Mod, mod_entry::ModEntry, set_field,
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    deploy::{self, DeployedFile, LoadoutManifest, LoadoutMod, Manifest},
+    fs::safe_dir_name,
+    ini::{self, IniTweak},
+    overlay::{self, OverlayMount},
+    repository::{
+        Cfg,
+        db::{
+            Db,
+            models::{DeployKind, GameModel, ProfileModel},
+        },
+        entities::{
+            EntityId, Result, Uid,
+            game::Game,
+            get_field, get_optional_field,
+            mod_::Mod,
+            mod_entry::{LoadOrderPlan, ModEntry},
+            set_field, set_optional_field,
+        },
     },
 };
 
+const INI_TWEAKS_FILE: &str = "ini_tweaks.toml";
+const INI_BACKUP_DIR: &str = "ini_backup";
+const PLUGINS_FILE: &str = "plugins.txt";
+/// Directory of the profile directory holding the upper and work directories of its overlays
+const OVERLAY_DIR: &str = "overlay";
+
+/// On-disk representation of a profile's INI tweaks
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IniTweaksFile {
+    tweaks: Vec<IniTweak>,
+}
+
+/// A profile's load order as written by [`Profile::export`], for
+/// [`Game::import_profile`](crate::repository::Game::import_profile). Mods are referred to by
+/// name only, so the file doesn't depend on where the library is.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ProfileExport {
+    pub mods: Vec<ExportedEntry>,
+}
+
+/// A mod entry in a [`ProfileExport`], along with the metadata of its mod
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExportedEntry {
+    pub name: String,
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
 /// Represents a profile entity in the Barnacle system.
 ///
 /// Provides methods to inspect and modify this profile's data, including
@@ -39,19 +94,24 @@ impl Profile {
         self.get_field("name")
     }
 
+    /// Rename this profile along with its directory. Fails with [`Error::UniqueViolation`] if
+    /// another profile of the game would collide with the new name.
     pub fn set_name(&self, new_name: &str) -> Result<()> {
         if new_name == self.name()? {
             return Ok(());
         }
+        for profile in self.parent()?.profiles_iter()? {
+            let profile = profile?;
+            if profile != *self && safe_dir_name(&profile.name()?) == safe_dir_name(new_name) {
+                return Err(Error::UniqueViolation(UniqueConstraint::ProfileName));
+            }
+        }
 
         let old_dir = self.dir()?;
+        let new_dir = old_dir.with_file_name(safe_dir_name(new_name));
+        fs::rename(old_dir, new_dir)?;
 
-        self.set_field("name", new_name)?;
-
-        let new_dir = self.dir()?;
-        fs::rename(old_dir, new_dir).unwrap();
-
-        Ok(())
+        self.set_field("name", new_name)
     }
 
     pub fn dir(&self) -> Result<PathBuf> {
@@ -59,14 +119,63 @@ impl Profile {
             .parent()?
             .dir()?
             .join("profiles")
-            .join(self.name()?.to_snake_case()))
+            .join(safe_dir_name(&self.name()?)))
+    }
+
+    /// How many times one of the game's tools was launched with this profile active
+    pub fn launch_count(&self) -> Result<u64> {
+        self.get_field("launch_count")
+    }
+
+    /// When this profile was last launched, in seconds since the unix epoch. `None` if it never
+    /// was.
+    pub fn last_played(&self) -> Result<Option<u64>> {
+        get_optional_field(&self.db, self.id, "last_played")
+    }
+
+    /// How long tools launched with this profile active ran for in total
+    pub fn play_time(&self) -> Result<Duration> {
+        Ok(Duration::from_secs(self.get_field("play_seconds")?))
+    }
+
+    /// Count a launch, stamping it as the last time this profile was played
+    pub(crate) fn record_launch(&self) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.set_field("launch_count", self.launch_count()? + 1)?;
+        self.set_field("last_played", now)
+    }
+
+    /// Add how long a launched tool ran for to the play time
+    pub(crate) fn add_play_time(&self, elapsed: Duration) -> Result<()> {
+        let seconds = self.play_time()?.saturating_add(elapsed).as_secs();
+        self.set_field("play_seconds", seconds)
     }
 
-    /// Make this profile the active one
+    /// Make this profile the active one of its game.
+    ///
+    /// A profile can only be in use while its game is, so the parent game is made the active one
+    /// too if it isn't already. The active profiles of other games are left as they are.
     pub fn activate(&self) -> Result<()> {
-        let parent_db_id = self.parent()?.id.db_id(&self.db)?;
+        self.activate_in_game()?;
+
+        let parent = self.parent()?;
+        if !parent.is_active()? {
+            parent.activate_keep_profile()?;
+        }
+
+        Ok(())
+    }
+
+    /// Make this profile the active one of its game, without making the game the active one.
+    /// Used where a profile is picked automatically, which mustn't switch the user's game.
+    pub(crate) fn activate_in_game(&self) -> Result<()> {
+        let parent = self.parent()?;
+        let parent_db_id = parent.id.db_id(&self.db)?;
         let db_id = self.id.db_id(&self.db)?;
-        self.db.write().transaction_mut(|t| {
+        self.db.write().transaction_mut(|t| -> Result<()> {
             // Remove `active` field from edge pointing to existing active profile, if present
             // BUG: Is this responsible for wiping out the active profile?
             t.exec_mut(
@@ -94,7 +203,11 @@ impl Profile {
             )?;
 
             Ok(())
-        })
+        })?;
+        // A profile has been chosen again
+        set_optional_field::<bool>(&self.db, parent.id, "profile_cleared", None)?;
+
+        Ok(())
     }
 
     pub fn is_active(&self) -> Result<bool> {
@@ -152,28 +265,884 @@ impl Profile {
             )?
             .elements
             .pop()
-            .expect("a Profile should have a parent Game")
+            .ok_or(Error::Orphaned)?
             .id;
 
         Game::load(parent_game_id, self.db.clone(), self.cfg.clone())
     }
 
+    /// Returns the INI overrides applied to the game's config files when this profile is deployed
+    pub fn ini_tweaks(&self) -> Result<Vec<IniTweak>> {
+        let path = self.dir()?.join(INI_TWEAKS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file: IniTweaksFile = toml::from_str(&fs::read_to_string(path)?)?;
+
+        Ok(file.tweaks)
+    }
+
+    pub fn set_ini_tweaks(&self, tweaks: &[IniTweak]) -> Result<()> {
+        let contents = toml::to_string_pretty(&IniTweaksFile {
+            tweaks: tweaks.to_vec(),
+        })?;
+
+        fs::write(self.dir()?.join(INI_TWEAKS_FILE), contents)?;
+
+        Ok(())
+    }
+
+    /// Returns the files that have been pinned to a specific [`Mod`], regardless of load order.
+    /// Overrides pointing at mods that have since been removed are skipped.
+    pub fn file_overrides(&self) -> Result<BTreeMap<PathBuf, Mod>> {
+        let mut overrides = BTreeMap::new();
+        for (path, uid) in self.raw_file_overrides()? {
+            if let Some(mod_) = Mod::load_by_uid(self.db.clone(), self.cfg.clone(), Uid(uid))? {
+                overrides.insert(PathBuf::from(path), mod_);
+            }
+        }
+
+        Ok(overrides)
+    }
+
+    /// Pin `relative_path` to `winner`, so it provides that file even if a mod later in the load
+    /// order ships it too.
+    pub fn set_file_override(&self, relative_path: &Path, winner: &Mod) -> Result<()> {
+        let mut overrides = self.raw_file_overrides()?;
+        overrides.insert(
+            relative_path.to_string_lossy().into_owned(),
+            winner.id.uid().0,
+        );
+
+        self.set_field("file_overrides", toml::to_string(&overrides)?)
+    }
+
+    /// Return `relative_path` to the default last-wins behaviour
+    pub fn clear_file_override(&self, relative_path: &Path) -> Result<()> {
+        let mut overrides = self.raw_file_overrides()?;
+        overrides.remove(relative_path.to_string_lossy().as_ref());
+
+        self.set_field("file_overrides", toml::to_string(&overrides)?)
+    }
+
+    /// File overrides as they're stored, mapping relative paths to mod UIDs
+    fn raw_file_overrides(&self) -> Result<BTreeMap<String, u64>> {
+        match get_optional_field::<String>(&self.db, self.id, "file_overrides")? {
+            Some(contents) => Ok(toml::from_str(&contents)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Works out which of the [`Profile::effective_entries`] provides each file when this
+    /// profile is deployed.
+    ///
+    /// Files are provided by the entry latest in the load order, unless they've been pinned to
+    /// an enabled mod that ships them with [`Profile::set_file_override`].
+    pub fn deploy_plan(&self) -> Result<BTreeMap<PathBuf, ModEntry>> {
+        let entries = self.effective_entries()?;
+
+        let mut plan = BTreeMap::new();
+        let mut files_by_mod = BTreeMap::new();
+        for entry in &entries {
+            let files = entry.mod_()?.files()?;
+            for file in &files {
+                plan.insert(file.clone(), entry.clone());
+            }
+            files_by_mod.insert(entry.mod_id.uid().0, files);
+        }
+
+        for (path, uid) in self.raw_file_overrides()? {
+            let path = PathBuf::from(path);
+            let ships_file = files_by_mod
+                .get(&uid)
+                .is_some_and(|files| files.contains(&path));
+
+            if ships_file && let Some(winner) = entries.iter().find(|e| e.mod_id.uid().0 == uid) {
+                plan.insert(path, winner.clone());
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Returns the enabled mods that ship `relative_path`, in load order. The last one is the
+    /// one that provides it when the profile is deployed, which is the pinned mod if the file
+    /// has been overridden with [`Profile::set_file_override`].
+    pub fn providers_of(&self, relative_path: &Path) -> Result<Vec<Mod>> {
+        let mut providers = Vec::new();
+        for entry in self.effective_entries()? {
+            let mod_ = entry.mod_()?;
+            if mod_.files()?.iter().any(|f| f == relative_path) {
+                providers.push(mod_);
+            }
+        }
+
+        let pinned = self
+            .raw_file_overrides()?
+            .get(relative_path.to_string_lossy().as_ref())
+            .copied();
+        if let Some(uid) = pinned
+            && let Some(index) = providers.iter().position(|m| m.id.uid().0 == uid)
+        {
+            let winner = providers.remove(index);
+            providers.push(winner);
+        }
+
+        Ok(providers)
+    }
+
+    /// Returns the files shipped by more than one enabled [`ModEntry`], each with the entries
+    /// that ship it in load order. Like [`Profile::providers_of`], the last entry is the one
+    /// that provides the file when the profile is deployed.
+    pub fn conflicts(&self) -> Result<BTreeMap<PathBuf, Vec<ModEntry>>> {
+        let mut conflicts: BTreeMap<PathBuf, Vec<ModEntry>> = BTreeMap::new();
+        for entry in self.effective_entries()? {
+            for file in entry.mod_()?.files()? {
+                conflicts.entry(file).or_default().push(entry.clone());
+            }
+        }
+        conflicts.retain(|_, providers| providers.len() > 1);
+
+        for (path, uid) in self.raw_file_overrides()? {
+            if let Some(providers) = conflicts.get_mut(Path::new(&path))
+                && let Some(index) = providers.iter().position(|e| e.mod_id.uid().0 == uid)
+            {
+                let winner = providers.remove(index);
+                providers.push(winner);
+            }
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Returns the enabled mods that contribute nothing when this profile is deployed, because
+    /// every file they ship is provided by another mod, in load order. Mods without any files
+    /// aren't included.
+    pub fn redundant_mods(&self) -> Result<Vec<Mod>> {
+        let winners: BTreeSet<u64> = self
+            .deploy_plan()?
+            .values()
+            .map(|e| e.mod_id.uid().0)
+            .collect();
+
+        let mut redundant = Vec::new();
+        for entry in self.effective_entries()? {
+            let mod_ = entry.mod_()?;
+            if !winners.contains(&entry.mod_id.uid().0) && !mod_.files()?.is_empty() {
+                redundant.push(mod_);
+            }
+        }
+
+        Ok(redundant)
+    }
+
+    /// Disable every mod returned by [`Profile::redundant_mods`] in this profile, skipping the
+    /// ones whose entry is locked or that other enabled mods require. A redundant mod inherited
+    /// from the [`Profile::base`] is disabled by adding a disabled entry for it to this profile,
+    /// leaving the base as it is. Returns the mods that were disabled.
+    pub fn disable_redundant_mods(&self) -> Result<Vec<Mod>> {
+        let entries = self.mod_entries()?;
+        let enabled = self
+            .effective_entries()?
+            .iter()
+            .map(ModEntry::mod_)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut disabled = Vec::new();
+        for mod_ in self.redundant_mods()? {
+            if mod_.required_by()?.iter().any(|m| enabled.contains(m)) {
+                continue;
+            }
+
+            let entry = match entries.iter().find(|e| e.mod_id == mod_.id) {
+                Some(entry) if entry.locked()? => continue,
+                Some(entry) => entry.clone(),
+                None => self.add_mod_entry(mod_.clone())?,
+            };
+            entry.set_enabled_force(false)?;
+            disabled.push(mod_);
+        }
+
+        Ok(disabled)
+    }
+
+    /// Returns the load order of the plugins provided by this profile's enabled mods. This is
+    /// separate from the mod order, so it can be rearranged independently.
+    ///
+    /// Plugins that haven't been placed with [`Profile::set_plugin_order`] are appended at the
+    /// end, in mod order.
+    pub fn plugin_order(&self) -> Result<Vec<String>> {
+        let mut available: Vec<String> = Vec::new();
+        for entry in self.effective_entries()? {
+            for plugin in entry.mod_()?.plugins()? {
+                let name = plugin.to_string_lossy().into_owned();
+                if !available.contains(&name) {
+                    available.push(name);
+                }
+            }
+        }
+
+        let stored: Vec<String> =
+            get_optional_field(&self.db, self.id, "plugin_order")?.unwrap_or_default();
+        let mut order: Vec<String> = stored
+            .into_iter()
+            .filter(|p| available.contains(p))
+            .collect();
+        for plugin in available {
+            if !order.contains(&plugin) {
+                order.push(plugin);
+            }
+        }
+
+        Ok(order)
+    }
+
+    pub fn set_plugin_order(&self, order: &[String]) -> Result<()> {
+        self.set_field("plugin_order", order.to_vec())
+    }
+
+    /// Returns the path of the `plugins.txt` written by [`Profile::deploy`]. It's in the parent
+    /// game's [`Game::plugins_dir`], where the game reads it, or in this profile's directory if
+    /// that hasn't been set.
+    pub fn plugins_file(&self) -> Result<PathBuf> {
+        let dir = match self.parent()?.plugins_dir()? {
+            Some(dir) => dir,
+            None => self.dir()?,
+        };
+
+        Ok(dir.join(PLUGINS_FILE))
+    }
+
+    /// Write the plugin order to `path` for external tools like LOOT, in the `plugins.txt`
+    /// format of the game's [`DeployKind`]
+    pub fn export_plugin_order(&self, path: &Path) -> Result<()> {
+        let deploy_kind = self.parent()?.deploy_kind()?;
+        fs::write(path, format_plugins(&self.plugin_order()?, deploy_kind))?;
+
+        Ok(())
+    }
+
+    /// Write this profile's own entries to `path` as TOML, in load order, with each mod's name,
+    /// enabled state, notes and metadata. The file holds no paths, so it can be shared and read
+    /// back into another library with [`Game::import_profile`].
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let mods = self
+            .mod_entries()?
+            .iter()
+            .map(|entry| {
+                let mod_ = entry.mod_()?;
+                Ok(ExportedEntry {
+                    name: mod_.name()?,
+                    enabled: entry.enabled()?,
+                    notes: entry.notes()?,
+                    version: mod_.version()?,
+                    author: mod_.author()?,
+                    source_url: mod_.source_url()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        fs::write(path, toml::to_string_pretty(&ProfileExport { mods })?)?;
+
+        Ok(())
+    }
+
+    /// Replace the plugin order with the one in the `plugins.txt` or `loadorder.txt` at `path`,
+    /// like the ones LOOT writes. Plugins are matched case-insensitively, since that's how the
+    /// games treat them. Returns the plugins in the file that no enabled mod provides, which
+    /// are left out.
+    pub fn import_plugin_order(&self, path: &Path) -> Result<Vec<String>> {
+        let contents = fs::read(path)?;
+        let available = self.plugin_order()?;
+
+        let mut order: Vec<String> = Vec::new();
+        let mut unknown = Vec::new();
+        for plugin in parse_plugins(&String::from_utf8_lossy(&contents)) {
+            match available.iter().find(|p| p.eq_ignore_ascii_case(plugin)) {
+                Some(known) if !order.contains(known) => order.push(known.clone()),
+                Some(_) => {}
+                None => unknown.push(plugin.to_string()),
+            }
+        }
+
+        self.set_plugin_order(&order)?;
+
+        Ok(unknown)
+    }
+
     // Operations
 
+    /// Write the plugin order to [`Profile::plugins_file`] in the format the game's
+    /// [`DeployKind`] expects, replacing whatever the game had there. Does nothing for games that
+    /// don't use a `plugins.txt`.
+    fn write_plugins_file(&self, game: &Game) -> Result<()> {
+        let deploy_kind = game.deploy_kind()?;
+        if !matches!(
+            deploy_kind,
+            DeployKind::Gamebryo | DeployKind::CreationEngine
+        ) {
+            return Ok(());
+        }
+
+        let path = self.plugins_file()?;
+        if game.plugins_dir()?.is_none() {
+            warn!(
+                "{} has no plugins directory set, so it won't see the plugin order written to {}",
+                game.name()?,
+                path.display()
+            );
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format_plugins(&self.plugin_order()?, deploy_kind))?;
+
+        Ok(())
+    }
+
+    /// Describe this profile at [`Game::deployment_manifest_path`] for other tools to read
+    fn write_loadout_manifest(&self, game: &Game) -> Result<()> {
+        let mods = self
+            .effective_entries()?
+            .iter()
+            .map(|entry| {
+                let mod_ = entry.mod_()?;
+                Ok(LoadoutMod {
+                    name: mod_.name()?,
+                    version: mod_.version()?,
+                    author: mod_.author()?,
+                    category: Some(mod_.category()?).filter(|category| !category.is_empty()),
+                    content_hash: mod_.content_hash()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let plugins = match game.deploy_kind()? {
+            DeployKind::Gamebryo | DeployKind::CreationEngine => self.plugin_order()?,
+            _ => Vec::new(),
+        };
+
+        LoadoutManifest {
+            version: LoadoutManifest::VERSION,
+            game: game.name()?,
+            profile: self.name()?,
+            mods,
+            plugins,
+        }
+        .save(&game.deployment_manifest_path()?)?;
+
+        Ok(())
+    }
+
+    /// Apply this profile's INI tweaks to the config files under `root`. The original files are
+    /// backed up into the profile directory so they can be put back with
+    /// [`Profile::restore_ini_tweaks`]. Any previously applied tweaks are restored first.
+    pub fn apply_ini_tweaks(&self, root: &Path) -> Result<()> {
+        self.restore_ini_tweaks(root)?;
+
+        let tweaks = self.ini_tweaks()?;
+        if tweaks.is_empty() {
+            return Ok(());
+        }
+
+        ini::apply(root, &self.dir()?.join(INI_BACKUP_DIR), &tweaks)?;
+
+        Ok(())
+    }
+
+    /// Restore the config files under `root` that were modified by [`Profile::apply_ini_tweaks`]
+    pub fn restore_ini_tweaks(&self, root: &Path) -> Result<()> {
+        ini::restore(root, &self.dir()?.join(INI_BACKUP_DIR))?;
+
+        Ok(())
+    }
+
+    /// Returns a hash of this profile's enabled mods, in load order, along with their content
+    /// hashes, file overrides and plugin order. It changes whenever a mod is enabled, disabled,
+    /// moved, or has its content re-hashed, a file is pinned to another mod, or the plugins are
+    /// reordered, which makes it a cheap way to tell whether a redeploy is due without building a
+    /// [`Profile::deploy_plan`].
+    pub fn loadout_hash(&self) -> Result<u64> {
+        let mut hasher = Sha256::new();
+        for entry in self.effective_entries()? {
+            let mod_ = entry.mod_()?;
+            hasher.update(mod_.name()?);
+            hasher.update([0]);
+            hasher.update(mod_.content_hash()?.unwrap_or_default());
+            hasher.update([0]);
+        }
+        // Separates the sections, so entries can't be mistaken for overrides or plugins
+        hasher.update([1]);
+        for (path, uid) in self.raw_file_overrides()? {
+            hasher.update(path);
+            hasher.update([0]);
+            hasher.update(uid.to_le_bytes());
+        }
+        hasher.update([1]);
+        for plugin in self.plugin_order()? {
+            hasher.update(plugin);
+            hasher.update([0]);
+        }
+
+        let digest = hasher.finalize();
+        let first = digest.first_chunk().copied().unwrap_or_default();
+        Ok(u64::from_le_bytes(first))
+    }
+
+    /// Returns `true` unless this profile is deployed and its [`Profile::loadout_hash`] is the
+    /// same as when it was
+    pub fn needs_deploy(&self) -> Result<bool> {
+        let manifest = Manifest::load(&self.parent()?.deployment_manifest()?)?;
+        Ok(match manifest {
+            Some(manifest) if manifest.profile == self.id.uid().0 => {
+                manifest.loadout != Some(format!("{:016x}", self.loadout_hash()?))
+            }
+            _ => true,
+        })
+    }
+
+    /// Returns the files of [`Profile::deploy_plan`] that replace a game file in one of the parent
+    /// game's targets, rather than just another mod's, along with the entry providing each. Game
+    /// files already moved aside by a deployment still count.
+    pub fn vanilla_overwrites(&self) -> Result<BTreeMap<PathBuf, ModEntry>> {
+        let game = self.parent()?;
+        let targets = game.targets()?;
+        if targets.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let targets = deploy::resolve_targets(&targets, self.cfg.read().library_dir())?;
+
+        let manifest = Manifest::load(&game.deployment_manifest()?)?.unwrap_or_default();
+        let moved_aside: Vec<PathBuf> = manifest.vanilla.into_iter().map(|v| v.original).collect();
+        // Hard linked files look like game files, but the manifest knows they were deployed
+        let deployed: BTreeSet<PathBuf> = manifest
+            .targets
+            .iter()
+            .flat_map(|target| manifest.files.keys().map(|path| target.join(path)))
+            .collect();
+
+        let mut overwrites = self.deploy_plan()?;
+        overwrites.retain(|path, _| {
+            targets.iter().any(|target| {
+                let dest = target.join(path);
+                (!dest.is_symlink() && dest.is_file() && !deployed.contains(&dest))
+                    || moved_aside.contains(&dest)
+            })
+        });
+
+        Ok(overwrites)
+    }
+
+    /// Link the files of this profile's enabled mods into the parent game's targets.
+    ///
+    /// If this profile is already deployed, only the files that were added, removed, or changed
+    /// since the last deployment are updated, which makes deploying an unchanged profile a
+    /// no-op. Game files that would be replaced, see [`Profile::vanilla_overwrites`], are moved
+    /// aside and put back once they're no longer linked over. Any other deployed profile of the
+    /// same game is undeployed first. Use
+    /// [`Profile::redeploy`] to start from scratch instead. Waits for any other deployment of the
+    /// parent game to finish first.
+    ///
+    /// Games deployed with [`DeployKind::Overlay`] get an OverlayFS mount over each target
+    /// instead, see [`Profile::deploy_command`]. Gamebryo and Creation Engine games also get the
+    /// plugin order written to [`Profile::plugins_file`].
+    ///
+    /// Once the files are in place, the profile's INI tweaks are applied to the config files
+    /// under the game's first target, see [`Profile::apply_ini_tweaks`].
+    pub fn deploy(&self) -> Result<()> {
+        let game = self.parent()?;
+        let lock = game.deploy_lock();
+        let _guard = lock.lock();
+
+        let targets = game.targets()?;
+        if targets.is_empty() {
+            return Err(Error::NoTargets);
+        }
+        let targets = deploy::resolve_targets(&targets, self.cfg.read().library_dir())?;
+
+        self.write_plugins_file(&game)?;
+        self.write_loadout_manifest(&game)?;
+
+        let ini_root = targets.first().cloned().ok_or(Error::NoTargets)?;
+        if game.deploy_kind()? == DeployKind::Overlay {
+            self.deploy_overlay(&game, targets)?;
+        } else {
+            self.deploy_links(&game, targets)?;
+        }
+        self.apply_ini_tweaks(&ini_root)
+    }
+
+    /// Deploy this profile by linking its files into `targets`, only updating the links that
+    /// changed since the last deployment
+    fn deploy_links(&self, game: &Game, targets: Vec<PathBuf>) -> Result<()> {
+        let mut files = BTreeMap::new();
+        for (path, entry) in self.deploy_plan()? {
+            let source = entry.mod_()?.content_dir()?.join(&path);
+            files.insert(
+                path.to_string_lossy().into_owned(),
+                DeployedFile::new(source)?,
+            );
+        }
+        let mut manifest = Manifest {
+            profile: self.id.uid().0,
+            targets,
+            files,
+            loadout: Some(format!("{:016x}", self.loadout_hash()?)),
+            vanilla: Vec::new(),
+            link_strategy: game.link_strategy()?,
+            overlay: false,
+        };
+
+        let manifest_path = game.deployment_manifest()?;
+        let previous = match Manifest::load(&manifest_path)? {
+            // Links can only be updated in place if they were made the same way in the same place
+            Some(previous)
+                if previous.profile == manifest.profile
+                    && previous.targets == manifest.targets
+                    && previous.link_strategy == manifest.link_strategy
+                    && !previous.overlay =>
+            {
+                previous
+            }
+            Some(previous) => {
+                game.undeploy_manifest(&previous)?;
+                fs::remove_file(&manifest_path)?;
+                Manifest::default()
+            }
+            None => Manifest::default(),
+        };
+        manifest.vanilla = previous.vanilla.clone();
+
+        let delta = previous.diff(&manifest);
+        if delta.is_empty() && manifest_path.exists() {
+            // Manifests written before the loadout was recorded still need it
+            if previous.loadout != manifest.loadout {
+                manifest.save(&manifest_path)?;
+            }
+            info!("Profile {} is already deployed", self.name()?);
+            return Ok(());
+        }
+
+        // Without a loadout, a partial deployment is never taken to be up to date
+        let mut deployed = Manifest {
+            files: previous.files,
+            vanilla: previous.vanilla,
+            loadout: None,
+            ..manifest.clone()
+        };
+        if let Err(err) = deploy::apply(&mut deployed, &manifest, &delta, &game.vanilla_dir()?) {
+            // Record what was deployed before the failure, so it can still be undone
+            deployed.save(&manifest_path)?;
+            return Err(err.into());
+        }
+        manifest.vanilla = deployed.vanilla;
+        manifest.save(&manifest_path)?;
+        self.db.flush()?;
+
+        info!(
+            "Deployed profile {}: {} links added, {} removed",
+            self.name()?,
+            delta.added.len(),
+            delta.removed.len()
+        );
+
+        Ok(())
+    }
+
+    /// Returns the `mount` commands that deploy this profile, one per target of the parent game,
+    /// for games deployed with [`DeployKind::Overlay`]. Mounting needs privileges, so these can be
+    /// shown to the user to check, or to run with `pkexec`. The upper directories, which collect
+    /// whatever the game writes, and the work directories the commands refer to are created
+    /// under this profile's directory. Games deployed with links have no commands.
+    ///
+    /// Each overlay stacks the directories of the [`Profile::effective_entries`] over the target,
+    /// later ones on top. Whole directories are layered, so file overrides don't apply.
+    pub fn deploy_command(&self) -> Result<Vec<String>> {
+        let game = self.parent()?;
+        if game.deploy_kind()? != DeployKind::Overlay {
+            return Ok(Vec::new());
+        }
+
+        let targets = game.targets()?;
+        if targets.is_empty() {
+            return Err(Error::NoTargets);
+        }
+        let targets = deploy::resolve_targets(&targets, self.cfg.read().library_dir())?;
+
+        let mut commands = Vec::new();
+        for mount in self.overlay_mounts(&targets)? {
+            mount.create_dirs()?;
+            commands.push(mount.command_line());
+        }
+
+        Ok(commands)
+    }
+
+    /// Deploy this profile by mounting an overlay over each of `targets`. An overlay can't be
+    /// changed while it's mounted, so a previous deployment is torn down and mounted again unless
+    /// it's unchanged and still mounted. Fails with [`Error::StaleMount`] if an overlay this
+    /// game's manifest doesn't know about is in the way.
+    fn deploy_overlay(&self, game: &Game, targets: Vec<PathBuf>) -> Result<()> {
+        let manifest = Manifest {
+            profile: self.id.uid().0,
+            targets,
+            files: BTreeMap::new(),
+            loadout: Some(format!("{:016x}", self.loadout_hash()?)),
+            vanilla: Vec::new(),
+            link_strategy: game.link_strategy()?,
+            overlay: true,
+        };
+
+        let manifest_path = game.deployment_manifest()?;
+        if let Some(previous) = Manifest::load(&manifest_path)? {
+            let mut mounted = true;
+            for target in &previous.targets {
+                mounted &= overlay::is_mounted(target)?;
+            }
+            if previous == manifest && mounted {
+                info!("Profile {} is already deployed", self.name()?);
+                return Ok(());
+            }
+
+            game.undeploy_manifest(&previous)?;
+            fs::remove_file(&manifest_path)?;
+        }
+
+        let mounts = self.overlay_mounts(&manifest.targets)?;
+        for (index, mount) in mounts.iter().enumerate() {
+            if let Err(err) = mount.mount() {
+                // Don't leave the game half deployed
+                for mounted in mounts.iter().take(index) {
+                    overlay::unmount(&mounted.target)?;
+                }
+                return Err(err);
+            }
+        }
+        manifest.save(&manifest_path)?;
+        self.db.flush()?;
+
+        info!(
+            "Deployed profile {} as overlays over {} targets",
+            self.name()?,
+            mounts.len()
+        );
+
+        Ok(())
+    }
+
+    /// The overlays deploying this profile mounts over `targets`
+    fn overlay_mounts(&self, targets: &[PathBuf]) -> Result<Vec<OverlayMount>> {
+        let mod_dirs = self
+            .effective_entries()?
+            .iter()
+            .map(|entry| entry.mod_()?.content_dir())
+            .collect::<Result<Vec<_>>>()?;
+        let overlay_dir = self.dir()?.join(OVERLAY_DIR);
+
+        Ok(targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let dir = overlay_dir.join(index.to_string());
+                OverlayMount {
+                    target: target.clone(),
+                    mod_dirs: mod_dirs.clone(),
+                    upper_dir: dir.join("upper"),
+                    work_dir: dir.join("work"),
+                }
+            })
+            .collect())
+    }
+
+    /// Tear down whatever is deployed for the parent game, then deploy this profile from
+    /// scratch rather than incrementally
+    pub fn redeploy(&self) -> Result<()> {
+        let game = self.parent()?;
+        let lock = game.deploy_lock();
+        let _guard = lock.lock();
+
+        let manifest_path = game.deployment_manifest()?;
+        if let Some(previous) = Manifest::load(&manifest_path)? {
+            game.undeploy_manifest(&previous)?;
+            fs::remove_file(manifest_path)?;
+        }
+
+        self.deploy()
+    }
+
+    /// Remove the links made by [`Profile::deploy`] and restore the config files its INI tweaks
+    /// changed. Does nothing if this profile isn't deployed.
+    pub fn undeploy(&self) -> Result<()> {
+        let game = self.parent()?;
+        let lock = game.deploy_lock();
+        let _guard = lock.lock();
+
+        let manifest_path = game.deployment_manifest()?;
+        match Manifest::load(&manifest_path)? {
+            Some(manifest) if manifest.profile == self.id.uid().0 => {
+                game.undeploy_manifest(&manifest)?;
+                fs::remove_file(manifest_path)?;
+                match fs::remove_file(game.deployment_manifest_path()?) {
+                    Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+                    _ => {}
+                }
+
+                info!("Undeployed profile {}", self.name()?);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     /// Add a new [`ModEntry`] to a [`Profile`] that points to the [`Mod`] given by ID.
     pub fn add_mod_entry(&self, mod_: Mod) -> Result<ModEntry> {
         ModEntry::add(&self.db, &self.cfg, self, mod_)
     }
 
+    /// Returns `true` if this profile has an entry for `mod_`. Only the entries pointing at
+    /// `mod_`, one per profile at most, are looked at rather than this profile's whole load order.
+    pub fn contains_mod(&self, mod_: &Mod) -> Result<bool> {
+        for entry in mod_.entries()? {
+            if entry.parent()? == *self {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Move `entry` to `position` in this profile's load order. Fails with
+    /// [`Error::Locked`] if the move would shift a locked entry.
+    pub fn move_mod_entry(&self, entry: &ModEntry, position: usize) -> Result<()> {
+        entry.move_to(position)
+    }
+
+    /// Move `entry` `delta` places later in the load order, or earlier if it's negative,
+    /// stopping at either end. Fails with [`Error::Locked`] like [`Profile::move_mod_entry`].
+    pub fn nudge(&self, entry: &ModEntry, delta: i32) -> Result<()> {
+        entry.nudge(delta)
+    }
+
+    /// Reorder this profile's entries to follow `order`, a list of mod names such as a LOOT
+    /// export. Entries that aren't listed keep their order after the listed ones. Returns the
+    /// names that don't match any entry. Fails with [`Error::Locked`] if a locked entry would
+    /// move.
+    pub fn apply_load_order(&self, order: &[String]) -> Result<Vec<String>> {
+        ModEntry::apply_load_order(&self.db, &self.cfg, self, order)
+    }
+
+    /// Preview [`Profile::apply_load_order`] without reordering anything
+    pub fn load_order_plan(&self, order: &[String]) -> Result<LoadOrderPlan> {
+        ModEntry::plan_load_order(&self.db, &self.cfg, self, order)
+    }
+
+    /// Remove `entry` from this profile. Fails with [`Error::Locked`] if the entry is locked.
+    pub fn remove_mod_entry(&self, entry: ModEntry) -> Result<()> {
+        entry.remove()
+    }
+
     pub fn mod_entries(&self) -> Result<Vec<ModEntry>> {
         ModEntry::list(&self.db, &self.cfg, self)
     }
 
+    /// Like [`Profile::mod_entries`], but loads each [`ModEntry`] lazily so callers that stop
+    /// early don't pay for the rest
+    pub fn mod_entries_iter(&self) -> Result<impl Iterator<Item = Result<ModEntry>>> {
+        ModEntry::iter(&self.db, &self.cfg, self)
+    }
+
+    /// Returns the profile this one inherits its enabled entries from, if any. See
+    /// [`Profile::effective_entries`]. A base profile that has since been removed is ignored.
+    pub fn base(&self) -> Result<Option<Profile>> {
+        let Some(uid) = get_optional_field::<u64>(&self.db, self.id, "base_profile")? else {
+            return Ok(None);
+        };
+
+        self.parent()?.profile_by_uid(uid)
+    }
+
+    /// Make this profile inherit from `base`, or stop inheriting if `None`. The base has to be a
+    /// profile of the same game, and fails with [`Error::InheritanceCycle`] if it inherits from
+    /// this profile, directly or not.
+    pub fn set_base(&self, base: Option<&Profile>) -> Result<()> {
+        let Some(base) = base else {
+            let db_id = self.id.db_id(&self.db)?;
+            self.db.write().exec_mut(
+                QueryBuilder::remove()
+                    .values("base_profile")
+                    .ids(db_id)
+                    .query(),
+            )?;
+            return Ok(());
+        };
+
+        if base.parent()? != self.parent()? {
+            return Err(Error::ForeignProfile);
+        }
+
+        let mut ancestor = Some(base.clone());
+        while let Some(profile) = ancestor {
+            if profile == *self {
+                return Err(Error::InheritanceCycle);
+            }
+            ancestor = profile.base()?;
+        }
+
+        self.set_field("base_profile", base.id.uid().0)
+    }
+
+    /// Returns the enabled entries this profile deploys, in load order.
+    ///
+    /// Without a [`Profile::base`], these are just the enabled [`Profile::mod_entries`]. With
+    /// one, the base's effective entries come first and this profile's own are layered on top.
+    /// An entry of this profile replaces the base's entry for the same mod, so a mod the base
+    /// enables can be moved later or disabled. Changes to the base show up in every profile that
+    /// inherits from it.
+    pub fn effective_entries(&self) -> Result<Vec<ModEntry>> {
+        let mut chain = vec![self.clone()];
+        let mut base = self.base()?;
+        while let Some(profile) = base {
+            // Guards against cycles that slipped past `set_base`
+            if chain.contains(&profile) {
+                return Err(Error::InheritanceCycle);
+            }
+            base = profile.base()?;
+            chain.push(profile);
+        }
+
+        let mut effective: Vec<ModEntry> = Vec::new();
+        for profile in chain.iter().rev() {
+            let entries = profile.mod_entries()?;
+            effective.retain(|e| !entries.iter().any(|own| own.mod_id == e.mod_id));
+            for entry in entries {
+                if entry.enabled()? {
+                    effective.push(entry);
+                }
+            }
+        }
+
+        Ok(effective)
+    }
+
+    /// Remove this profile along with its directory. A directory that's already gone, or can't
+    /// be removed, is logged rather than treated as an error since the profile itself is gone
+    /// by then.
     pub fn remove(self) -> Result<()> {
+        self.remove_with(true)
+    }
+
+    /// Like [`Profile::remove`], but leaves the profile's directory on disk, for debugging or
+    /// recovering its files
+    pub fn remove_keep_files(self) -> Result<()> {
+        self.remove_with(false)
+    }
+
+    fn remove_with(self, remove_files: bool) -> Result<()> {
         for entry in self.mod_entries()? {
             let entry_id = entry.entry_id;
             entry
-                .remove()
+                .remove_unchecked()
                 .or_else(|err| match err {
                     Error::RemovedEntity => Ok(()), // if id is stale assume already removed
                     other => Err(other),
@@ -196,14 +1165,24 @@ impl Profile {
             .write()
             .exec_mut(QueryBuilder::remove().ids(db_id).query())?;
 
-        fs::remove_dir_all(dir).unwrap();
+        if remove_files {
+            match fs::remove_dir_all(&dir) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    warn!("Directory of profile {name} was already removed");
+                }
+                Err(err) => warn!("Failed to remove {}: {err}", dir.display()),
+            }
+        }
 
-        // Bootstrap active profile if there isn't one set
+        // Bootstrap active profile if there isn't one set, unless it was deliberately cleared
         if Profile::active(self.db.clone(), self.cfg.clone(), parent_game.clone())?.is_none()
-            && let Some(first_profile) =
-                Profile::list(&self.db.clone(), &self.cfg.clone(), &parent_game.clone())?.first()
+            && !parent_game.active_profile_cleared()?
+            && let Some(first_profile) = Profile::iter(&self.db, &self.cfg, &parent_game)?
+                .next()
+                .transpose()?
         {
-            first_profile.activate()?;
+            first_profile.activate_in_game()?;
         }
 
         info!("Removed profile: {name}");
@@ -213,12 +1192,10 @@ impl Profile {
 
     pub(crate) fn add(db: &Db, cfg: &Cfg, game: &Game, name: &str) -> Result<Self> {
         let model = ProfileModel::new(Uid::new(db)?, name);
-        if game
-            .profiles()?
-            .iter()
-            .any(|p: &Profile| p.name().unwrap() == model.name())
-        {
-            return Err(Error::DuplicateName);
+        for profile in game.profiles_iter()? {
+            if safe_dir_name(&profile?.name()?) == safe_dir_name(model.name()) {
+                return Err(Error::UniqueViolation(UniqueConstraint::ProfileName));
+            }
         }
 
         let game_id = game.id.db_id(db)?;
@@ -246,21 +1223,28 @@ impl Profile {
 
         fs::create_dir_all(profile.dir()?).unwrap();
 
-        // Bootstrap active profile if there isn't one set
+        // Bootstrap active profile if there isn't one set, unless it was deliberately cleared
         if Profile::active(db.clone(), cfg.clone(), game.clone())?.is_none()
-            && let Some(first_profile) =
-                Profile::list(&db.clone(), &cfg.clone(), &game.clone())?.first()
+            && !game.active_profile_cleared()?
+            && let Some(first_profile) = Profile::iter(db, cfg, game)?.next().transpose()?
         {
-            first_profile.activate()?;
-            return Ok(first_profile.clone());
+            first_profile.activate_in_game()?;
         }
 
         Ok(profile)
     }
 
     pub(crate) fn list(db: &Db, cfg: &Cfg, game: &Game) -> Result<Vec<Self>> {
+        Profile::iter(db, cfg, game)?.collect()
+    }
+
+    pub(crate) fn iter(
+        db: &Db,
+        cfg: &Cfg,
+        game: &Game,
+    ) -> Result<impl Iterator<Item = Result<Self>> + use<>> {
         let db_id = game.id.db_id(db)?;
-        Ok(db
+        let elements = db
             .read()
             .exec(
                 QueryBuilder::select()
@@ -269,17 +1253,55 @@ impl Profile {
                     .from(db_id)
                     .query(),
             )?
-            .elements
-            .iter()
-            .map(|e| Profile::load(e.id, db.clone(), cfg.clone()).unwrap())
-            .collect())
+            .elements;
+
+        let (db, cfg) = (db.clone(), cfg.clone());
+        Ok(elements
+            .into_iter()
+            .map(move |e| Profile::load(e.id, db.clone(), cfg.clone())))
     }
 
-    /// Search for a profile under the given game by name
-    pub(crate) fn search(db: Db, cfg: Cfg, game: &Game, name: &str) -> Result<Option<Profile>> {
-        let game_id = game.id.db_id(&db)?;
-        db.read()
-            .exec(
+    /// Count the profiles of the given game without loading them
+    pub(crate) fn count(db: &Db, game: &Game) -> Result<u64> {
+        let db_id = game.id.db_id(db)?;
+        let count = db
+            .read()
+            .exec(
+                QueryBuilder::search()
+                    .from(db_id)
+                    .where_()
+                    .element::<ProfileModel>()
+                    .query(),
+            )?
+            .result;
+
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    /// Load only the first profile of the given game
+    pub(crate) fn first(db: &Db, cfg: &Cfg, game: &Game) -> Result<Option<Self>> {
+        let db_id = game.id.db_id(db)?;
+        db.read()
+            .exec(
+                QueryBuilder::search()
+                    .from(db_id)
+                    .limit(1)
+                    .where_()
+                    .element::<ProfileModel>()
+                    .query(),
+            )?
+            .elements
+            .first()
+            .map(|e| Profile::load(e.id, db.clone(), cfg.clone()))
+            .transpose()
+    }
+
+    /// Find `game`'s profile named `name`, ignoring case if no name matches exactly
+    pub(crate) fn search(db: Db, cfg: Cfg, game: &Game, name: &str) -> Result<Option<Profile>> {
+        let game_id = game.id.db_id(&db)?;
+        let exact = db
+            .read()
+            .exec(
                 QueryBuilder::select()
                     .element::<ProfileModel>()
                     .search()
@@ -292,7 +1314,20 @@ impl Profile {
             .elements
             .first()
             .map(|p| Profile::load(p.id, db.clone(), cfg.clone()))
-            .transpose()
+            .transpose()?;
+        if exact.is_some() {
+            return Ok(exact);
+        }
+
+        let name = name.to_lowercase();
+        for profile in game.profiles_iter()? {
+            let profile = profile?;
+            if profile.name()?.to_lowercase() == name {
+                return Ok(Some(profile));
+            }
+        }
+
+        Ok(None)
     }
 
     fn get_field<T>(&self, field: &str) -> Result<T>
@@ -311,17 +1346,60 @@ impl Profile {
     }
 }
 
+/// Format `order` as a `plugins.txt`, one plugin per line. Creation Engine games mark enabled
+/// plugins with an asterisk.
+fn format_plugins(order: &[String], deploy_kind: DeployKind) -> String {
+    let prefix = match deploy_kind {
+        DeployKind::CreationEngine => "*",
+        _ => "",
+    };
+
+    order
+        .iter()
+        .map(|plugin| format!("{prefix}{plugin}\n"))
+        .collect()
+}
+
+/// Returns the plugins listed in a `plugins.txt` or `loadorder.txt`, in order. Comments and
+/// blank lines are skipped, and the asterisk Creation Engine games mark enabled plugins with is
+/// stripped.
+fn parse_plugins(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix('*').unwrap_or(line))
+}
+
 impl PartialEq for Profile {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
     }
 }
 
+impl Eq for Profile {}
+
+impl Hash for Profile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::{fs, path::Path};
+
+    use agdb::{CountComparison, QueryBuilder};
+    use tempfile::tempdir;
+
     use crate::{
         Repository,
-        repository::{DeployKind, entities::Error},
+        ini::IniTweak,
+        repository::{
+            DeployKind, LinkStrategy, Profile,
+            entities::{Error, UniqueConstraint},
+        },
+        test_utils::{add_entry_with_files, add_mod_with_files, assert_tree},
     };
 
     #[test]
@@ -343,7 +1421,7 @@ mod test {
 
         assert!(matches!(
             game.add_profile("Test"),
-            Err(Error::DuplicateName)
+            Err(Error::UniqueViolation(UniqueConstraint::ProfileName))
         ));
     }
 
@@ -367,6 +1445,31 @@ mod test {
         assert_eq!(game.profiles().unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_remove_missing_dir() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        fs::remove_dir_all(profile.dir().unwrap()).unwrap();
+        profile.remove().unwrap();
+
+        assert_eq!(game.profiles().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_remove_keep_files() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let dir = profile.dir().unwrap();
+
+        profile.remove_keep_files().unwrap();
+
+        assert!(dir.exists());
+        assert_eq!(game.profiles().unwrap().len(), 0);
+    }
+
     #[test]
     fn test_list() {
         let repo = Repository::mock();
@@ -379,6 +1482,22 @@ mod test {
         assert_eq!(repo.games().unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_search() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let other = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let vanilla = game.add_profile("Vanilla").unwrap();
+        let modded = game.add_profile("Modded").unwrap();
+        other.add_profile("Survival").unwrap();
+
+        assert_eq!(game.search_profile("Vanilla").unwrap(), Some(vanilla));
+        assert_eq!(game.search_profile("mODDED").unwrap(), Some(modded));
+        assert_eq!(game.search_profile("Hardcore").unwrap(), None);
+        // Profiles of other games aren't found
+        assert_eq!(game.search_profile("Survival").unwrap(), None);
+    }
+
     #[test]
     fn test_parent() {
         let repo = Repository::mock();
@@ -389,6 +1508,32 @@ mod test {
         assert_eq!(profile.parent().unwrap(), game);
     }
 
+    #[test]
+    fn test_parent_orphaned() {
+        let repo = Repository::mock();
+
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let db_id = profile.id.db_id(&profile.db).unwrap();
+        profile
+            .db
+            .write()
+            .exec_mut(
+                QueryBuilder::remove()
+                    .search()
+                    .to(db_id)
+                    .where_()
+                    .edge()
+                    .and()
+                    .distance(CountComparison::Equal(1))
+                    .query(),
+            )
+            .unwrap();
+
+        assert!(matches!(profile.parent(), Err(Error::Orphaned)));
+    }
+
     #[test]
     fn test_activate() {
         let repo = Repository::mock();
@@ -406,6 +1551,85 @@ mod test {
         assert!(profile2.is_active().unwrap());
     }
 
+    #[test]
+    fn test_activate_other_game() {
+        let repo = Repository::mock();
+
+        let morrowind = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let morrowind_profile = morrowind.add_profile("Test").unwrap();
+        let skyrim_profile1 = skyrim.add_profile("Test1").unwrap();
+        let skyrim_profile2 = skyrim.add_profile("Test2").unwrap();
+
+        morrowind.activate().unwrap();
+        skyrim_profile2.activate().unwrap();
+
+        // The game follows its profile
+        assert_eq!(repo.active_game().unwrap(), Some(skyrim.clone()));
+        assert_eq!(skyrim.active_profile().unwrap(), Some(skyrim_profile2));
+        assert!(!skyrim_profile1.is_active().unwrap());
+        // Other games keep their active profile
+        assert!(morrowind_profile.is_active().unwrap());
+    }
+
+    #[test]
+    fn test_clear_active_profile() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+
+        let profile = game.add_profile("Test").unwrap();
+        assert!(profile.is_active().unwrap());
+
+        repo.clear_active_profile().unwrap();
+        assert!(!profile.is_active().unwrap());
+        assert_eq!(game.active_profile().unwrap(), None);
+
+        // Adding or removing a profile doesn't pick one again
+        let other = game.add_profile("Other").unwrap();
+        assert_eq!(other.name().unwrap(), "Other");
+        assert_eq!(game.active_profile().unwrap(), None);
+        assert!(game.active_profile_cleared().unwrap());
+        other.remove().unwrap();
+        assert_eq!(game.active_profile().unwrap(), None);
+
+        profile.activate().unwrap();
+        assert!(profile.is_active().unwrap());
+        assert!(!game.active_profile_cleared().unwrap());
+    }
+
+    #[test]
+    fn test_no_active_profile() {
+        let repo = Repository::mock();
+
+        // A fresh database has nothing active
+        assert_eq!(repo.active_game().unwrap(), None);
+        repo.clear_active_profile().unwrap();
+
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        assert_eq!(game.active_profile().unwrap(), None);
+        assert_eq!(game.first_profile().unwrap(), None);
+        game.clear_active_profile().unwrap();
+        assert_eq!(game.active_profile().unwrap(), None);
+    }
+
+    #[test]
+    fn test_contains_mod() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let other_profile = game.add_profile("Other").unwrap();
+
+        let present = game.add_mod("Present", None).unwrap();
+        let absent = game.add_mod("Absent", None).unwrap();
+        profile.add_mod_entry(present.clone()).unwrap();
+        other_profile.add_mod_entry(absent.clone()).unwrap();
+
+        assert!(profile.contains_mod(&present).unwrap());
+        assert!(!profile.contains_mod(&absent).unwrap());
+        assert!(other_profile.contains_mod(&absent).unwrap());
+        assert!(!other_profile.contains_mod(&present).unwrap());
+    }
+
     #[test]
     fn test_remove_made_next_profile_active() {
         let repo = Repository::mock();
@@ -420,4 +1644,875 @@ mod test {
         profile1.remove().unwrap();
         assert!(profile2.is_active().unwrap());
     }
+
+    #[test]
+    fn test_ini_tweaks() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        assert!(profile.ini_tweaks().unwrap().is_empty());
+
+        let tweaks = vec![IniTweak::new("Skyrim.ini", "Display", "iSize W", "1920")];
+        profile.set_ini_tweaks(&tweaks).unwrap();
+
+        assert_eq!(profile.ini_tweaks().unwrap(), tweaks);
+    }
+
+    #[test]
+    fn test_apply_and_restore_ini_tweaks() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let root = tempdir().unwrap();
+        let original = "[Display]\niSize W=1280\n";
+        fs::write(root.path().join("Skyrim.ini"), original).unwrap();
+
+        profile
+            .set_ini_tweaks(&[
+                IniTweak::new("Skyrim.ini", "Display", "iSize W", "1920"),
+                IniTweak::new("SkyrimPrefs.ini", "General", "bFXAA", "1"),
+            ])
+            .unwrap();
+        profile.apply_ini_tweaks(root.path()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.path().join("Skyrim.ini")).unwrap(),
+            "[Display]\niSize W=1920\n"
+        );
+        assert!(root.path().join("SkyrimPrefs.ini").exists());
+
+        profile.restore_ini_tweaks(root.path()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.path().join("Skyrim.ini")).unwrap(),
+            original
+        );
+        assert!(!root.path().join("SkyrimPrefs.ini").exists());
+    }
+
+    #[test]
+    fn test_deploy_applies_ini_tweaks() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let other = game.add_profile("Other").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+        let ini = target.path().join("Skyrim.ini");
+        let original = "[Display]\niSize W=1280\n";
+        fs::write(&ini, original).unwrap();
+        add_entry_with_files(&profile, "Mod", &[("mod.esp", "")]);
+
+        profile
+            .set_ini_tweaks(&[IniTweak::new("Skyrim.ini", "Display", "iSize W", "1920")])
+            .unwrap();
+        profile.deploy().unwrap();
+        assert_eq!(
+            fs::read_to_string(&ini).unwrap(),
+            "[Display]\niSize W=1920\n"
+        );
+
+        // Deploying another profile puts the original back first
+        other.deploy().unwrap();
+        assert_eq!(fs::read_to_string(&ini).unwrap(), original);
+
+        profile.deploy().unwrap();
+        profile.undeploy().unwrap();
+        assert_eq!(fs::read_to_string(&ini).unwrap(), original);
+        assert_tree(target.path(), &["Skyrim.ini"]);
+    }
+
+    #[test]
+    fn test_file_overrides() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let mod1 = add_entry_with_files(&profile, "Mod1", &[("meshes/armor.nif", "Mod1")])
+            .mod_()
+            .unwrap();
+        add_entry_with_files(&profile, "Mod2", &[("meshes/armor.nif", "Mod2")]);
+
+        let path = Path::new("meshes/armor.nif");
+        let winner = |profile: &Profile| {
+            profile
+                .deploy_plan()
+                .unwrap()
+                .get(path)
+                .unwrap()
+                .name()
+                .unwrap()
+        };
+
+        // Last one wins by default
+        assert_eq!(winner(&profile), "Mod2");
+
+        profile.set_file_override(path, &mod1).unwrap();
+        assert_eq!(winner(&profile), "Mod1");
+        assert_eq!(profile.file_overrides().unwrap().get(path), Some(&mod1));
+
+        profile.clear_file_override(path).unwrap();
+        assert_eq!(winner(&profile), "Mod2");
+        assert!(profile.file_overrides().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let files: [(&str, &[&str]); 3] = [
+            ("Mod1", &["meshes/armor.nif", "meshes/sword.nif"]),
+            ("Mod2", &["meshes/armor.nif", "textures/armor.dds"]),
+            ("Mod3", &["meshes/armor.nif", "meshes/sword.nif"]),
+        ];
+        let mut mods = Vec::new();
+        for (name, paths) in files {
+            let files: Vec<(&str, &str)> = paths.iter().map(|path| (*path, name)).collect();
+            let mod_ = add_mod_with_files(&game, name, &files);
+            fs::create_dir_all(mod_.dir().unwrap().join("scripts")).unwrap();
+            mod_.reindex().unwrap();
+            profile.add_mod_entry(mod_.clone()).unwrap();
+            mods.push(mod_);
+        }
+
+        let providers = |path: &str| -> Vec<String> {
+            profile
+                .conflicts()
+                .unwrap()
+                .get(Path::new(path))
+                .map(|entries| entries.iter().map(|e| e.name().unwrap()).collect())
+                .unwrap_or_default()
+        };
+
+        // Files only one mod ships aren't conflicts, and neither are shared directories
+        assert_eq!(profile.conflicts().unwrap().len(), 2);
+        assert!(providers("textures/armor.dds").is_empty());
+        assert!(providers("meshes").is_empty());
+        assert!(providers("scripts").is_empty());
+        assert_eq!(providers("meshes/armor.nif"), ["Mod1", "Mod2", "Mod3"]);
+        assert_eq!(providers("meshes/sword.nif"), ["Mod1", "Mod3"]);
+
+        // Overrides move the pinned mod to the end
+        let mod1 = mods.first().unwrap();
+        profile
+            .set_file_override(Path::new("meshes/sword.nif"), mod1)
+            .unwrap();
+        assert_eq!(providers("meshes/sword.nif"), ["Mod3", "Mod1"]);
+
+        // Disabled entries don't take part
+        let mod3 = profile.mod_entries().unwrap().pop().unwrap();
+        mod3.set_enabled(false).unwrap();
+        assert_eq!(providers("meshes/armor.nif"), ["Mod1", "Mod2"]);
+        assert!(providers("meshes/sword.nif").is_empty());
+    }
+
+    #[test]
+    fn test_redundant_mods() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let base = game.add_profile("Base").unwrap();
+        let child = game.add_profile("Child").unwrap();
+        child.set_base(Some(&base)).unwrap();
+
+        let files: [(&str, &[&str]); 4] = [
+            ("Mod1", &["meshes/armor.nif", "meshes/sword.nif"]),
+            ("Mod2", &["meshes/armor.nif", "textures/armor.dds"]),
+            ("Mod3", &["meshes/armor.nif", "meshes/sword.nif"]),
+            ("Empty", &[]),
+        ];
+        let mut mods = Vec::new();
+        for (name, paths) in files {
+            let files: Vec<(&str, &str)> = paths.iter().map(|path| (*path, name)).collect();
+            let entry = add_entry_with_files(&base, name, &files);
+            mods.push(entry.mod_().unwrap());
+        }
+        let (mod1, mod2) = (mods.first().unwrap(), mods.get(1).unwrap());
+
+        // Every file of Mod1 is provided by Mod3, and mods without files don't count
+        assert_eq!(base.redundant_mods().unwrap(), vec![mod1.clone()]);
+
+        // The child disables the inherited mod without touching the base
+        assert_eq!(child.disable_redundant_mods().unwrap(), vec![mod1.clone()]);
+        assert!(child.redundant_mods().unwrap().is_empty());
+        assert_eq!(base.redundant_mods().unwrap(), vec![mod1.clone()]);
+
+        // Locked and required mods are left enabled
+        let entry = base.mod_entries().unwrap().into_iter().next().unwrap();
+        entry.set_locked(true).unwrap();
+        assert!(base.disable_redundant_mods().unwrap().is_empty());
+        entry.set_locked(false).unwrap();
+
+        mod2.add_requirement(mod1).unwrap();
+        assert!(base.disable_redundant_mods().unwrap().is_empty());
+        mod2.remove_requirement(mod1).unwrap();
+
+        assert_eq!(base.disable_redundant_mods().unwrap(), vec![mod1.clone()]);
+        assert!(!entry.enabled().unwrap());
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let base = game.add_profile("Base").unwrap();
+        let middle = game.add_profile("Middle").unwrap();
+        let top = game.add_profile("Top").unwrap();
+
+        let mod1 = game.add_mod("Mod1", None).unwrap();
+        let mod2 = game.add_mod("Mod2", None).unwrap();
+        let mod3 = game.add_mod("Mod3", None).unwrap();
+        let mod4 = game.add_mod("Mod4", None).unwrap();
+        let names = |profile: &Profile| -> Vec<String> {
+            profile
+                .effective_entries()
+                .unwrap()
+                .iter()
+                .map(|e| e.name().unwrap())
+                .collect()
+        };
+
+        base.add_mod_entry(mod1.clone()).unwrap();
+        base.add_mod_entry(mod2).unwrap();
+        middle.add_mod_entry(mod3).unwrap();
+        top.add_mod_entry(mod4).unwrap();
+        assert_eq!(names(&top), ["Mod4"]);
+
+        middle.set_base(Some(&base)).unwrap();
+        top.set_base(Some(&middle)).unwrap();
+        assert_eq!(top.base().unwrap(), Some(middle.clone()));
+        assert_eq!(names(&middle), ["Mod1", "Mod2", "Mod3"]);
+        assert_eq!(names(&top), ["Mod1", "Mod2", "Mod3", "Mod4"]);
+
+        // Changes to the base propagate down the chain
+        let base_mod2 = base.mod_entries().unwrap().pop().unwrap();
+        base_mod2.set_enabled(false).unwrap();
+        assert_eq!(names(&top), ["Mod1", "Mod3", "Mod4"]);
+        base_mod2.set_enabled(true).unwrap();
+
+        // A child's own entry for an inherited mod replaces it, moving or disabling it
+        let top_mod1 = top.add_mod_entry(mod1).unwrap();
+        assert_eq!(names(&top), ["Mod2", "Mod3", "Mod4", "Mod1"]);
+        top_mod1.set_enabled(false).unwrap();
+        assert_eq!(names(&top), ["Mod2", "Mod3", "Mod4"]);
+        assert_eq!(names(&middle), ["Mod1", "Mod2", "Mod3"]);
+
+        // Cycles are refused
+        assert!(matches!(
+            base.set_base(Some(&top)),
+            Err(Error::InheritanceCycle)
+        ));
+        assert!(matches!(
+            top.set_base(Some(&top)),
+            Err(Error::InheritanceCycle)
+        ));
+        assert_eq!(base.base().unwrap(), None);
+
+        let other = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let foreign = other.add_profile("Default").unwrap();
+        assert!(matches!(
+            top.set_base(Some(&foreign)),
+            Err(Error::ForeignProfile)
+        ));
+
+        top.set_base(None).unwrap();
+        assert_eq!(top.base().unwrap(), None);
+        assert_eq!(names(&top), ["Mod4"]);
+    }
+
+    #[test]
+    fn test_deploy_through_symlinked_target() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        // The game directory lives on another drive, with a symlink pointing at it
+        let tmp = tempdir().unwrap();
+        let real = tmp.path().join("drive/skyrim");
+        let link = tmp.path().join("skyrim");
+        fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        game.set_targets(&[link.clone()]).unwrap();
+
+        add_entry_with_files(&profile, "Mod", &[("mod.esp", "")]);
+
+        profile.deploy().unwrap();
+
+        assert!(link.is_symlink());
+        assert!(real.join("mod.esp").is_symlink());
+
+        profile.undeploy().unwrap();
+        assert!(link.is_symlink());
+        assert_tree(&real, &[]);
+    }
+
+    #[test]
+    fn test_providers_of() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let add = |name: &str| {
+            let entry = add_entry_with_files(&profile, name, &[("meshes/armor.nif", name)]);
+            (entry.mod_().unwrap(), entry)
+        };
+        let (mod1, _) = add("Mod1");
+        let (mod2, entry2) = add("Mod2");
+        let (mod3, _) = add("Mod3");
+
+        let path = Path::new("meshes/armor.nif");
+        assert_eq!(
+            profile.providers_of(path).unwrap(),
+            vec![mod1.clone(), mod2, mod3.clone()]
+        );
+        assert!(
+            profile
+                .providers_of(Path::new("meshes/helmet.nif"))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Disabled mods don't provide anything
+        entry2.set_enabled(false).unwrap();
+        assert_eq!(
+            profile.providers_of(path).unwrap(),
+            vec![mod1.clone(), mod3.clone()]
+        );
+
+        // The pinned mod wins, so it goes last
+        profile.set_file_override(path, &mod1).unwrap();
+        assert_eq!(profile.providers_of(path).unwrap(), vec![mod3, mod1]);
+    }
+
+    #[test]
+    fn test_deploy_applies_delta() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        add_entry_with_files(&profile, "Mod1", &[("meshes/armor.nif", "Mod1")]);
+        let entry2 = add_entry_with_files(
+            &profile,
+            "Mod2",
+            &[("meshes/armor.nif", "Mod2"), ("mod2.esp", "")],
+        );
+
+        assert!(!game.is_deployed().unwrap());
+        profile.deploy().unwrap();
+        assert!(game.is_deployed().unwrap());
+
+        let armor = target.path().join("meshes/armor.nif");
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Mod2");
+        assert!(target.path().join("mod2.esp").is_symlink());
+
+        // Deploying again without changes leaves the links alone
+        let before = fs::symlink_metadata(&armor).unwrap().modified().unwrap();
+        profile.deploy().unwrap();
+        assert_eq!(
+            fs::symlink_metadata(&armor).unwrap().modified().unwrap(),
+            before
+        );
+
+        entry2.set_enabled(false).unwrap();
+        profile.deploy().unwrap();
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Mod1");
+        assert!(!target.path().join("mod2.esp").exists());
+
+        profile.undeploy().unwrap();
+        assert!(!game.is_deployed().unwrap());
+        assert_tree(target.path(), &[]);
+    }
+
+    #[test]
+    fn test_deploy_writes_loadout_manifest() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let mod1 = add_entry_with_files(&profile, "Mod1", &[("mod1.esp", "")])
+            .mod_()
+            .unwrap();
+        mod1.set_category("Armor").unwrap();
+        mod1.set_version(Some("1.2.0")).unwrap();
+        mod1.set_author(Some("Bethesda")).unwrap();
+        let entry2 = add_entry_with_files(&profile, "Mod2", &[("mod2.esp", "")]);
+
+        let path = game.deployment_manifest_path().unwrap();
+        let read = || -> serde_json::Value {
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap()
+        };
+
+        profile.deploy().unwrap();
+        let manifest = read();
+        assert_eq!(manifest["version"], 1);
+        assert_eq!(manifest["game"], "Skyrim");
+        assert_eq!(manifest["profile"], "Test");
+        assert_eq!(manifest["mods"][0]["name"], "Mod1");
+        assert_eq!(manifest["mods"][0]["version"], "1.2.0");
+        assert_eq!(manifest["mods"][0]["author"], "Bethesda");
+        assert_eq!(manifest["mods"][0]["category"], "Armor");
+        assert_eq!(
+            manifest["mods"][0]["content_hash"],
+            serde_json::json!(mod1.content_hash().unwrap())
+        );
+        assert_eq!(manifest["mods"][1]["name"], "Mod2");
+        assert!(manifest["mods"][1]["version"].is_null());
+        assert!(manifest["mods"][1]["category"].is_null());
+        assert_eq!(
+            manifest["plugins"],
+            serde_json::json!(["mod1.esp", "mod2.esp"])
+        );
+
+        // It follows the profile on every deploy
+        entry2.set_enabled(false).unwrap();
+        profile.deploy().unwrap();
+        let manifest = read();
+        assert_eq!(manifest["mods"].as_array().unwrap().len(), 1);
+        assert_eq!(manifest["plugins"], serde_json::json!(["mod1.esp"]));
+
+        profile.undeploy().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_deploy_keeps_vanilla_files() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+        let armor = target.path().join("meshes/armor.nif");
+        fs::create_dir_all(armor.parent().unwrap()).unwrap();
+        fs::write(&armor, "Vanilla").unwrap();
+
+        let entry = add_entry_with_files(
+            &profile,
+            "Mod",
+            &[("meshes/armor.nif", "Mod"), ("mod.esp", "")],
+        );
+
+        let armor_path = Path::new("meshes/armor.nif");
+        let overwrites = profile.vanilla_overwrites().unwrap();
+        assert_eq!(overwrites.keys().collect::<Vec<_>>(), vec![armor_path]);
+        assert_eq!(overwrites.get(armor_path), Some(&entry));
+
+        profile.deploy().unwrap();
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Mod");
+        // Still an overwrite while the game file is moved aside
+        assert_eq!(profile.vanilla_overwrites().unwrap(), overwrites);
+
+        entry.set_enabled(false).unwrap();
+        profile.deploy().unwrap();
+        assert!(!armor.is_symlink());
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Vanilla");
+
+        entry.set_enabled(true).unwrap();
+        profile.deploy().unwrap();
+        profile.undeploy().unwrap();
+        assert_tree(target.path(), &["meshes/", "meshes/armor.nif"]);
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Vanilla");
+    }
+
+    #[test]
+    fn test_failed_deploy_can_be_undone() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+        let armor = target.path().join("meshes/armor.nif");
+        fs::create_dir_all(armor.parent().unwrap()).unwrap();
+        fs::write(&armor, "Vanilla").unwrap();
+        // A directory in the way of a later file makes the deployment fail partway through
+        let blocked = target.path().join("meshes/boots.nif");
+        fs::create_dir_all(blocked.join("nested")).unwrap();
+
+        add_entry_with_files(
+            &profile,
+            "Mod",
+            &[("meshes/armor.nif", "Mod"), ("meshes/boots.nif", "Mod")],
+        );
+
+        assert!(profile.deploy().is_err());
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Mod");
+        assert!(profile.needs_deploy().unwrap());
+
+        fs::remove_dir_all(&blocked).unwrap();
+        profile.undeploy().unwrap();
+        assert!(!armor.is_symlink());
+        assert_eq!(fs::read_to_string(&armor).unwrap(), "Vanilla");
+        assert_tree(target.path(), &["meshes/", "meshes/armor.nif"]);
+    }
+
+    #[test]
+    fn test_deploy_with_hardlinks() {
+        use std::os::unix::fs::MetadataExt;
+
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        game.set_link_strategy(LinkStrategy::Hardlink).unwrap();
+
+        // Same filesystem as the library, so nothing has to be copied
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let entry = add_entry_with_files(&profile, "Mod", &[("meshes/armor.nif", "Mod")]);
+        let source = entry
+            .mod_()
+            .unwrap()
+            .dir()
+            .unwrap()
+            .join("meshes/armor.nif");
+
+        profile.deploy().unwrap();
+        let armor = target.path().join("meshes/armor.nif");
+        assert!(!armor.is_symlink());
+        assert_eq!(
+            fs::metadata(&armor).unwrap().ino(),
+            fs::metadata(&source).unwrap().ino()
+        );
+        // Deployed files aren't mistaken for game files
+        assert!(profile.vanilla_overwrites().unwrap().is_empty());
+
+        assert!(matches!(
+            game.set_link_strategy(LinkStrategy::Symlink),
+            Err(Error::Deployed)
+        ));
+
+        profile.undeploy().unwrap();
+        assert_tree(target.path(), &[]);
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn test_deploy_command() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Generic", DeployKind::Overlay).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let mut mod_dirs = Vec::new();
+        for name in ["First", "Second"] {
+            let mod_ = game.add_mod(name, None).unwrap();
+            mod_dirs.push(mod_.dir().unwrap());
+            profile.add_mod_entry(mod_).unwrap();
+        }
+        let (first, second) = (mod_dirs.first().unwrap(), mod_dirs.get(1).unwrap());
+
+        let target = target.path().canonicalize().unwrap();
+        let upper = profile.dir().unwrap().join("overlay/0/upper");
+        let work = profile.dir().unwrap().join("overlay/0/work");
+        assert_eq!(
+            profile.deploy_command().unwrap(),
+            vec![format!(
+                "mount -t overlay overlay -o lowerdir={}:{}:{},upperdir={},workdir={} {}",
+                second.display(),
+                first.display(),
+                target.display(),
+                upper.display(),
+                work.display(),
+                target.display()
+            )]
+        );
+        assert!(upper.is_dir());
+        assert!(work.is_dir());
+
+        // Games deployed with links don't mount anything
+        game.set_deploy_kind(DeployKind::Gamebryo).unwrap();
+        assert!(profile.deploy_command().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_loadout_hash() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let entry1 = profile
+            .add_mod_entry(game.add_mod("Mod1", None).unwrap())
+            .unwrap();
+        let entry2 = profile
+            .add_mod_entry(game.add_mod("Mod2", None).unwrap())
+            .unwrap();
+
+        let hash = profile.loadout_hash().unwrap();
+        assert_eq!(profile.loadout_hash().unwrap(), hash);
+
+        entry2.set_enabled(false).unwrap();
+        assert_ne!(profile.loadout_hash().unwrap(), hash);
+        entry2.set_enabled(true).unwrap();
+        assert_eq!(profile.loadout_hash().unwrap(), hash);
+
+        profile.move_mod_entry(&entry1, 1).unwrap();
+        assert_ne!(profile.loadout_hash().unwrap(), hash);
+
+        assert!(profile.needs_deploy().unwrap());
+        profile.deploy().unwrap();
+        assert!(!profile.needs_deploy().unwrap());
+        entry1.set_enabled(false).unwrap();
+        assert!(profile.needs_deploy().unwrap());
+    }
+
+    #[test]
+    fn test_loadout_hash_overrides_and_plugins() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let mod1 = add_entry_with_files(&profile, "Mod1", &[("A.esp", ""), ("armor.nif", "1")])
+            .mod_()
+            .unwrap();
+        add_entry_with_files(&profile, "Mod2", &[("B.esp", ""), ("armor.nif", "2")]);
+        profile.deploy().unwrap();
+        assert!(!profile.needs_deploy().unwrap());
+
+        let path = Path::new("armor.nif");
+        profile.set_file_override(path, &mod1).unwrap();
+        assert!(profile.needs_deploy().unwrap());
+        profile.deploy().unwrap();
+        assert!(!profile.needs_deploy().unwrap());
+
+        profile
+            .set_plugin_order(&["B.esp".into(), "A.esp".into()])
+            .unwrap();
+        assert!(profile.needs_deploy().unwrap());
+        profile.deploy().unwrap();
+        assert!(!profile.needs_deploy().unwrap());
+    }
+
+    #[test]
+    fn test_plugin_order() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let target = tempdir().unwrap();
+        game.set_targets(&[target.path().to_path_buf()]).unwrap();
+
+        let add_mod = |name: &str| {
+            add_entry_with_files(&profile, name, &[(format!("{name}.esp").as_str(), "")])
+        };
+
+        add_mod("A");
+        add_mod("B");
+        assert_eq!(profile.plugin_order().unwrap(), vec!["A.esp", "B.esp"]);
+
+        profile
+            .set_plugin_order(&["B.esp".into(), "A.esp".into()])
+            .unwrap();
+        let c = add_mod("C");
+        assert_eq!(
+            profile.plugin_order().unwrap(),
+            vec!["B.esp", "A.esp", "C.esp"]
+        );
+
+        c.set_enabled(false).unwrap();
+        profile.deploy().unwrap();
+        assert_eq!(
+            profile.plugins_file().unwrap(),
+            profile.dir().unwrap().join("plugins.txt")
+        );
+        assert_eq!(
+            fs::read_to_string(profile.plugins_file().unwrap()).unwrap(),
+            "*B.esp\n*A.esp\n"
+        );
+
+        // Written where the game reads it once that's known
+        let appdata = tempdir().unwrap();
+        let plugins_dir = appdata.path().join("Skyrim Special Edition");
+        game.set_plugins_dir(Some(&plugins_dir)).unwrap();
+        assert_eq!(game.plugins_dir().unwrap(), Some(plugins_dir.clone()));
+        fs::create_dir(&plugins_dir).unwrap();
+        fs::write(plugins_dir.join("plugins.txt"), "*Vanilla.esp\n").unwrap();
+        profile.deploy().unwrap();
+        assert_eq!(
+            profile.plugins_file().unwrap(),
+            plugins_dir.join("plugins.txt")
+        );
+        assert_eq!(
+            fs::read_to_string(plugins_dir.join("plugins.txt")).unwrap(),
+            "*B.esp\n*A.esp\n"
+        );
+
+        game.set_plugins_dir(None).unwrap();
+        assert_eq!(game.plugins_dir().unwrap(), None);
+    }
+
+    #[test]
+    fn test_export_import() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        for name in ["SkyUI", "USSEP", "Immersive Armors"] {
+            let mod_ = game.add_mod(name, None).unwrap();
+            profile.add_mod_entry(mod_).unwrap();
+        }
+        let [skyui, ussep, armors] = profile.mod_entries().unwrap().try_into().unwrap();
+        profile.move_mod_entry(&armors, 0).unwrap();
+        ussep.set_enabled(false).unwrap();
+        skyui.set_notes("Needs SKSE").unwrap();
+        let skyui_mod = skyui.mod_().unwrap();
+        skyui_mod.set_version(Some("5.2")).unwrap();
+        skyui_mod.set_author(Some("schlangster")).unwrap();
+        skyui_mod
+            .set_source_url(Some(
+                "https://www.nexusmods.com/skyrimspecialedition/mods/12604",
+            ))
+            .unwrap();
+
+        let tmp = tempdir().unwrap();
+        let exported = tmp.path().join("profile.toml");
+        profile.export(&exported).unwrap();
+        // Nothing ties the file to this library
+        let contents = fs::read_to_string(&exported).unwrap();
+        let library_dir = repo.cfg.read().library_dir().to_string_lossy().into_owned();
+        assert!(!contents.contains(&library_dir));
+
+        // The other game already has one of the mods, which is used as is
+        let other = repo
+            .add_game("Skyrim VR", DeployKind::CreationEngine)
+            .unwrap();
+        let existing = other.add_mod("USSEP", None).unwrap();
+        let imported = other.import_profile(&exported, "Shared").unwrap();
+
+        let describe = |profile: &Profile| -> Vec<(String, bool, String)> {
+            profile
+                .mod_entries()
+                .unwrap()
+                .iter()
+                .map(|e| (e.name().unwrap(), e.enabled().unwrap(), e.notes().unwrap()))
+                .collect()
+        };
+        assert_eq!(imported.name().unwrap(), "Shared");
+        assert_eq!(describe(&imported), describe(&profile));
+        assert_eq!(other.mods().unwrap().len(), 3);
+        assert!(imported.contains_mod(&existing).unwrap());
+
+        // Placeholders carry the exported metadata
+        let placeholder = other
+            .mods()
+            .unwrap()
+            .into_iter()
+            .find(|m| m.name().unwrap() == "SkyUI")
+            .unwrap();
+        assert_eq!(placeholder.version().unwrap().as_deref(), Some("5.2"));
+        assert_eq!(
+            placeholder.author().unwrap().as_deref(),
+            Some("schlangster")
+        );
+        assert_eq!(
+            placeholder.source_url().unwrap(),
+            skyui_mod.source_url().unwrap()
+        );
+
+        assert!(matches!(
+            other.import_profile(&exported, "Shared"),
+            Err(Error::UniqueViolation(UniqueConstraint::ProfileName))
+        ));
+
+        // Both placeholders would share a directory, so nothing is left of a failed import
+        let third = repo
+            .add_game("Enderal", DeployKind::CreationEngine)
+            .unwrap();
+        let broken = tmp.path().join("broken.toml");
+        fs::write(
+            &broken,
+            "[[mods]]\nname = \"SkyUI\"\nenabled = true\n\n\
+             [[mods]]\nname = \"Sky UI\"\nenabled = true\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            third.import_profile(&broken, "Broken"),
+            Err(Error::UniqueViolation(UniqueConstraint::ModName))
+        ));
+        assert!(third.profiles().unwrap().is_empty());
+        assert!(third.mods().unwrap().is_empty());
+
+        // Without an active profile, the entries still go into the new profile, and a failed
+        // import leaves the existing ones alone
+        let kept = third.add_profile("Kept").unwrap();
+        kept.add_mod_entry(third.add_mod("Kept Mod", None).unwrap())
+            .unwrap();
+        third.clear_active_profile().unwrap();
+        assert!(third.import_profile(&broken, "Broken").is_err());
+        assert_eq!(third.profiles().unwrap(), vec![kept.clone()]);
+        assert_eq!(kept.mod_entries().unwrap().len(), 1);
+
+        let imported = third.import_profile(&exported, "Shared").unwrap();
+        assert_eq!(imported.name().unwrap(), "Shared");
+        assert_eq!(describe(&imported), describe(&profile));
+        assert_eq!(kept.mod_entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_plugin_order_export_import() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        for name in ["A", "B", "C"] {
+            add_entry_with_files(&profile, name, &[(format!("{name}.esp").as_str(), "")]);
+        }
+        profile
+            .set_plugin_order(&["C.esp".into(), "A.esp".into(), "B.esp".into()])
+            .unwrap();
+
+        let tmp = tempdir().unwrap();
+        let exported = tmp.path().join("plugins.txt");
+        game.export_plugin_load_order(&exported).unwrap();
+        assert_eq!(
+            fs::read_to_string(&exported).unwrap(),
+            "*C.esp\n*A.esp\n*B.esp\n"
+        );
+
+        // Round trip
+        profile.set_plugin_order(&[]).unwrap();
+        assert!(game.import_plugin_load_order(&exported).unwrap().is_empty());
+        assert_eq!(
+            profile.plugin_order().unwrap(),
+            vec!["C.esp", "A.esp", "B.esp"]
+        );
+
+        // LOOT's loadorder.txt has no asterisks and may differ in case
+        let loadorder = tmp.path().join("loadorder.txt");
+        fs::write(
+            &loadorder,
+            "# This file is used by Skyrim to keep track of your downloaded content.\r\n\
+             Skyrim.esm\r\nb.esp\r\nA.esp\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            game.import_plugin_load_order(&loadorder).unwrap(),
+            vec!["Skyrim.esm"]
+        );
+        assert_eq!(
+            profile.plugin_order().unwrap(),
+            vec!["B.esp", "A.esp", "C.esp"]
+        );
+
+        repo.clear_active_profile().unwrap();
+        assert!(matches!(
+            game.export_plugin_load_order(&exported),
+            Err(Error::NoActiveProfile)
+        ));
+    }
 }
@@ -0,0 +1,253 @@
+//! Reviewing the layout of an archive before it's installed into a mod.
+//!
+//! Archives don't always put their files where the game expects them: some wrap everything in a
+//! directory named after the mod, others ship several variants side by side. Staging extracts
+//! the archive next to the mod, so the directory to install from, the data root, can be picked
+//! before anything in the mod itself changes.
+
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use tracing::info;
+use walkdir::WalkDir;
+
+use crate::{
+    fs::{Permissions, change_dir_permissions},
+    repository::{
+        StorageKind,
+        entities::{Error, Mod, Result},
+    },
+};
+
+/// An archive extracted into a staging directory by [`Mod::stage`]. Nothing is installed until
+/// [`StagedMod::commit`] is called, and [`StagedMod::discard`] cleans the staging directory up
+/// again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedMod {
+    mod_: Mod,
+    dir: PathBuf,
+    source: PathBuf,
+    /// Relative to `dir`
+    data_root: PathBuf,
+}
+
+impl StagedMod {
+    pub(crate) fn new(mod_: Mod, dir: PathBuf, source: PathBuf) -> Self {
+        Self {
+            mod_,
+            dir,
+            source,
+            data_root: PathBuf::new(),
+        }
+    }
+
+    /// Returns the mod the staged files are going to be installed into
+    pub fn mod_(&self) -> &Mod {
+        &self.mod_
+    }
+
+    /// Returns the directory the archive was extracted into
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the directory whose contents get installed, relative to [`StagedMod::dir`]. It
+    /// starts out empty, installing everything.
+    pub fn data_root(&self) -> &Path {
+        &self.data_root
+    }
+
+    /// Install from `data_root` instead, which has to be a directory of the staged files.
+    /// An empty path installs everything.
+    pub fn set_data_root(&mut self, data_root: &Path) -> Result<()> {
+        let inside = data_root
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        if !inside || !self.dir.join(data_root).is_dir() {
+            return Err(Error::InvalidDataRoot(data_root.to_path_buf()));
+        }
+
+        self.data_root = data_root.to_path_buf();
+
+        Ok(())
+    }
+
+    /// Returns the relative paths of every staged directory, in sorted order. These are the
+    /// candidates for [`StagedMod::set_data_root`].
+    pub fn directories(&self) -> Result<Vec<PathBuf>> {
+        self.walk(true)
+    }
+
+    /// Returns the relative paths of the files that would be installed from the current data
+    /// root, in sorted order
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let root = self.dir.join(&self.data_root);
+        Ok(self
+            .walk(false)?
+            .into_iter()
+            .filter_map(|path| {
+                self.dir
+                    .join(&path)
+                    .strip_prefix(&root)
+                    .ok()
+                    .map(Path::to_path_buf)
+            })
+            .collect())
+    }
+
+    fn walk(&self, directories: bool) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for entry in WalkDir::new(&self.dir).min_depth(1).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            if entry.file_type().is_dir() != directories {
+                continue;
+            }
+
+            if let Ok(relative) = entry.path().strip_prefix(&self.dir) {
+                paths.push(relative.to_path_buf());
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Replace the mod's files with the contents of the data root. The mod is stored loose
+    /// afterwards, even if it was archived, since the adjusted layout no longer matches its
+    /// archive.
+    pub fn commit(self) -> Result<()> {
+        let mod_dir = self.mod_.dir()?;
+        if mod_dir.exists() {
+            fs::remove_dir_all(&mod_dir)?;
+        }
+        let archive_path = self.mod_.archive_path()?;
+        if archive_path.exists() {
+            fs::remove_file(archive_path)?;
+        }
+        self.mod_.clear_unpacked()?;
+
+        fs::rename(self.dir.join(&self.data_root), &mod_dir)?;
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        if self.mod_.read_only()? {
+            change_dir_permissions(&mod_dir, Permissions::ReadOnly)?;
+        }
+
+        self.mod_.set_field("storage_kind", StorageKind::Loose)?;
+        self.mod_.record_source(&self.source)?;
+        self.mod_.reindex()?;
+        self.mod_
+            .set_field("content_hash", self.mod_.hash_content()?)?;
+
+        info!("Installed staged files into mod: {}", self.mod_.name()?);
+
+        Ok(())
+    }
+
+    /// Throw the staged files away, leaving the mod as it was
+    pub fn discard(self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use tempfile::tempdir;
+
+    use crate::{
+        Repository,
+        repository::{DeployKind, StorageKind, entities::Error},
+        test_utils::{assert_tree, write_zip},
+    };
+
+    #[test]
+    fn test_stage_commit() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let mod_ = game.add_mod("Armor", None).unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("armor.zip");
+        write_zip(
+            &archive,
+            &[
+                ("Armor v2/Data/meshes/armor.nif", "armor"),
+                ("Armor v2/readme.txt", "readme"),
+            ],
+        );
+
+        let mut staged = mod_.stage(&archive).unwrap();
+        assert_eq!(staged.data_root(), Path::new(""));
+        assert_eq!(
+            staged.directories().unwrap(),
+            vec![
+                PathBuf::from("Armor v2"),
+                PathBuf::from("Armor v2/Data"),
+                PathBuf::from("Armor v2/Data/meshes")
+            ]
+        );
+        assert_eq!(
+            staged.files().unwrap(),
+            vec![
+                PathBuf::from("Armor v2/Data/meshes/armor.nif"),
+                PathBuf::from("Armor v2/readme.txt")
+            ]
+        );
+
+        assert!(matches!(
+            staged.set_data_root(Path::new("../Armor v2")),
+            Err(Error::InvalidDataRoot(_))
+        ));
+        assert!(matches!(
+            staged.set_data_root(Path::new("Armor v2/readme.txt")),
+            Err(Error::InvalidDataRoot(_))
+        ));
+        staged.set_data_root(Path::new("Armor v2/Data")).unwrap();
+        assert_eq!(
+            staged.files().unwrap(),
+            vec![PathBuf::from("meshes/armor.nif")]
+        );
+
+        let staging_dir = staged.dir().to_path_buf();
+        staged.commit().unwrap();
+        assert!(!staging_dir.exists());
+        assert_eq!(mod_.storage_kind().unwrap(), StorageKind::Loose);
+        assert_tree(&mod_.dir().unwrap(), &["meshes/", "meshes/armor.nif"]);
+        assert_eq!(
+            mod_.files().unwrap(),
+            vec![PathBuf::from("meshes/armor.nif")]
+        );
+        assert_eq!(mod_.source().unwrap(), Some(archive));
+    }
+
+    #[test]
+    fn test_stage_discard() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let mod_ = game.add_mod("Armor", None).unwrap();
+        fs::write(mod_.dir().unwrap().join("armor.esp"), "").unwrap();
+
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("armor.zip");
+        write_zip(&archive, &[("meshes/armor.nif", "armor")]);
+
+        let staged = mod_.stage(&archive).unwrap();
+        let staging_dir = staged.dir().to_path_buf();
+        assert!(staging_dir.exists());
+
+        staged.discard().unwrap();
+        assert!(!staging_dir.exists());
+        assert_tree(&mod_.dir().unwrap(), &["armor.esp"]);
+    }
+}
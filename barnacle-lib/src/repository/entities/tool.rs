@@ -1,11 +1,24 @@
-use std::{fmt::Debug, path::PathBuf};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    time::Instant,
+};
 
-use agdb::DbValue;
+use agdb::{DbId, DbValue, QueryBuilder, QueryId};
+use tracing::{info, warn};
 
 use crate::repository::{
     config::Cfg,
-    db::Db,
-    entities::{EntityId, Result, get_field, set_field},
+    db::{
+        Db,
+        models::{GameModel, ToolModel},
+    },
+    entities::{
+        EntityId, Error, Result, Uid, game::Game, get_field, get_optional_field, profile::Profile,
+        set_field,
+    },
 };
 
 /// Represents a tool entity in the Barnacle system.
@@ -24,6 +37,38 @@ impl Tool {
         Ok(Self { id, db, cfg })
     }
 
+    pub(crate) fn list(db: Db, cfg: Cfg) -> Result<Vec<Tool>> {
+        db.read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<ToolModel>()
+                    .search()
+                    .from("tools")
+                    .query(),
+            )?
+            .elements
+            .iter()
+            .map(|e| Tool::load(EntityId::load(&db, e.id)?, db.clone(), cfg.clone()))
+            .collect()
+    }
+
+    /// List the tools registered for `game`
+    pub(crate) fn list_for(db: &Db, cfg: &Cfg, game: &Game) -> Result<Vec<Tool>> {
+        let game_id = game.id.db_id(db)?;
+        db.read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<ToolModel>()
+                    .search()
+                    .from(game_id)
+                    .query(),
+            )?
+            .elements
+            .iter()
+            .map(|e| Tool::load(EntityId::load(db, e.id)?, db.clone(), cfg.clone()))
+            .collect()
+    }
+
     pub fn name(&self) -> Result<String> {
         self.get_field("name")
     }
@@ -32,9 +77,118 @@ impl Tool {
         self.get_field("path")
     }
 
-    // TODO: This can actually be Option<String>
-    pub fn args(&self) -> Result<String> {
-        self.get_field("args")
+    pub(crate) fn set_path(&self, new_path: PathBuf) -> Result<()> {
+        self.set_field("path", new_path)
+    }
+
+    /// Additional command-line arguments passed to the tool, if any
+    pub fn args(&self) -> Result<Option<String>> {
+        get_optional_field(&self.db, self.id, "args")
+    }
+
+    /// Build the command that runs this tool. The arguments are split like a shell would, see
+    /// [`split_args`].
+    pub fn command(&self) -> Result<Command> {
+        let mut command = Command::new(self.path()?);
+        if let Some(args) = self.args()? {
+            command.args(split_args(&args)?);
+        }
+
+        Ok(command)
+    }
+
+    /// Spawn this tool as a child process. If one of the parent game's profiles is being
+    /// deployed, this waits for it to finish so the tool never sees a half deployed game.
+    pub fn run(&self) -> Result<Child> {
+        self.spawn(self.command()?)
+    }
+
+    /// Like [`Tool::run`], but with the tool's stdout and stderr piped so its output can be read
+    /// from the returned [`Child`]
+    pub fn run_captured(&self) -> Result<Child> {
+        let mut command = self.command()?;
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        self.spawn(command)
+    }
+
+    fn spawn(&self, mut command: Command) -> Result<Child> {
+        let lock = self.parent()?.deploy_lock();
+        let _guard = lock.lock();
+
+        Ok(command.spawn()?)
+    }
+
+    /// Returns the [`Game`] this tool belongs to
+    pub fn parent(&self) -> Result<Game> {
+        let parent_game_id = self
+            .db
+            .read()
+            .exec(
+                QueryBuilder::select()
+                    .elements::<GameModel>()
+                    .search()
+                    // Reverse search to parent game from tool
+                    .to(self.id.db_id(&self.db)?)
+                    .limit(1)
+                    .query(),
+            )?
+            .elements
+            .pop()
+            .ok_or(Error::Orphaned)?
+            .id;
+
+        Game::load(parent_game_id, self.db.clone(), self.cfg.clone())
+    }
+
+    pub(crate) fn add(
+        db: Db,
+        cfg: Cfg,
+        game: &Game,
+        name: &str,
+        path: PathBuf,
+        args: Option<&str>,
+    ) -> Result<Self> {
+        let game_id = game.id.db_id(&db)?;
+
+        let model = ToolModel::new(Uid::new(&db)?, name, path, args);
+        let tool_id = db.write().transaction_mut(|t| -> Result<DbId> {
+            let tool_id = t
+                .exec_mut(QueryBuilder::insert().element(model).query())?
+                .elements
+                .first()
+                .expect("ToolModel insertion should return the ID as the first element")
+                .id;
+
+            // Link Tool to the specified Game node and root "tools" node
+            t.exec_mut(
+                QueryBuilder::insert()
+                    .edges()
+                    .from([QueryId::from("tools"), QueryId::from(game_id)])
+                    .to(tool_id)
+                    .query(),
+            )?;
+
+            Ok(tool_id)
+        })?;
+
+        Tool::load(EntityId::load(&db, tool_id)?, db, cfg)
+    }
+
+    /// Unregister this tool. Its executable is left alone.
+    pub fn remove(self) -> Result<()> {
+        let name = self.name()?;
+        let db_id = self.id.db_id(&self.db)?;
+        self.db
+            .write()
+            .exec_mut(QueryBuilder::remove().ids(db_id).query())?;
+
+        info!("Removed tool: {name}");
+
+        Ok(())
     }
 
     fn get_field<T>(&self, field: &str) -> Result<T>
@@ -53,8 +207,407 @@ impl Tool {
     }
 }
 
+/// A tool started by [`Game::launch`]. Waiting on it adds how long it ran to the play time of
+/// the profile that was active when it was launched.
+#[derive(Debug)]
+pub struct Launch {
+    child: Child,
+    /// Cleared once the play time has been recorded
+    profile: Option<Profile>,
+    started: Instant,
+}
+
+impl Launch {
+    /// Count a launch of `child` for `profile`. Failing to update the counters only logs a
+    /// warning, it's no reason to leave a running tool unattended.
+    pub(crate) fn new(child: Child, profile: Option<Profile>) -> Self {
+        if let Some(profile) = &profile
+            && let Err(err) = profile.record_launch()
+        {
+            warn!("Failed to record launch: {err}");
+        }
+
+        Self {
+            child,
+            profile,
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the launched process, to read its output or kill it
+    pub fn child_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Wait for the launched process to exit, then add how long it ran to the profile's play
+    /// time
+    pub fn wait(&mut self) -> Result<ExitStatus> {
+        let status = self.child.wait()?;
+        self.record_play_time();
+
+        Ok(status)
+    }
+
+    /// Like [`Launch::wait`], but returns `None` right away if the process is still running, so
+    /// it can be polled without blocking whatever else needs the [`Launch`], like killing it
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        let status = self.child.try_wait()?;
+        if status.is_some() {
+            self.record_play_time();
+        }
+
+        Ok(status)
+    }
+
+    fn record_play_time(&mut self) {
+        if let Some(profile) = self.profile.take()
+            && let Err(err) = profile.add_play_time(self.started.elapsed())
+        {
+            warn!("Failed to record play time: {err}");
+        }
+    }
+}
+
+/// Split `args` into separate arguments on whitespace, like a shell would. Single quotes keep
+/// everything between them as is, double quotes only treat a backslash before `"` or `\` as an
+/// escape, and outside of quotes a backslash only escapes a quote or whitespace, so Windows
+/// paths like `C:\Games\x` are left alone. Fails with [`Error::UnterminatedQuote`] if a quote is
+/// never closed.
+fn split_args(args: &str) -> Result<Vec<String>> {
+    let mut split = Vec::new();
+    // The argument being built, `None` between arguments so `""` still makes an empty one
+    let mut current: Option<String> = None;
+    let mut chars = args.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => split.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(Error::UnterminatedQuote(args.to_string())),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err(Error::UnterminatedQuote(args.to_string())),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(Error::UnterminatedQuote(args.to_string())),
+                    }
+                }
+            }
+            '\\' => {
+                let arg = current.get_or_insert_default();
+                match chars.clone().next() {
+                    Some(c @ ('\'' | '"')) => {
+                        arg.push(c);
+                        chars.next();
+                    }
+                    Some(c) if c.is_whitespace() => {
+                        arg.push(c);
+                        chars.next();
+                    }
+                    _ => arg.push('\\'),
+                }
+            }
+            c => current.get_or_insert_default().push(c),
+        }
+    }
+    split.extend(current);
+
+    Ok(split)
+}
+
 impl PartialEq for Tool {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
     }
 }
+
+impl Eq for Tool {}
+
+impl Hash for Tool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::PathBuf,
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{Repository, repository::DeployKind};
+
+    use super::*;
+
+    #[test]
+    fn test_run_captured() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let tool = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "Echo",
+            PathBuf::from("echo"),
+            Some("hello world"),
+        )
+        .unwrap();
+        assert_eq!(game.tools().unwrap(), vec![tool.clone()]);
+
+        let output = tool.run_captured().unwrap().wait_with_output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "hello world\n");
+    }
+
+    #[test]
+    fn test_run_waits_for_deploy() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let tool = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "True",
+            PathBuf::from("true"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(tool.parent().unwrap(), game);
+
+        // Stand in for a deployment that takes a while
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let deploying_game = game.clone();
+        let deploy = thread::spawn(move || {
+            let lock = deploying_game.deploy_lock();
+            let _guard = lock.lock();
+            locked_tx.send(Instant::now()).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            Instant::now()
+        });
+
+        let locked_at = locked_rx.recv().unwrap();
+        assert!(game.is_deploying());
+        tool.run().unwrap().wait().unwrap();
+        let launched_at = Instant::now();
+
+        let deployed_at = deploy.join().unwrap();
+        assert!(launched_at >= deployed_at);
+        assert!(launched_at.duration_since(locked_at) >= Duration::from_millis(200));
+        assert!(!game.is_deploying());
+    }
+
+    #[test]
+    fn test_deploy_lock_per_repository() {
+        let repo = Repository::mock();
+        let other_repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let other_game = other_repo
+            .add_game("Skyrim", DeployKind::CreationEngine)
+            .unwrap();
+        // UIDs are only unique within a database
+        assert_eq!(game.id.uid(), other_game.id.uid());
+
+        let lock = game.deploy_lock();
+        let _guard = lock.lock();
+        let deploying_game = game.clone();
+        let (is_deploying, other_is_deploying) =
+            thread::spawn(move || (deploying_game.is_deploying(), other_game.is_deploying()))
+                .join()
+                .unwrap();
+        assert!(is_deploying);
+        assert!(!other_is_deploying);
+    }
+
+    #[test]
+    fn test_launch() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Default").unwrap();
+        let other = game.add_profile("Other").unwrap();
+        let tool = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "Sleep",
+            PathBuf::from("sleep"),
+            Some("1"),
+        )
+        .unwrap();
+        assert_eq!(profile.launch_count().unwrap(), 0);
+        assert_eq!(profile.last_played().unwrap(), None);
+        assert_eq!(profile.play_time().unwrap(), Duration::ZERO);
+
+        let mut launch = game.launch(&tool).unwrap();
+        assert_eq!(profile.launch_count().unwrap(), 1);
+        assert!(profile.last_played().unwrap().is_some());
+        assert!(launch.wait().unwrap().success());
+        assert!(profile.play_time().unwrap() >= Duration::from_secs(1));
+
+        // Only the active profile is counted
+        assert_eq!(other.launch_count().unwrap(), 0);
+        assert_eq!(other.play_time().unwrap(), Duration::ZERO);
+
+        // Polling only counts the play time once the process has exited
+        let mut launch = game.launch(&tool).unwrap();
+        assert!(launch.try_wait().unwrap().is_none());
+        launch.child_mut().kill().unwrap();
+        while launch.try_wait().unwrap().is_none() {}
+        assert_eq!(profile.launch_count().unwrap(), 2);
+
+        // Without an active profile there's nothing to count towards
+        repo.clear_active_profile().unwrap();
+        game.launch(&tool).unwrap().wait().unwrap();
+        assert_eq!(profile.launch_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_args() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let path = PathBuf::from("/usr/bin/loot");
+
+        let no_args = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "LOOT",
+            path.clone(),
+            None,
+        )
+        .unwrap();
+        let empty_args = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "LOOT (empty)",
+            path.clone(),
+            Some(""),
+        )
+        .unwrap();
+        let with_args = Tool::add(
+            repo.db.clone(),
+            repo.cfg.clone(),
+            &game,
+            "LOOT (sorted)",
+            path,
+            Some("--game Skyrim --sort"),
+        )
+        .unwrap();
+
+        assert_eq!(no_args.args().unwrap(), None);
+        assert_eq!(empty_args.args().unwrap(), Some(String::new()));
+
+        assert_eq!(no_args.command().unwrap().get_args().count(), 0);
+        assert_eq!(empty_args.command().unwrap().get_args().count(), 0);
+        assert_eq!(
+            with_args.command().unwrap().get_args().collect::<Vec<_>>(),
+            vec!["--game", "Skyrim", "--sort"]
+        );
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let other = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let loot = game
+            .add_tool("LOOT", PathBuf::from("/usr/bin/loot"), None)
+            .unwrap();
+        let xedit = game
+            .add_tool(
+                "xEdit",
+                PathBuf::from("/usr/bin/xedit"),
+                Some("-quickautoclean"),
+            )
+            .unwrap();
+        other
+            .add_tool("LOOT", PathBuf::from("/usr/bin/loot"), None)
+            .unwrap();
+
+        assert_eq!(game.tools().unwrap(), vec![loot.clone(), xedit.clone()]);
+        assert_eq!(xedit.args().unwrap().as_deref(), Some("-quickautoclean"));
+        assert_eq!(xedit.parent().unwrap(), game);
+        assert_eq!(repo.tools().unwrap().len(), 3);
+
+        loot.remove().unwrap();
+        assert_eq!(game.tools().unwrap(), vec![xedit]);
+        assert_eq!(repo.tools().unwrap().len(), 2);
+
+        // Removing a game takes its tools along
+        other.remove().unwrap();
+        assert_eq!(repo.tools().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("").unwrap(), Vec::<String>::new());
+        assert_eq!(split_args("   ").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            split_args("  --game   Skyrim ").unwrap(),
+            vec!["--game", "Skyrim"]
+        );
+
+        // Quoted
+        assert_eq!(
+            split_args(r#"--config "C:\My Game\loot.yaml""#).unwrap(),
+            vec!["--config", r"C:\My Game\loot.yaml"]
+        );
+        assert_eq!(
+            split_args(r#"--name 'The "Best" Mod' --sort"#).unwrap(),
+            vec!["--name", r#"The "Best" Mod"#, "--sort"]
+        );
+        assert_eq!(
+            split_args(r#""say \"hi\"" it's'"#).unwrap(),
+            vec![r#"say "hi""#, "its"]
+        );
+        assert_eq!(
+            split_args(r#"--out=/tmp/"a b"/c"#).unwrap(),
+            vec!["--out=/tmp/a b/c"]
+        );
+
+        // Escaped
+        assert_eq!(
+            split_args(r"/games/My\ Game/data \'x").unwrap(),
+            vec!["/games/My Game/data", "'x"]
+        );
+        assert_eq!(
+            split_args(r#"--path C:\Games\x \"quoted\" C:\\share\"#).unwrap(),
+            vec!["--path", r"C:\Games\x", r#""quoted""#, r"C:\\share\"]
+        );
+
+        // Empty
+        assert_eq!(
+            split_args(r#"--prefix "" ''"#).unwrap(),
+            vec!["--prefix", "", ""]
+        );
+
+        assert!(matches!(
+            split_args(r#"--config "unclosed"#),
+            Err(Error::UnterminatedQuote(_))
+        ));
+        assert!(matches!(
+            split_args("'unclosed"),
+            Err(Error::UnterminatedQuote(_))
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+//! Finding and merging mods that are different versions of the same thing.
+//!
+//! Installing an update as a new mod rather than reinstalling the old one leaves both in the
+//! library, usually named after their version, like "SkyUI v5.1" and "SkyUI 5.2". Unlike
+//! [`Repository::find_duplicate_mods`], their content differs, so they're matched by name and the
+//! user picks which one to keep.
+
+use std::collections::BTreeMap;
+
+use tracing::info;
+
+use crate::repository::{
+    Repository,
+    entities::{self, Error, Mod},
+};
+
+/// Words that only describe the version of a mod, ignored when comparing names
+const VERSION_WORDS: [&str; 4] = ["v", "ver", "version", "update"];
+
+impl Repository {
+    /// Find mods of the same game whose names only differ by a version number, spacing,
+    /// punctuation or case, so they can be merged with [`Repository::merge_mods`]. Each group is
+    /// ordered from the oldest to the most recently added mod. Matching is a heuristic, so the
+    /// groups are only suggestions to confirm with the user.
+    pub fn suggest_merges(&self) -> entities::Result<Vec<Vec<Mod>>> {
+        let mut groups = Vec::new();
+        for game in self.games()? {
+            let mut mods: BTreeMap<String, Vec<Mod>> = BTreeMap::new();
+            for mod_ in game.mods()? {
+                let base_name = base_name(&mod_.name()?);
+                if !base_name.is_empty() {
+                    mods.entry(base_name).or_default().push(mod_);
+                }
+            }
+
+            groups.extend(
+                mods.into_values()
+                    .filter(|group| group.len() > 1)
+                    .map(|mut group| {
+                        group.sort_by_key(|m| m.id.uid());
+                        group
+                    }),
+            );
+        }
+
+        Ok(groups)
+    }
+
+    /// Merge `drop` into `keep`, so only the files of `keep` remain. Every profile using `drop`
+    /// uses `keep` in its place, at the same position and with the same state and notes, unless
+    /// it already has an entry for `keep`. Mods requiring `drop` require `keep` instead. `drop` is
+    /// removed afterwards, along with its files.
+    ///
+    /// Fails with [`Error::ForeignMod`] if the mods belong to different games, and with
+    /// [`Error::Locked`] if any entry for `drop` is locked, before changing anything.
+    pub fn merge_mods(&self, keep: &Mod, drop: Mod) -> entities::Result<()> {
+        if *keep == drop {
+            return Ok(());
+        }
+        if keep.parent()? != drop.parent()? {
+            return Err(Error::ForeignMod);
+        }
+
+        let mut entries = Vec::new();
+        for profile in drop.used_by()? {
+            for entry in profile.mod_entries()? {
+                if entry.mod_id != drop.id {
+                    continue;
+                }
+                if entry.locked()? {
+                    return Err(Error::Locked);
+                }
+                entries.push((profile.contains_mod(keep)?, entry));
+            }
+        }
+
+        for (has_keep, mut entry) in entries {
+            if has_keep {
+                entry.remove()?;
+            } else {
+                entry.repoint(keep)?;
+            }
+        }
+
+        for dependent in drop.required_by()? {
+            dependent.remove_requirement(&drop)?;
+            if dependent != *keep {
+                match dependent.add_requirement(keep) {
+                    // Requiring `keep` would close a loop, so the requirement is dropped instead
+                    Ok(()) | Err(Error::DependencyCycle) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        let (kept, dropped) = (keep.name()?, drop.name()?);
+        drop.remove()?;
+        info!("Merged mod {dropped} into {kept}");
+
+        Ok(())
+    }
+}
+
+/// The name of a mod without its version, case, spacing and punctuation, so "Cool Mod v1.2" and
+/// "coolmod-2.0" both become "coolmod"
+fn base_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !is_version(word))
+        .collect()
+}
+
+fn is_version(word: &str) -> bool {
+    let digits = word.strip_prefix('v').unwrap_or(word);
+    VERSION_WORDS.contains(&word)
+        || (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::repository::{DeployKind, Profile, Repository, entities::Error};
+
+    use super::base_name;
+
+    #[test]
+    fn test_base_name() {
+        assert_eq!(base_name("Cool Mod v1.2"), "coolmod");
+        assert_eq!(base_name("coolmod-2.0"), "coolmod");
+        assert_eq!(base_name("Cool_Mod (Version 3)"), "coolmod");
+        assert_eq!(base_name("Cool Mod Vanilla"), "coolmodvanilla");
+        assert_eq!(base_name("1.0"), "");
+    }
+
+    #[test]
+    fn test_suggest_merges() {
+        let repo = Repository::mock();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let oblivion = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let old = skyrim.add_mod("SkyUI v5.1", None).unwrap();
+        let new = skyrim.add_mod("SkyUI 5.2", None).unwrap();
+        skyrim.add_mod("SkyUI Patches", None).unwrap();
+        skyrim.add_mod("2.0", None).unwrap();
+        skyrim.add_mod("3.0", None).unwrap();
+        oblivion.add_mod("SkyUI", None).unwrap();
+
+        assert_eq!(repo.suggest_merges().unwrap(), vec![vec![old, new]]);
+    }
+
+    #[test]
+    fn test_merge_mods() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let both = game.add_profile("Both").unwrap();
+        let old_only = game.add_profile("Old Only").unwrap();
+        let old = game.add_mod("SkyUI v5.1", None).unwrap();
+        let new = game.add_mod("SkyUI v5.2", None).unwrap();
+        let other = game.add_mod("Patch", None).unwrap();
+        other.add_requirement(&old).unwrap();
+
+        both.add_mod_entry(old.clone()).unwrap();
+        both.add_mod_entry(new.clone()).unwrap();
+        old_only.add_mod_entry(other.clone()).unwrap();
+        let entry = old_only.add_mod_entry(old.clone()).unwrap();
+        entry.set_enabled_force(false).unwrap();
+
+        // A locked entry stops the merge before anything changes
+        entry.set_locked(true).unwrap();
+        assert!(matches!(
+            repo.merge_mods(&new, old.clone()),
+            Err(Error::Locked)
+        ));
+        assert_eq!(game.mods().unwrap().len(), 3);
+        entry.set_locked(false).unwrap();
+
+        let foreign = repo
+            .add_game("Oblivion", DeployKind::Gamebryo)
+            .unwrap()
+            .add_mod("SkyUI", None)
+            .unwrap();
+        assert!(matches!(
+            repo.merge_mods(&new, foreign),
+            Err(Error::ForeignMod)
+        ));
+
+        repo.merge_mods(&new, old).unwrap();
+        assert_eq!(game.mods().unwrap(), vec![new.clone(), other.clone()]);
+
+        let names = |profile: &Profile| -> Vec<String> {
+            profile
+                .mod_entries()
+                .unwrap()
+                .iter()
+                .map(|e| e.name().unwrap())
+                .collect()
+        };
+        assert_eq!(names(&both), vec!["SkyUI v5.2"]);
+        assert_eq!(names(&old_only), vec!["Patch", "SkyUI v5.2"]);
+
+        let repointed = old_only.mod_entries().unwrap().pop().unwrap();
+        assert!(!repointed.enabled().unwrap());
+        assert_eq!(other.requires().unwrap(), vec![new]);
+    }
+}
@@ -0,0 +1,107 @@
+//! The file tree a game sees once a profile is deployed.
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::repository::{Mod, Profile, entities};
+
+/// A directory of the merged file tree returned by [`Profile::merged_tree`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergedTree {
+    /// Subdirectories, by name
+    pub dirs: BTreeMap<String, MergedTree>,
+    /// Files, by name, along with the mod that provides each of them
+    pub files: BTreeMap<String, Mod>,
+}
+
+impl MergedTree {
+    /// Returns the number of files in this directory and all of its subdirectories
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+            + self
+                .dirs
+                .values()
+                .map(MergedTree::file_count)
+                .sum::<usize>()
+    }
+
+    /// Returns the mod providing the file at `path`, relative to this directory
+    pub fn provider_of(&self, path: &Path) -> Option<&Mod> {
+        let components = components(path);
+        let (name, parents) = components.split_last()?;
+        let mut dir = self;
+        for parent in parents {
+            dir = dir.dirs.get(parent)?;
+        }
+
+        dir.files.get(name)
+    }
+
+    fn insert(&mut self, path: &Path, provider: Mod) {
+        let components = components(path);
+        let Some((name, parents)) = components.split_last() else {
+            return;
+        };
+
+        let mut dir = self;
+        for parent in parents {
+            dir = dir.dirs.entry(parent.clone()).or_default();
+        }
+        dir.files.insert(name.clone(), provider);
+    }
+}
+
+fn components(path: &Path) -> Vec<String> {
+    path.iter()
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect()
+}
+
+impl Profile {
+    /// Returns the file tree the game sees once this profile is deployed, with the mod providing
+    /// each file. This is [`Profile::deploy_plan`] arranged as a tree, so the same rules decide
+    /// which mod wins a file.
+    pub fn merged_tree(&self) -> entities::Result<MergedTree> {
+        let mut tree = MergedTree::default();
+        for (path, entry) in self.deploy_plan()? {
+            tree.insert(&path, entry.mod_()?);
+        }
+
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{Repository, repository::DeployKind, test_utils::add_entry_with_files};
+
+    #[test]
+    fn test_merged_tree() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+
+        let add = |name: &str, files: &[&str]| {
+            let files: Vec<(&str, &str)> = files.iter().map(|file| (*file, name)).collect();
+            add_entry_with_files(&profile, name, &files).mod_().unwrap()
+        };
+        let mod1 = add("Mod1", &["meshes/armor.nif", "mod1.esp"]);
+        let mod2 = add("Mod2", &["meshes/armor.nif", "meshes/sword/sword.nif"]);
+
+        let tree = profile.merged_tree().unwrap();
+        assert_eq!(tree.file_count(), 3);
+        assert_eq!(tree.files.keys().collect::<Vec<_>>(), vec!["mod1.esp"]);
+        assert_eq!(tree.dirs.keys().collect::<Vec<_>>(), vec!["meshes"]);
+
+        // The later mod wins
+        assert_eq!(tree.provider_of(Path::new("meshes/armor.nif")), Some(&mod2));
+        assert_eq!(
+            tree.provider_of(Path::new("meshes/sword/sword.nif")),
+            Some(&mod2)
+        );
+        assert_eq!(tree.provider_of(Path::new("mod1.esp")), Some(&mod1));
+        assert_eq!(tree.provider_of(Path::new("meshes")), None);
+        assert_eq!(tree.provider_of(Path::new("missing.esp")), None);
+    }
+}
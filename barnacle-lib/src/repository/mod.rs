@@ -1,22 +1,50 @@
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use parking_lot::RwLock;
+use regex::Regex;
+use tracing::info;
 
 use crate::{
     Result,
+    progress::{OnProgress, Phase, Progress},
     repository::{
-        config::{Cfg, CoreConfig},
-        db::Db,
+        config::{CategoryRule, Cfg, CoreConfig},
+        db::{Db, models::CURRENT_MODEL_VERSION},
     },
 };
 
+mod bundle;
+mod compact;
 mod db;
+mod merge;
+mod merged_tree;
+mod orphans;
+mod relocate;
+mod scan;
 
 pub mod config;
 pub mod entities;
 
-pub use db::models::DeployKind;
-pub use entities::{Game, Mod, ModEntry, Profile, Tool};
+pub use bundle::BundleContents;
+pub use compact::Compaction;
+pub use db::models::{DeployKind, LinkStrategy, ParseDeployKindError, StorageKind};
+pub use entities::{
+    Game, Launch, LoadOrderPlan, Mod, ModEntry, Profile, StagedMod, Tool, UniqueConstraint,
+};
+pub use merged_tree::MergedTree;
+pub use relocate::Relocation;
+pub use scan::DetectedGame;
+
+/// A [`Mod`] along with the [`Game`] it belongs to, as returned by [`Repository::all_mods`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryMod {
+    pub mod_: Mod,
+    pub game: Game,
+}
 
 /// Central access point for all persistent data.
 ///
@@ -30,29 +58,353 @@ pub struct Repository {
 }
 
 impl Repository {
-    pub fn new() -> Self {
-        Self {
-            db: Db::new(),
-            cfg: Arc::new(RwLock::new(CoreConfig::load())),
+    /// Open the [`Repository`], running any pending database migrations. Fails if the XDG
+    /// directories can't be found, or the database or configuration can't be read.
+    pub fn new() -> entities::Result<Self> {
+        Ok(Self {
+            db: Db::new()?,
+            cfg: Arc::new(RwLock::new(CoreConfig::load()?)),
+        })
+    }
+
+    /// Open the [`Repository`] without running any pending database migrations. This is
+    /// intended for inspecting or explicitly performing migrations with [`Repository::migrate`].
+    pub fn without_migrations() -> entities::Result<Self> {
+        Ok(Self {
+            db: Db::open()?,
+            cfg: Arc::new(RwLock::new(CoreConfig::load()?)),
+        })
+    }
+
+    /// Open the [`Repository`] for previewing changes: pending database migrations aren't run
+    /// and a missing configuration file isn't written. Check [`Repository::needs_migration`]
+    /// before relying on the stored data.
+    pub fn for_dry_run() -> entities::Result<Self> {
+        Ok(Self {
+            db: Db::open()?,
+            cfg: Arc::new(RwLock::new(CoreConfig::read()?)),
+        })
+    }
+
+    /// Returns whether the stored data is behind [`Repository::target_model_version`]
+    pub fn needs_migration(&self) -> bool {
+        self.db.needs_migration()
+    }
+
+    /// Returns the model version of the stored data
+    pub fn model_version(&self) -> Option<u64> {
+        self.db.model_version()
+    }
+
+    /// Returns the model version this build of Barnacle expects the stored data to be at
+    pub fn target_model_version(&self) -> u64 {
+        CURRENT_MODEL_VERSION
+    }
+
+    /// Back up the database and run any pending migrations. Returns `false` if the database was
+    /// already up to date.
+    pub fn migrate(&self) -> entities::Result<bool> {
+        if !self.needs_migration() {
+            return Ok(false);
         }
+
+        self.db.migrate()?;
+
+        Ok(true)
+    }
+
+    /// Persist all pending database writes to disk.
+    ///
+    /// Every query or transaction is written to the database file as soon as it completes, with
+    /// a write-ahead log so a crash can't leave a change half applied. What a crash can lose is
+    /// anything the operating system hasn't written out to the disk yet, so this is called after
+    /// operations that make many changes at once, such as deploying or applying category rules.
+    pub fn flush(&self) -> entities::Result<()> {
+        Ok(self.db.flush()?)
     }
 
     pub fn add_game(&self, name: &str, deploy_kind: DeployKind) -> entities::Result<Game> {
         Game::add(&self.db.clone(), self.cfg.clone(), name, deploy_kind)
     }
 
+    /// Like [`Repository::add_game`], but also gives the game a profile to start with, named
+    /// after [`Repository::default_profile_name`]. Being the game's only profile, it's the
+    /// active one.
+    pub fn add_game_with_default_profile(
+        &self,
+        name: &str,
+        deploy_kind: DeployKind,
+    ) -> entities::Result<Game> {
+        let game = self.add_game(name, deploy_kind)?;
+        game.add_profile(&self.default_profile_name())?;
+
+        Ok(game)
+    }
+
+    /// Returns the name given to the profile [`Repository::add_game_with_default_profile`]
+    /// creates
+    pub fn default_profile_name(&self) -> String {
+        self.cfg.read().default_profile_name().to_string()
+    }
+
+    pub fn set_default_profile_name(&self, name: &str) {
+        let mut cfg = self.cfg.write();
+        cfg.set_default_profile_name(name.to_string());
+        cfg.save();
+    }
+
+    /// Returns whether mods added to a profile start out enabled
+    pub fn enable_new_mods_by_default(&self) -> bool {
+        self.cfg.read().enable_new_mods_by_default()
+    }
+
+    pub fn set_enable_new_mods_by_default(&self, enabled: bool) {
+        let mut cfg = self.cfg.write();
+        cfg.set_enable_new_mods_by_default(enabled);
+        cfg.save();
+    }
+
+    /// Returns whether the files of newly installed mods are made read-only. This keeps tools
+    /// run against the deployed game from changing the files behind Barnacle's back, through the
+    /// links, at the cost of having to mark a mod [editable](Mod::set_editable) to edit its files
+    /// in place. It doesn't change the permissions of mods that are already installed.
+    pub fn enforce_readonly_mods(&self) -> bool {
+        self.cfg.read().enforce_readonly_mods()
+    }
+
+    pub fn set_enforce_readonly_mods(&self, enforce: bool) {
+        let mut cfg = self.cfg.write();
+        cfg.set_enforce_readonly_mods(enforce);
+        cfg.save();
+    }
+
+    /// Returns every game in the library, in the order they were added
     pub fn games(&self) -> entities::Result<Vec<Game>> {
         Game::list(self.db.clone(), self.cfg.clone())
     }
 
+    /// Like [`Repository::games`], but loads each [`Game`] lazily so callers that stop early
+    /// don't pay for the rest
+    pub fn games_iter(&self) -> entities::Result<impl Iterator<Item = entities::Result<Game>>> {
+        Game::iter(self.db.clone(), self.cfg.clone())
+    }
+
+    /// Returns every tool in the library, across all games
+    pub fn tools(&self) -> entities::Result<Vec<Tool>> {
+        Tool::list(self.db.clone(), self.cfg.clone())
+    }
+
+    /// Rename `game`. See [`Game::set_name`].
+    pub fn rename_game(&self, game: &Game, new_name: &str) -> entities::Result<()> {
+        game.set_name(new_name)?;
+        info!("Renamed game to {new_name}");
+
+        Ok(())
+    }
+
+    /// Rename `profile`. See [`Profile::set_name`].
+    pub fn rename_profile(&self, profile: &Profile, new_name: &str) -> entities::Result<()> {
+        profile.set_name(new_name)?;
+        info!("Renamed profile to {new_name}");
+
+        Ok(())
+    }
+
+    /// Rename `mod_`. See [`Mod::set_name`].
+    pub fn rename_mod(&self, mod_: &Mod, new_name: &str) -> entities::Result<()> {
+        mod_.set_name(new_name)?;
+        info!("Renamed mod to {new_name}");
+
+        Ok(())
+    }
+
+    /// Find the game named `name`, ignoring case if no name matches exactly. Returns `None` if
+    /// there's no such game.
     pub fn search_game(&self, name: &str) -> entities::Result<Option<Game>> {
         Game::search(self.db.clone(), self.cfg.clone(), name)
     }
 
+    /// Returns the active game, if there is one. This only reads the database: the active game
+    /// and profile are kept set by the operations that change them, like adding, activating and
+    /// removing games and profiles.
     pub fn active_game(&self) -> entities::Result<Option<Game>> {
         Game::active(self.db.clone(), self.cfg.clone())
     }
 
+    /// Returns the active profile of the active game, if there is one. Unlike
+    /// [`Repository::ensure_active_context`], this never activates or adds a profile.
+    pub fn active_profile(&self) -> entities::Result<Option<Profile>> {
+        match self.active_game()? {
+            Some(game) => game.active_profile(),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the active game along with its active profile, or `None` if there's no active
+    /// game or its active profile was cleared, see [`Game::active_profile_cleared`]. A game
+    /// whose profile was never chosen gets one: its first profile is activated, or if it has
+    /// none, a profile named after [`Repository::default_profile_name`] is added.
+    pub fn ensure_active_context(&self) -> entities::Result<Option<(Game, Profile)>> {
+        let Some(game) = self.active_game()? else {
+            return Ok(None);
+        };
+
+        let profile = match game.active_profile()? {
+            Some(profile) => profile,
+            None if game.active_profile_cleared()? => return Ok(None),
+            None => match game.first_profile()? {
+                Some(profile) => {
+                    profile.activate()?;
+                    profile
+                }
+                // Being the game's only profile, it's activated as it's added
+                None => game.add_profile(&self.default_profile_name())?,
+            },
+        };
+
+        Ok(Some((game, profile)))
+    }
+
+    /// Clear the active profile of the active game, if there is one. See
+    /// [`Game::clear_active_profile`].
+    pub fn clear_active_profile(&self) -> entities::Result<()> {
+        match self.active_game()? {
+            Some(game) => game.clear_active_profile(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the category rules saved in the configuration
+    pub fn category_rules(&self) -> Vec<CategoryRule> {
+        self.cfg.read().category_rules().to_vec()
+    }
+
+    /// Replace the saved category rules
+    pub fn set_category_rules(&self, rules: Vec<CategoryRule>) {
+        let mut cfg = self.cfg.write();
+        cfg.set_category_rules(rules);
+        cfg.save();
+    }
+
+    /// Returns when category rules were last applied, as a Unix timestamp
+    pub fn category_rules_last_run(&self) -> Option<u64> {
+        self.cfg.read().category_rules_last_run()
+    }
+
+    /// Sort the mods of `game` into categories by name. Each mod gets the category of the first
+    /// rule whose pattern matches its name, and mods that don't match any rule are left as they
+    /// are. Returns how many mods each rule matched, in the same order as `rules`.
+    pub fn apply_category_rules(
+        &self,
+        game: &Game,
+        rules: &[(Regex, String)],
+    ) -> entities::Result<Vec<usize>> {
+        let mut matched = vec![0; rules.len()];
+        for mod_ in game.mods_iter()? {
+            let mod_ = mod_?;
+            let name = mod_.name()?;
+            if let Some((count, (_, category))) = matched
+                .iter_mut()
+                .zip(rules)
+                .find(|(_, (pattern, _))| pattern.is_match(&name))
+            {
+                mod_.set_category(category)?;
+                *count += 1;
+            }
+        }
+
+        for ((pattern, category), count) in rules.iter().zip(&matched) {
+            info!("Category rule {pattern} -> {category} matched {count} mods");
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut cfg = self.cfg.write();
+        cfg.set_category_rules_last_run(now);
+        cfg.save();
+
+        self.flush()?;
+
+        Ok(matched)
+    }
+
+    /// List every mod in the library, across all games
+    pub fn all_mods(&self) -> entities::Result<Vec<LibraryMod>> {
+        Mod::list(self.db.clone(), self.cfg.clone())?
+            .into_iter()
+            .map(|mod_| {
+                Ok(LibraryMod {
+                    game: mod_.parent()?,
+                    mod_,
+                })
+            })
+            .collect()
+    }
+
+    /// Find archives that have been installed under more than one game. Archives are matched by
+    /// file name and size. Each group lists every install of the same archive.
+    pub fn duplicate_installs(&self) -> entities::Result<Vec<Vec<LibraryMod>>> {
+        let mut installs: BTreeMap<(String, u64), Vec<LibraryMod>> = BTreeMap::new();
+        for library_mod in self.all_mods()? {
+            if let Some(source) = library_mod.mod_.source_archive()? {
+                installs.entry(source).or_default().push(library_mod);
+            }
+        }
+
+        Ok(installs
+            .into_values()
+            .filter(|group| match group.split_first() {
+                Some((first, rest)) => rest.iter().any(|m| m.game != first.game),
+                None => false,
+            })
+            .collect())
+    }
+
+    /// Find mods with identical content, based on their stored [`Mod::content_hash`], so
+    /// duplicates can be merged. Each group lists every mod sharing the same content. Mods
+    /// without any files all hash the same, but aren't copies of each other, so they're left out.
+    pub fn find_duplicate_mods(&self) -> entities::Result<Vec<Vec<Mod>>> {
+        let mut mods: BTreeMap<String, Vec<Mod>> = BTreeMap::new();
+        for mod_ in Mod::list(self.db.clone(), self.cfg.clone())? {
+            if let Some(hash) = mod_.content_hash()?
+                && !mod_.files()?.is_empty()
+            {
+                mods.entry(hash).or_default().push(mod_);
+            }
+        }
+
+        Ok(mods.into_values().filter(|group| group.len() > 1).collect())
+    }
+
+    /// Rebuild the file index of every mod in the library. This walks every mod directory, so
+    /// frontends should run it in the background.
+    pub fn reindex(&self) -> entities::Result<()> {
+        self.reindex_with_progress(&mut |_| {})
+    }
+
+    /// Like [`Repository::reindex`], reporting progress after each mod is indexed
+    pub fn reindex_with_progress(&self, on_progress: OnProgress) -> entities::Result<()> {
+        let mut mods = Vec::new();
+        for game in self.games()? {
+            mods.extend(game.mods()?);
+        }
+
+        let total = mods.len() as u64;
+        for (i, mod_) in mods.iter().enumerate() {
+            mod_.reindex()?;
+            on_progress(Progress::new(
+                Phase::Index,
+                i as u64 + 1,
+                total,
+                Some(mod_.dir()?),
+            ));
+        }
+
+        self.flush()
+    }
+
     #[cfg(test)]
     /// A mock version of a [`Repository`] with an in-memory database and configuration
     /// file, for using in tests.
@@ -64,8 +416,199 @@ impl Repository {
     }
 }
 
-impl Default for Repository {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, sync::Arc};
+
+    use parking_lot::RwLock;
+    use tempfile::tempdir;
+
+    use crate::{
+        Repository,
+        repository::{
+            DeployKind,
+            config::CoreConfig,
+            db::Db,
+            entities::{Error, UniqueConstraint, set_optional_field},
+        },
+        test_utils::write_zip,
+    };
+
+    #[test]
+    fn test_flush() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.db");
+        let cfg = Arc::new(RwLock::new(CoreConfig::mock()));
+
+        let repo = Repository {
+            db: Db::open_file(&path).unwrap(),
+            cfg: cfg.clone(),
+        };
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        game.add_profile("Default").unwrap();
+        let size = repo.db.file_size().unwrap();
+        repo.flush().unwrap();
+        // Flushing syncs the file rather than compacting it
+        assert_eq!(repo.db.file_size().unwrap(), size);
+        drop(repo);
+
+        let reopened = Repository {
+            db: Db::open_file(&path).unwrap(),
+            cfg,
+        };
+        let games = reopened.games().unwrap();
+        assert_eq!(games.len(), 1);
+        let game = games.first().unwrap();
+        assert_eq!(game.name().unwrap(), "Skyrim");
+        let profiles = game.profiles().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles.first().unwrap().name().unwrap(), "Default");
+    }
+
+    #[test]
+    fn test_rename() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let profile = game.add_profile("Default").unwrap();
+        game.add_profile("Vanilla").unwrap();
+        let mod_ = game.add_mod("Spoons", None).unwrap();
+        let tmp = tempdir().unwrap();
+        let archive = tmp.path().join("forks.zip");
+        write_zip(&archive, &[("meshes/fork.nif", "fork")]);
+        let archived = game.add_archived_mod("Forks", &archive).unwrap();
+
+        assert!(matches!(
+            repo.rename_game(&game, "oblivion"),
+            Err(Error::UniqueViolation(UniqueConstraint::GameName))
+        ));
+        repo.rename_game(&game, "Skyrim SE").unwrap();
+        assert_eq!(game.name().unwrap(), "Skyrim SE");
+        assert!(game.dir().unwrap().exists());
+
+        assert!(matches!(
+            repo.rename_profile(&profile, "Vanilla"),
+            Err(Error::UniqueViolation(UniqueConstraint::ProfileName))
+        ));
+        repo.rename_profile(&profile, "Modded").unwrap();
+        assert_eq!(profile.name().unwrap(), "Modded");
+        assert!(profile.dir().unwrap().exists());
+
+        assert!(matches!(
+            repo.rename_mod(&mod_, "Forks"),
+            Err(Error::UniqueViolation(UniqueConstraint::ModName))
+        ));
+        repo.rename_mod(&mod_, "Better Spoons").unwrap();
+        repo.rename_mod(&archived, "Better Forks").unwrap();
+        assert!(mod_.dir().unwrap().exists());
+        assert!(archived.archive_path().unwrap().exists());
+        assert_eq!(
+            archived.files().unwrap(),
+            vec![PathBuf::from("meshes/fork.nif")]
+        );
+    }
+
+    #[test]
+    fn test_add_game_with_default_profile() {
+        let repo = Repository::mock();
+
+        let skyrim = repo
+            .add_game_with_default_profile("Skyrim", DeployKind::CreationEngine)
+            .unwrap();
+        let profiles = skyrim.profiles().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(skyrim.active_profile().unwrap(), profiles.first().cloned());
+        assert_eq!(profiles.first().unwrap().name().unwrap(), "Default");
+
+        repo.set_default_profile_name("Vanilla");
+        let morrowind = repo
+            .add_game_with_default_profile("Morrowind", DeployKind::OpenMW)
+            .unwrap();
+        assert_eq!(
+            morrowind.active_profile().unwrap().unwrap().name().unwrap(),
+            "Vanilla"
+        );
+
+        let oblivion = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        assert_eq!(oblivion.profile_count().unwrap(), 0);
+
+        // Picking a profile for a new game doesn't switch away from the active one
+        assert_eq!(repo.active_game().unwrap(), Some(skyrim.clone()));
+        let profile = oblivion.add_profile("Test").unwrap();
+        assert!(profile.is_active().unwrap());
+        assert_eq!(repo.active_game().unwrap(), Some(skyrim));
+    }
+
+    #[test]
+    fn test_ensure_active_context() {
+        let repo = Repository::mock();
+        assert!(repo.ensure_active_context().unwrap().is_none());
+
+        // A game without profiles gets a default one
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        game.activate().unwrap();
+        let (active_game, profile) = repo.ensure_active_context().unwrap().unwrap();
+        assert_eq!(active_game, game);
+        assert_eq!(profile.name().unwrap(), "Default");
+        assert_eq!(game.active_profile().unwrap(), Some(profile.clone()));
+        assert_eq!(game.profile_count().unwrap(), 1);
+
+        // An existing active profile is returned as is
+        let vanilla = game.add_profile("Vanilla").unwrap();
+        vanilla.activate().unwrap();
+        let (active_game, profile) = repo.ensure_active_context().unwrap().unwrap();
+        assert_eq!(active_game, game);
+        assert_eq!(profile, vanilla);
+        assert_eq!(game.profile_count().unwrap(), 2);
+
+        // A deliberately cleared profile stays cleared
+        game.clear_active_profile().unwrap();
+        assert!(game.active_profile_cleared().unwrap());
+        assert!(repo.ensure_active_context().unwrap().is_none());
+        assert_eq!(game.active_profile().unwrap(), None);
+        assert_eq!(game.profile_count().unwrap(), 2);
+
+        vanilla.activate().unwrap();
+        assert!(!game.active_profile_cleared().unwrap());
+
+        // Without an active profile that was never chosen, like in a library from before
+        // clearing was recorded, the first one is activated rather than adding another
+        game.clear_active_profile().unwrap();
+        set_optional_field::<bool>(&repo.db, game.id, "profile_cleared", None).unwrap();
+        let (_, profile) = repo.ensure_active_context().unwrap().unwrap();
+        assert_eq!(profile, game.first_profile().unwrap().unwrap());
+        assert_eq!(game.active_profile().unwrap(), Some(profile));
+        assert_eq!(game.profile_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_active() {
+        let repo = Repository::mock();
+        assert_eq!(repo.active_game().unwrap(), None);
+        assert_eq!(repo.active_profile().unwrap(), None);
+
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let oblivion = repo.add_game("Oblivion", DeployKind::Gamebryo).unwrap();
+        let vanilla = skyrim.add_profile("Vanilla").unwrap();
+        let modded = skyrim.add_profile("Modded").unwrap();
+        let survival = oblivion.add_profile("Survival").unwrap();
+        skyrim.activate().unwrap();
+        assert_eq!(repo.active_game().unwrap(), Some(skyrim.clone()));
+        assert_eq!(repo.active_profile().unwrap(), Some(vanilla.clone()));
+
+        modded.activate().unwrap();
+        assert_eq!(repo.active_profile().unwrap(), Some(modded.clone()));
+        assert_eq!(skyrim.active_profile().unwrap(), Some(modded.clone()));
+
+        // Activating a profile of another game switches games too
+        survival.activate().unwrap();
+        assert_eq!(repo.active_game().unwrap(), Some(oblivion.clone()));
+        assert_eq!(repo.active_profile().unwrap(), Some(survival));
+        // Each game remembers its own active profile
+        assert_eq!(skyrim.active_profile().unwrap(), Some(modded));
+
+        oblivion.clear_active_profile().unwrap();
+        assert_eq!(repo.active_game().unwrap(), Some(oblivion));
+        assert_eq!(repo.active_profile().unwrap(), None);
     }
 }
@@ -0,0 +1,142 @@
+//! Finding and cleaning up mods that lost their game.
+//!
+//! A mod is linked from both the `mods` root and its game. If Barnacle is interrupted between
+//! removing a game and its mods, a mod can be left reachable from the root with no game, along with
+//! its files. Such a mod can't be used or removed the normal way since its directory is derived
+//! from its game.
+
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use tracing::info;
+
+use crate::{
+    fs::safe_dir_name,
+    repository::{
+        Repository,
+        entities::{self, Error, Mod},
+    },
+};
+
+impl Repository {
+    /// List the mods that are still in the library but no longer belong to a game
+    pub fn orphaned_mods(&self) -> entities::Result<Vec<Mod>> {
+        let mut orphans = Vec::new();
+        for mod_ in Mod::list(self.db.clone(), self.cfg.clone())? {
+            match mod_.parent() {
+                Ok(_) => {}
+                Err(Error::Orphaned) => orphans.push(mod_),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(orphans)
+    }
+
+    /// Remove every mod returned by [`Repository::orphaned_mods`], along with any files they left
+    /// behind. Since an orphan's game is unknown, its files are looked for under every game
+    /// directory of the library, skipping the ones a remaining mod still uses. Returns the names
+    /// of the removed mods.
+    pub fn remove_orphaned_mods(&self) -> entities::Result<Vec<String>> {
+        let orphans = self.orphaned_mods()?;
+        if orphans.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut in_use = BTreeSet::new();
+        for mod_ in Mod::list(self.db.clone(), self.cfg.clone())? {
+            if orphans.contains(&mod_) {
+                continue;
+            }
+            in_use.insert(mod_.dir()?);
+            in_use.insert(mod_.archive_path()?);
+            in_use.insert(mod_.unpacked_dir()?);
+        }
+
+        let library_dir = self.cfg.read().library_dir().to_path_buf();
+        let game_dirs: Vec<PathBuf> = match fs::read_dir(&library_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_dir())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let mut removed = Vec::new();
+        for orphan in orphans {
+            let name = orphan.name()?;
+            let dir_name = safe_dir_name(&name);
+
+            for game_dir in &game_dirs {
+                let dir = game_dir.join("mods").join(&dir_name);
+                let leftovers = [
+                    dir.with_extension("archive"),
+                    dir,
+                    game_dir.join("unpacked").join(&dir_name),
+                ];
+                for path in leftovers {
+                    if in_use.contains(&path) {
+                        continue;
+                    }
+
+                    if path.is_dir() {
+                        fs::remove_dir_all(&path)?;
+                    } else if path.is_file() {
+                        fs::remove_file(&path)?;
+                    }
+                }
+            }
+
+            orphan.remove_node()?;
+            info!("Removed orphaned mod: {name}");
+            removed.push(name);
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use agdb::{CountComparison, QueryBuilder};
+
+    use crate::{Repository, repository::DeployKind};
+
+    #[test]
+    fn test_remove_orphaned_mods() {
+        let repo = Repository::mock();
+        let skyrim = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let morrowind = repo.add_game("Morrowind", DeployKind::OpenMW).unwrap();
+
+        let kept = skyrim.add_mod("Shared", None).unwrap();
+        let orphan = morrowind.add_mod("Shared", None).unwrap();
+        let orphan_dir = orphan.dir().unwrap();
+        assert!(repo.orphaned_mods().unwrap().is_empty());
+
+        // Cut the orphan off from its game, as an interrupted game removal would
+        let orphan_id = orphan.id.db_id(&orphan.db).unwrap();
+        let game_id = morrowind.id.db_id(&morrowind.db).unwrap();
+        repo.db
+            .write()
+            .exec_mut(
+                QueryBuilder::remove()
+                    .search()
+                    .from(game_id)
+                    .to(orphan_id)
+                    .where_()
+                    .edge()
+                    .and()
+                    .distance(CountComparison::Equal(1))
+                    .query(),
+            )
+            .unwrap();
+
+        assert_eq!(repo.orphaned_mods().unwrap(), vec![orphan.clone()]);
+
+        assert_eq!(repo.remove_orphaned_mods().unwrap(), vec!["Shared"]);
+        assert!(repo.orphaned_mods().unwrap().is_empty());
+        assert!(!orphan_dir.exists());
+        // A mod of the same name in another game is left alone
+        assert!(kept.dir().unwrap().exists());
+        assert_eq!(skyrim.mods().unwrap(), vec![kept]);
+    }
+}
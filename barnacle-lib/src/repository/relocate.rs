@@ -0,0 +1,241 @@
+//! Moving the library and everything it references to a new location.
+//!
+//! Entity directories are derived from the library directory, so they follow it for free. Only
+//! the absolute paths stored alongside entities and in deployment manifests have to be rewritten,
+//! and the links of deployed profiles made again so they lead to the new location.
+
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+use crate::{
+    deploy::{MANIFEST_FILE, Manifest},
+    repository::{
+        Repository,
+        entities::{self, Error, Tool},
+    },
+};
+
+/// A stored path that is rewritten by [`Repository::relocate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// Human friendly description of what the path belongs to
+    pub subject: String,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl Repository {
+    /// List every stored path that [`Repository::relocate`] would rewrite, without changing
+    /// anything.
+    pub fn relocation_plan(
+        &self,
+        old_base: &Path,
+        new_base: &Path,
+    ) -> entities::Result<Vec<Relocation>> {
+        self.relocate_paths(old_base, new_base, true)
+    }
+
+    /// Rewrite every stored absolute path under `old_base` to point under `new_base` instead,
+    /// for after the library has been moved. The library directory must already exist at its new
+    /// location. Deployed profiles whose files moved are redeployed, so their links lead to the
+    /// new location. Overlays can't be remounted without privileges, so those have to be deployed
+    /// again by hand. Returns the paths that were rewritten.
+    pub fn relocate(&self, old_base: &Path, new_base: &Path) -> entities::Result<Vec<Relocation>> {
+        self.relocate_paths(old_base, new_base, false)
+    }
+
+    fn relocate_paths(
+        &self,
+        old_base: &Path,
+        new_base: &Path,
+        dry_run: bool,
+    ) -> entities::Result<Vec<Relocation>> {
+        let mut relocations = Vec::new();
+        let mut stale_deployments = Vec::new();
+
+        let library_dir = self.cfg.read().library_path().to_path_buf();
+        let new_library_dir = rebase(&library_dir, old_base, new_base);
+        let target_library_dir = new_library_dir.as_ref().unwrap_or(&library_dir);
+        if !target_library_dir.is_dir() {
+            return Err(Error::MissingLibrary(target_library_dir.clone()));
+        }
+
+        if let Some(to) = new_library_dir {
+            relocations.push(Relocation {
+                subject: "Library directory".to_string(),
+                from: library_dir,
+                to: to.clone(),
+            });
+
+            if !dry_run {
+                let mut cfg = self.cfg.write();
+                cfg.set_library_dir(to);
+                cfg.save();
+            }
+        }
+
+        for game in self.games()? {
+            let name = game.name()?;
+            let targets = game.targets()?;
+
+            let mut changed = false;
+            let new_targets: Vec<PathBuf> = targets
+                .into_iter()
+                .map(|target| match rebase(&target, old_base, new_base) {
+                    Some(to) => {
+                        changed = true;
+                        relocations.push(Relocation {
+                            subject: format!("Target of {name}"),
+                            from: target,
+                            to: to.clone(),
+                        });
+                        to
+                    }
+                    None => target,
+                })
+                .collect();
+
+            if changed && !dry_run {
+                game.set_targets(&new_targets)?;
+            }
+
+            let manifest_path = game.dir_in(target_library_dir)?.join(MANIFEST_FILE);
+            if let Some(mut manifest) = Manifest::load(&manifest_path)? {
+                let mut changed = false;
+                let paths = manifest
+                    .targets
+                    .iter_mut()
+                    .chain(manifest.files.values_mut().map(|f| &mut f.source))
+                    .chain(
+                        manifest
+                            .vanilla
+                            .iter_mut()
+                            .flat_map(|v| [&mut v.original, &mut v.backup]),
+                    );
+                for path in paths {
+                    if let Some(to) = rebase(path, old_base, new_base) {
+                        changed = true;
+                        relocations.push(Relocation {
+                            subject: format!("Deployment of {name}"),
+                            from: path.clone(),
+                            to: to.clone(),
+                        });
+                        *path = to;
+                    }
+                }
+
+                if changed && !dry_run {
+                    manifest.save(&manifest_path)?;
+
+                    // The links themselves still lead into the old location
+                    match game.profile_by_uid(manifest.profile)? {
+                        Some(profile) if !manifest.overlay => stale_deployments.push(profile),
+                        _ => {
+                            warn!("Deployment of {name} has to be redeployed to use the new paths")
+                        }
+                    }
+                }
+            }
+        }
+
+        for tool in Tool::list(self.db.clone(), self.cfg.clone())? {
+            let path = tool.path()?;
+            if let Some(to) = rebase(&path, old_base, new_base) {
+                relocations.push(Relocation {
+                    subject: format!("Tool {}", tool.name()?),
+                    from: path,
+                    to: to.clone(),
+                });
+
+                if !dry_run {
+                    tool.set_path(to)?;
+                }
+            }
+        }
+
+        for profile in stale_deployments {
+            profile.redeploy()?;
+        }
+
+        if !dry_run {
+            info!(
+                "Relocated {} paths from {} to {}",
+                relocations.len(),
+                old_base.display(),
+                new_base.display()
+            );
+        }
+
+        Ok(relocations)
+    }
+}
+
+/// Returns `path` with its `old_base` prefix swapped for `new_base`, or `None` if it isn't under
+/// `old_base`
+fn rebase(path: &Path, old_base: &Path, new_base: &Path) -> Option<PathBuf> {
+    path.strip_prefix(old_base)
+        .ok()
+        .map(|relative| new_base.join(relative))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use tempfile::tempdir;
+
+    use crate::{Repository, repository::DeployKind, test_utils::add_entry_with_files};
+
+    #[test]
+    fn test_relocate() {
+        let repo = Repository::mock();
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+
+        let old = tempdir().unwrap();
+        let new = tempdir().unwrap();
+        let other = PathBuf::from("/opt/skyrim");
+        game.set_targets(&[old.path().join("skyrim"), other.clone()])
+            .unwrap();
+
+        let plan = repo.relocation_plan(old.path(), new.path()).unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(
+            game.targets().unwrap(),
+            vec![old.path().join("skyrim"), other.clone()]
+        );
+
+        let relocated = repo.relocate(old.path(), new.path()).unwrap();
+        assert_eq!(relocated, plan);
+        assert_eq!(
+            game.targets().unwrap(),
+            vec![new.path().join("skyrim"), other]
+        );
+    }
+
+    #[test]
+    fn test_relocate_deployed() {
+        let repo = Repository::mock();
+        let old = tempdir().unwrap();
+        let new = tempdir().unwrap();
+        repo.cfg.write().set_library_dir(old.path().join("library"));
+
+        let game = repo.add_game("Skyrim", DeployKind::CreationEngine).unwrap();
+        let profile = game.add_profile("Test").unwrap();
+        let target = old.path().join("skyrim");
+        fs::create_dir_all(&target).unwrap();
+        game.set_targets(&[target]).unwrap();
+        add_entry_with_files(&profile, "Mod", &[("mod.esp", "Mod")]);
+        profile.deploy().unwrap();
+
+        for dir in ["library", "skyrim"] {
+            fs::rename(old.path().join(dir), new.path().join(dir)).unwrap();
+        }
+        repo.relocate(old.path(), new.path()).unwrap();
+
+        let link = new.path().join("skyrim/mod.esp");
+        assert!(fs::read_link(&link).unwrap().starts_with(new.path()));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "Mod");
+        assert!(game.is_deployed().unwrap());
+    }
+}
@@ -0,0 +1,253 @@
+//! Detecting games installed in the usual places so they don't have to be added by hand.
+//!
+//! Steam and GOG libraries, along with a few common install directories, are searched for the
+//! executables listed in [`KNOWN_GAMES`]. Supporting another game only takes another entry there.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::repository::{DeployKind, Game, Repository, entities};
+
+/// A game that can be recognized by [`Repository::scan_for_games`]
+struct KnownGame {
+    name: &'static str,
+    /// Path of the executable, relative to the install directory
+    executable: &'static str,
+    deploy_kind: DeployKind,
+}
+
+const KNOWN_GAMES: &[KnownGame] = &[
+    KnownGame {
+        name: "Skyrim Special Edition",
+        executable: "SkyrimSE.exe",
+        deploy_kind: DeployKind::CreationEngine,
+    },
+    KnownGame {
+        name: "Skyrim",
+        executable: "TESV.exe",
+        deploy_kind: DeployKind::CreationEngine,
+    },
+    KnownGame {
+        name: "Fallout 4",
+        executable: "Fallout4.exe",
+        deploy_kind: DeployKind::CreationEngine,
+    },
+    KnownGame {
+        name: "Fallout: New Vegas",
+        executable: "FalloutNV.exe",
+        deploy_kind: DeployKind::Gamebryo,
+    },
+    KnownGame {
+        name: "Fallout 3",
+        executable: "Fallout3.exe",
+        deploy_kind: DeployKind::Gamebryo,
+    },
+    KnownGame {
+        name: "Oblivion",
+        executable: "Oblivion.exe",
+        deploy_kind: DeployKind::Gamebryo,
+    },
+    KnownGame {
+        name: "Morrowind",
+        executable: "Morrowind.exe",
+        deploy_kind: DeployKind::OpenMW,
+    },
+    KnownGame {
+        name: "Baldur's Gate 3",
+        executable: "bin/bg3.exe",
+        deploy_kind: DeployKind::BaldursGate3,
+    },
+];
+
+/// An installed game found by [`Repository::scan_for_games`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedGame {
+    pub name: String,
+    /// The install directory
+    pub path: PathBuf,
+    pub deploy_kind: DeployKind,
+    /// The directory mod files should be deployed into
+    pub target: PathBuf,
+}
+
+impl Repository {
+    /// Search the Steam and GOG libraries and common install directories for known games.
+    /// Games already in the library, going by their targets, aren't returned.
+    pub fn scan_for_games(&self) -> entities::Result<Vec<DetectedGame>> {
+        self.scan_for_games_in(&search_roots())
+    }
+
+    fn scan_for_games_in(&self, roots: &[PathBuf]) -> entities::Result<Vec<DetectedGame>> {
+        let mut known_targets = Vec::new();
+        for game in self.games()? {
+            known_targets.extend(game.targets()?.iter().map(|t| canonical(t)));
+        }
+
+        let mut detected: Vec<DetectedGame> = Vec::new();
+        for dir in roots.iter().flat_map(|root| subdirs(root)) {
+            for known in KNOWN_GAMES {
+                if !dir.join(known.executable).is_file() {
+                    continue;
+                }
+
+                let target = dir.join(known.deploy_kind.data_dir());
+                let canonical_target = canonical(&target);
+                if known_targets.contains(&canonical_target)
+                    || detected
+                        .iter()
+                        .any(|d| canonical(&d.target) == canonical_target)
+                {
+                    continue;
+                }
+
+                detected.push(DetectedGame {
+                    name: known.name.to_string(),
+                    path: dir.clone(),
+                    deploy_kind: known.deploy_kind,
+                    target,
+                });
+            }
+        }
+
+        Ok(detected)
+    }
+
+    /// Add a game returned by [`Repository::scan_for_games`], targeting its data directory
+    pub fn add_detected_game(&self, detected: &DetectedGame) -> entities::Result<Game> {
+        let game = self.add_game(&detected.name, detected.deploy_kind)?;
+        game.set_targets(std::slice::from_ref(&detected.target))?;
+
+        Ok(game)
+    }
+}
+
+/// Returns the directories games are installed under: every Steam library, plus the default
+/// GOG, Heroic and Lutris install directories
+fn search_roots() -> Vec<PathBuf> {
+    let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+
+    let steam_dirs = [
+        home.join(".local/share/Steam"),
+        home.join(".steam/steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ];
+
+    let mut roots = Vec::new();
+    for steam_dir in steam_dirs {
+        roots.push(steam_dir.join("steamapps/common"));
+        let library_folders = steam_dir.join("steamapps/libraryfolders.vdf");
+        if let Ok(contents) = fs::read_to_string(library_folders) {
+            roots.extend(steam_library_paths(&contents).map(|p| p.join("steamapps/common")));
+        }
+    }
+
+    roots.extend([
+        home.join("GOG Games"),
+        home.join("Games"),
+        home.join("Games/Heroic"),
+    ]);
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        if root.is_dir() && !unique.iter().any(|r| canonical(r) == canonical(&root)) {
+            unique.push(root);
+        }
+    }
+
+    unique
+}
+
+/// Returns the library paths listed in the contents of Steam's `libraryfolders.vdf`
+fn steam_library_paths(contents: &str) -> impl Iterator<Item = PathBuf> + use<'_> {
+    contents.lines().filter_map(|line| {
+        let value = line.trim().strip_prefix("\"path\"")?.trim();
+        let value = value.strip_prefix('"')?.strip_suffix('"')?;
+        Some(PathBuf::from(value.replace("\\\\", "\\")))
+    })
+}
+
+fn subdirs(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use tempfile::tempdir;
+
+    use super::steam_library_paths;
+    use crate::{Repository, repository::DeployKind};
+
+    #[test]
+    fn test_steam_library_paths() {
+        let contents = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"label"		""
+	}
+	"1"
+	{
+		"path"		"/mnt/games/SteamLibrary"
+	}
+}
+"#;
+
+        assert_eq!(
+            steam_library_paths(contents).collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("/home/user/.local/share/Steam"),
+                PathBuf::from("/mnt/games/SteamLibrary")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_for_games() {
+        let repo = Repository::mock();
+        let root = tempdir().unwrap();
+
+        let skyrim = root.path().join("Skyrim Special Edition");
+        let morrowind = root.path().join("Morrowind");
+        fs::create_dir_all(skyrim.join("Data")).unwrap();
+        fs::write(skyrim.join("SkyrimSE.exe"), "").unwrap();
+        fs::create_dir_all(&morrowind).unwrap();
+        fs::write(morrowind.join("Morrowind.exe"), "").unwrap();
+        fs::create_dir_all(root.path().join("Unknown")).unwrap();
+
+        let roots = [root.path().to_path_buf()];
+        let mut detected = repo.scan_for_games_in(&roots).unwrap();
+        detected.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(detected.len(), 2);
+        let [morrowind_game, skyrim_game] = detected.try_into().unwrap();
+        assert_eq!(morrowind_game.deploy_kind, DeployKind::OpenMW);
+        assert_eq!(morrowind_game.target, morrowind.join("Data Files"));
+        assert_eq!(skyrim_game.name, "Skyrim Special Edition");
+        assert_eq!(skyrim_game.path, skyrim);
+
+        let game = repo.add_detected_game(&skyrim_game).unwrap();
+        assert_eq!(game.targets().unwrap(), vec![skyrim.join("Data")]);
+
+        // Already added games aren't suggested again
+        let detected = repo.scan_for_games_in(&roots).unwrap();
+        assert_eq!(detected, vec![morrowind_game]);
+    }
+}
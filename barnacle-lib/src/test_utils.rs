@@ -0,0 +1,130 @@
+//! Helpers shared between tests.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use walkdir::WalkDir;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::repository::{Game, Mod, ModEntry, Profile};
+
+/// Take a snapshot of the directory tree under `dir`.
+///
+/// Each entry is the path relative to `dir`. Directories have a trailing `/`, and symlinks are
+/// rendered as `link -> target` without being followed.
+pub(crate) fn snapshot(dir: &Path) -> BTreeSet<String> {
+    WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .map(|entry| {
+            let entry = entry.expect("directory tree should be readable");
+            let relative = entry
+                .path()
+                .strip_prefix(dir)
+                .expect("entry should be inside the snapshotted directory")
+                .to_string_lossy()
+                .into_owned();
+
+            if entry.path_is_symlink() {
+                let target = fs::read_link(entry.path()).expect("symlink should be readable");
+                format!("{relative} -> {}", target.display())
+            } else if entry.file_type().is_dir() {
+                format!("{relative}/")
+            } else {
+                relative
+            }
+        })
+        .collect()
+}
+
+/// Assert that the directory tree under `dir` contains exactly the `expected` entries, in the
+/// format produced by [`snapshot`].
+#[track_caller]
+pub(crate) fn assert_tree(dir: &Path, expected: &[&str]) {
+    let expected: BTreeSet<String> = expected.iter().map(|e| e.to_string()).collect();
+
+    assert_eq!(
+        snapshot(dir),
+        expected,
+        "unexpected tree at {}",
+        dir.display()
+    );
+}
+
+/// Add a mod named `name` to `game` with the given `(path, contents)` files, indexed so its
+/// files are known
+pub(crate) fn add_mod_with_files(game: &Game, name: &str, files: &[(&str, &str)]) -> Mod {
+    let mod_ = game.add_mod(name, None).unwrap();
+    let dir = mod_.dir().unwrap();
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    mod_.reindex().unwrap();
+
+    mod_
+}
+
+/// Like [`add_mod_with_files`], but also adds an entry for the mod to `profile`
+pub(crate) fn add_entry_with_files(
+    profile: &Profile,
+    name: &str,
+    files: &[(&str, &str)],
+) -> ModEntry {
+    let mod_ = add_mod_with_files(&profile.parent().unwrap(), name, files);
+    profile.add_mod_entry(mod_).unwrap()
+}
+
+/// Write a zip archive containing the given `(path, contents)` files to `path`
+pub(crate) fn write_zip(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in files {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Write an uncompressed ustar archive containing the given `(path, contents)` files to `path`
+pub(crate) fn write_tar(path: &Path, files: &[(&str, &str)]) {
+    // Each header field is NUL padded to its width
+    let field = |value: &[u8], width: usize| {
+        let mut field = value.to_vec();
+        field.resize(width, 0);
+        field
+    };
+
+    let mut tar = Vec::new();
+    for (name, contents) in files {
+        let mut header = [
+            field(name.as_bytes(), 100),
+            field(b"0000644", 8),
+            field(b"0000000", 8),
+            field(b"0000000", 8),
+            field(format!("{:011o}", contents.len()).as_bytes(), 12),
+            field(b"00000000000", 12),
+            // The checksum is computed with its own field set to spaces
+            b"        ".to_vec(),
+            b"0".to_vec(),
+            field(b"", 100),
+            b"ustar\x0000".to_vec(),
+        ]
+        .concat();
+        header.resize(512, 0);
+        let checksum: u32 = header.iter().copied().map(u32::from).sum();
+        header.splice(148..156, format!("{checksum:06o}\0 ").bytes());
+
+        tar.extend(header);
+        tar.extend(contents.as_bytes());
+        tar.resize(tar.len().next_multiple_of(512), 0);
+    }
+    // Two empty blocks mark the end of the archive
+    tar.resize(tar.len() + 1024, 0);
+
+    fs::write(path, tar).unwrap();
+}